@@ -0,0 +1,255 @@
+-- 从初始版本的表结构升级, 新部署直接使用 mysql.sql
+USE cmm;
+
+-- 已有表新增的字段
+ALTER TABLE `app`
+    ADD COLUMN `quota_item_count` int unsigned NOT NULL DEFAULT 0 COMMENT 'namespace 最大 item 数量 0:使用全局设置' AFTER `creator_user`,
+    ADD COLUMN `quota_value_size` int unsigned NOT NULL DEFAULT 0 COMMENT '单个值的最大字节数 0:使用全局设置' AFTER `quota_item_count`,
+    ADD COLUMN `quota_release_size` bigint unsigned NOT NULL DEFAULT 0 COMMENT 'namespace 发布配置的最大字节数 0:使用全局设置' AFTER `quota_value_size`;
+
+ALTER TABLE `user_favorite`
+    ADD COLUMN `sort` int unsigned NOT NULL DEFAULT 0 COMMENT '排序 越小越靠前' AFTER `app_id`;
+
+-- 已有集群归入 dev 环境, 升级后按实际情况调整
+ALTER TABLE `cluster`
+    ADD COLUMN `env` varchar(80) NOT NULL DEFAULT 'dev' COMMENT '所属环境' AFTER `name`,
+    ADD COLUMN `approval` tinyint unsigned NOT NULL DEFAULT 0 COMMENT '发布审批 0:继承环境 1:开启 2:关闭' AFTER `secret`,
+    ADD KEY `ix_env` (`env`);
+
+ALTER TABLE `namespace`
+    ADD COLUMN `key_pattern` varchar(255) NOT NULL DEFAULT '' COMMENT 'key 的正则规则 为空不限制' AFTER `scope`,
+    ADD COLUMN `key_reserved_prefix` varchar(1024) NOT NULL DEFAULT '' COMMENT '保留的 key 前缀 逗号分隔' AFTER `key_pattern`,
+    ADD COLUMN `key_max_depth` tinyint unsigned NOT NULL DEFAULT 0 COMMENT 'key 的最大层级 0:不限制' AFTER `key_reserved_prefix`,
+    ADD COLUMN `edit_lock` tinyint unsigned NOT NULL DEFAULT 0 COMMENT '编辑锁 0:关闭 1:开启' AFTER `key_max_depth`,
+    ADD COLUMN `lock_user_id` int unsigned NOT NULL DEFAULT 0 COMMENT '持有编辑锁的用户ID' AFTER `edit_lock`,
+    ADD COLUMN `lock_expired_at` bigint unsigned NOT NULL DEFAULT 0 COMMENT '编辑锁过期时间 second' AFTER `lock_user_id`,
+    ADD COLUMN `approval` tinyint unsigned NOT NULL DEFAULT 0 COMMENT '发布审批 0:继承集群 1:开启 2:关闭' AFTER `lock_expired_at`,
+    ADD COLUMN `retain_count` int unsigned NOT NULL DEFAULT 0 COMMENT '保留的发布数量 0:使用全局设置' AFTER `approval`,
+    ADD COLUMN `retain_days` int unsigned NOT NULL DEFAULT 0 COMMENT '保留的发布天数 0:使用全局设置' AFTER `retain_count`,
+    ADD COLUMN `quota_item_count` int unsigned NOT NULL DEFAULT 0 COMMENT '最大 item 数量 0:使用应用设置' AFTER `retain_days`,
+    ADD COLUMN `quota_value_size` int unsigned NOT NULL DEFAULT 0 COMMENT '单个值的最大字节数 0:使用应用设置' AFTER `quota_item_count`,
+    ADD COLUMN `quota_release_size` bigint unsigned NOT NULL DEFAULT 0 COMMENT '发布配置的最大字节数 0:使用应用设置' AFTER `quota_value_size`,
+    ADD COLUMN `polled_at` bigint unsigned NOT NULL DEFAULT 0 COMMENT '客户端最近拉取时间 second' AFTER `quota_release_size`,
+    ADD COLUMN `delete_batch` bigint unsigned NOT NULL DEFAULT 0 COMMENT '删除批次 随其删除的关联数据使用相同的批次' AFTER `deleted_at`;
+
+ALTER TABLE `app_extend`
+    ADD COLUMN `delete_batch` bigint unsigned NOT NULL DEFAULT 0 COMMENT '随 namespace 删除的批次 0:未随 namespace 删除' AFTER `deleted_at`;
+
+ALTER TABLE `item`
+    ADD COLUMN `delete_batch` bigint unsigned NOT NULL DEFAULT 0 COMMENT '随 namespace 删除的批次 0:未随 namespace 删除' AFTER `deleted_at`;
+
+-- 已有发布的校验和为空, 读取时按配置计算
+ALTER TABLE `release`
+    ADD COLUMN `checksum` varchar(64) NOT NULL DEFAULT '' COMMENT '发布配置的 sha256 校验和' AFTER `configurations`,
+    ADD COLUMN `delete_batch` bigint unsigned NOT NULL DEFAULT 0 COMMENT '随 namespace 删除的批次 0:未随 namespace 删除' AFTER `deleted_at`;
+
+ALTER TABLE `release_history`
+    ADD COLUMN `operation` tinyint unsigned NOT NULL DEFAULT 0 COMMENT '操作 0:发布 1:回滚' AFTER `release_id`,
+    ADD COLUMN `delete_batch` bigint unsigned NOT NULL DEFAULT 0 COMMENT '随 namespace 删除的批次 0:未随 namespace 删除' AFTER `deleted_at`;
+
+-- 新增的表
+CREATE TABLE IF NOT EXISTS `app_member` (
+    `id` bigint unsigned AUTO_INCREMENT COMMENT '主键',
+    `app_id` varchar(80) NOT NULL COMMENT 'appID',
+    `user_id` int unsigned NOT NULL COMMENT '用户ID',
+    `role` tinyint unsigned NOT NULL DEFAULT 1 COMMENT '成员角色 0:owner 1:maintainer',
+    `creator_user` int unsigned NOT NULL DEFAULT 0 COMMENT '添加用户ID',
+    `deleted_at` bigint unsigned NOT NULL DEFAULT 0 COMMENT '删除时间 second',
+    `created_at` timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP COMMENT '创建时间',
+    `updated_at` timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP COMMENT '更新时间',
+    primary key (`id`),
+    unique key `uk_app_user` (`app_id`, `user_id`, `deleted_at`),
+    key `ix_user_id` (`user_id`)
+) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4 COMMENT = '应用成员';
+
+CREATE TABLE IF NOT EXISTS `user_recent` (
+    `id` bigint unsigned AUTO_INCREMENT COMMENT '主键',
+    `user_id` int unsigned NOT NULL COMMENT '用户ID',
+    `app_id` int unsigned NOT NULL COMMENT '应用ID',
+    `visited_at` bigint unsigned NOT NULL DEFAULT 0 COMMENT '最后访问时间 second',
+    `created_at` timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP COMMENT '创建时间',
+    `updated_at` timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP COMMENT '更新时间',
+    primary key (`id`),
+    unique key `uk_user_app` (`user_id`, `app_id`),
+    key `ix_app_id` (`app_id`)
+) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4 COMMENT = '最近访问应用';
+
+CREATE TABLE IF NOT EXISTS `environment` (
+    `id` int unsigned AUTO_INCREMENT COMMENT '主键',
+    `name` varchar(80) NOT NULL COMMENT '环境名称',
+    `description` varchar(255) NOT NULL DEFAULT '' COMMENT '环境描述',
+    `approval` tinyint unsigned NOT NULL DEFAULT 2 COMMENT '发布审批 1:开启 2:关闭',
+    `deleted_at` bigint unsigned NOT NULL DEFAULT 0 COMMENT '删除时间 second',
+    `created_at` timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP COMMENT '创建时间',
+    `updated_at` timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP COMMENT '更新时间',
+    primary key (`id`),
+    unique key `uk_name` (`name`, `deleted_at`)
+) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4 COMMENT = '环境';
+
+INSERT IGNORE INTO `environment` (`name`, `description`, `approval`) VALUES
+    ('dev', '开发环境', 2),
+    ('test', '测试环境', 2),
+    ('staging', '预发布环境', 2),
+    ('prod', '生产环境', 1);
+
+CREATE TABLE IF NOT EXISTS `namespace_template` (
+    `id` bigint unsigned AUTO_INCREMENT COMMENT '主键',
+    `app_id` varchar(80) NOT NULL COMMENT '所属应用ID',
+    `name` varchar(80) NOT NULL COMMENT '模板名称',
+    `description` varchar(255) NOT NULL DEFAULT '' COMMENT '模板描述',
+    `scope` tinyint unsigned NOT NULL DEFAULT 0 COMMENT '范围',
+    `key_pattern` varchar(255) NOT NULL DEFAULT '' COMMENT 'key 的正则规则 为空不限制',
+    `key_reserved_prefix` varchar(1024) NOT NULL DEFAULT '' COMMENT '保留的 key 前缀 逗号分隔',
+    `key_max_depth` tinyint unsigned NOT NULL DEFAULT 0 COMMENT 'key 的最大层级 0:不限制',
+    `items` longtext NOT NULL COMMENT 'key 及默认值 json',
+    `creator_user` int unsigned NOT NULL COMMENT '创建的用户ID',
+    `deleted_at` bigint unsigned NOT NULL DEFAULT 0 COMMENT '删除时间 second',
+    `created_at` timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP COMMENT '创建时间',
+    `updated_at` timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP COMMENT '更新时间',
+    primary key (`id`),
+    unique key `uk_app_name` (`app_id`, `name`, `deleted_at`)
+) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4 COMMENT = 'namespace 模板';
+
+CREATE TABLE IF NOT EXISTS `item_revision` (
+    `id` bigint unsigned AUTO_INCREMENT COMMENT '主键',
+    `namespace_id` bigint unsigned NOT NULL COMMENT '关联的 namespace_id',
+    `item_id` bigint unsigned NOT NULL COMMENT '关联的 item_id',
+    `key` varchar(255) NOT NULL COMMENT '配置key',
+    `old_value` text NOT NULL COMMENT '修改前的value',
+    `new_value` text NOT NULL COMMENT '修改后的value',
+    `category` varchar(20) NOT NULL COMMENT 'value 类型',
+    `operation` tinyint unsigned NOT NULL DEFAULT 0 COMMENT '操作类型 0:创建 1:修改 2:删除 3:还原',
+    `version` bigint unsigned NOT NULL DEFAULT 0 COMMENT '修改后的版本',
+    `user_id` int unsigned NOT NULL DEFAULT 0 COMMENT '修改用户',
+    `deleted_at` bigint unsigned NOT NULL DEFAULT 0 COMMENT '删除时间 second',
+    `created_at` timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP COMMENT '创建时间',
+    `updated_at` timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP COMMENT '更新时间',
+    PRIMARY KEY (`id`),
+    KEY `ix_item` (`item_id`, `deleted_at`),
+    KEY `ix_namespace` (`namespace_id`, `deleted_at`)
+) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4 COMMENT = '配置修改记录';
+
+CREATE TABLE IF NOT EXISTS `publish_request` (
+    `id` bigint unsigned NOT NULL AUTO_INCREMENT COMMENT '自增主键',
+    `namespace_id` bigint unsigned NOT NULL COMMENT '命名空间ID',
+    `name` varchar(64) NOT NULL DEFAULT '' COMMENT '发布名称',
+    `remark` varchar(255) NOT NULL DEFAULT '' COMMENT '发布说明',
+    `items` longtext NOT NULL COMMENT '待发布的 item 及版本',
+    `change` longtext NOT NULL COMMENT '与线上配置的差异',
+    `base_release_id` bigint unsigned NOT NULL DEFAULT 0 COMMENT '申请时的线上发布ID',
+    `rollback_release_id` bigint unsigned NOT NULL DEFAULT 0 COMMENT '回滚申请的目标发布ID 0:发布申请',
+    `status` tinyint unsigned NOT NULL DEFAULT 0 COMMENT '状态 0:待审批 1:通过 2:拒绝 3:取消',
+    `request_user_id` int unsigned NOT NULL COMMENT '申请人',
+    `review_user_id` int unsigned NOT NULL DEFAULT 0 COMMENT '审批人',
+    `review_remark` varchar(255) NOT NULL DEFAULT '' COMMENT '审批意见',
+    `release_id` bigint unsigned NOT NULL DEFAULT 0 COMMENT '审批通过后的发布ID',
+    `deleted_at` bigint unsigned NOT NULL DEFAULT 0 COMMENT '删除时间 second',
+    `delete_batch` bigint unsigned NOT NULL DEFAULT 0 COMMENT '随 namespace 删除的批次 0:未随 namespace 删除',
+    `created_at` timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP COMMENT '创建时间',
+    `updated_at` timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP COMMENT '更新时间',
+    PRIMARY KEY (`id`),
+    KEY `ix_namespace_status` (`namespace_id`, `status`, `deleted_at`)
+) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4 COMMENT = '发布审批';
+
+CREATE TABLE IF NOT EXISTS `publish_schedule` (
+    `id` bigint unsigned NOT NULL AUTO_INCREMENT COMMENT '自增主键',
+    `namespace_id` bigint unsigned NOT NULL COMMENT '命名空间ID',
+    `name` varchar(64) NOT NULL DEFAULT '' COMMENT '发布名称',
+    `remark` varchar(255) NOT NULL DEFAULT '' COMMENT '发布说明',
+    `items` longtext NOT NULL COMMENT '待发布的 item 及版本',
+    `base_release_id` bigint unsigned NOT NULL DEFAULT 0 COMMENT '计划基于的发布ID',
+    `publish_at` bigint unsigned NOT NULL COMMENT '计划发布时间 second',
+    `status` tinyint unsigned NOT NULL DEFAULT 0 COMMENT '状态 0:等待 1:执行中 2:已发布 3:失败 4:取消',
+    `claimed_at` bigint unsigned NOT NULL DEFAULT 0 COMMENT '开始执行时间 second',
+    `reason` varchar(255) NOT NULL DEFAULT '' COMMENT '失败原因',
+    `release_id` bigint unsigned NOT NULL DEFAULT 0 COMMENT '发布ID',
+    `user_id` int unsigned NOT NULL COMMENT '创建人',
+    `deleted_at` bigint unsigned NOT NULL DEFAULT 0 COMMENT '删除时间 second',
+    `created_at` timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP COMMENT '创建时间',
+    `updated_at` timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP COMMENT '更新时间',
+    PRIMARY KEY (`id`),
+    KEY `ix_status_publish_at` (`status`, `publish_at`),
+    KEY `ix_namespace_id` (`namespace_id`, `deleted_at`)
+) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4 COMMENT = '定时发布';
+
+CREATE TABLE IF NOT EXISTS `webhook` (
+    `id` bigint unsigned NOT NULL AUTO_INCREMENT COMMENT '自增主键',
+    `app_id` varchar(80) NOT NULL COMMENT 'appID',
+    `namespace_id` bigint unsigned NOT NULL DEFAULT 0 COMMENT '命名空间ID 0:app 下所有 namespace',
+    `name` varchar(64) NOT NULL DEFAULT '' COMMENT '名称',
+    `url` varchar(1024) NOT NULL COMMENT '回调地址',
+    `secret` varchar(128) NOT NULL DEFAULT '' COMMENT '签名密钥',
+    `events` varchar(255) NOT NULL DEFAULT '' COMMENT '订阅的事件 逗号分隔 publish,rollback,item',
+    `creator_user` int unsigned NOT NULL DEFAULT 0 COMMENT '创建人',
+    `deleted_at` bigint unsigned NOT NULL DEFAULT 0 COMMENT '删除时间 second',
+    `delete_batch` bigint unsigned NOT NULL DEFAULT 0 COMMENT '随 namespace 删除的批次 0:未随 namespace 删除',
+    `created_at` timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP COMMENT '创建时间',
+    `updated_at` timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP COMMENT '更新时间',
+    PRIMARY KEY (`id`),
+    KEY `ix_app_namespace` (`app_id`, `namespace_id`, `deleted_at`)
+) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4 COMMENT = 'Webhook';
+
+CREATE TABLE IF NOT EXISTS `webhook_delivery` (
+    `id` bigint unsigned NOT NULL AUTO_INCREMENT COMMENT '自增主键',
+    `webhook_id` bigint unsigned NOT NULL COMMENT 'WebhookID',
+    `event` varchar(20) NOT NULL DEFAULT '' COMMENT '事件',
+    `payload` longtext NOT NULL COMMENT '请求内容',
+    `status` tinyint unsigned NOT NULL DEFAULT 0 COMMENT '状态 0:投递中 1:成功 2:失败',
+    `attempts` tinyint unsigned NOT NULL DEFAULT 0 COMMENT '已请求次数',
+    `response_code` smallint unsigned NOT NULL DEFAULT 0 COMMENT '最后一次请求的状态码',
+    `response` varchar(1024) NOT NULL DEFAULT '' COMMENT '最后一次请求的响应或错误信息',
+    `created_at` timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP COMMENT '创建时间',
+    `updated_at` timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP COMMENT '更新时间',
+    PRIMARY KEY (`id`),
+    KEY `ix_webhook_id` (`webhook_id`)
+) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4 COMMENT = 'Webhook 投递记录';
+
+-- 为已有应用补充 owner 成员: 应用创建人作为 owner
+INSERT INTO `app_member` (`app_id`, `user_id`, `role`, `creator_user`)
+SELECT `app`.`app_id`, `app`.`creator_user`, 0, `app`.`creator_user`
+FROM `app`
+WHERE `app`.`deleted_at` = 0
+  AND `app`.`creator_user` != 0
+  AND NOT EXISTS (
+    SELECT 1 FROM `app_member`
+    WHERE `app_member`.`app_id` = `app`.`app_id`
+      AND `app_member`.`user_id` = `app`.`creator_user`
+      AND `app_member`.`deleted_at` = 0
+  );
+
+-- 创建人已是成员但不是 owner 的应用, 将创建人设为 owner
+UPDATE `app_member`
+JOIN `app` ON `app`.`app_id` = `app_member`.`app_id` AND `app`.`creator_user` = `app_member`.`user_id`
+SET `app_member`.`role` = 0
+WHERE `app`.`deleted_at` = 0
+  AND `app_member`.`deleted_at` = 0
+  AND NOT EXISTS (
+    SELECT 1 FROM (SELECT `app_id` FROM `app_member` WHERE `role` = 0 AND `deleted_at` = 0) AS `owner`
+    WHERE `owner`.`app_id` = `app_member`.`app_id`
+  );
+
+-- 为已有应用补充 Approve 权限, 与创建应用时一致绑定到应用的 Master 角色
+INSERT INTO `rule` (`verb`, `resource`)
+SELECT 'Approve', `app`.`app_id`
+FROM `app`
+WHERE `app`.`deleted_at` = 0
+  AND NOT EXISTS (
+    SELECT 1 FROM `rule`
+    WHERE `rule`.`verb` = 'Approve'
+      AND `rule`.`resource` = `app`.`app_id`
+      AND `rule`.`deleted_at` = 0
+  );
+
+INSERT INTO `role_rule` (`role_id`, `rule_id`)
+SELECT `role`.`id`, `rule`.`id`
+FROM `role`
+JOIN `rule` ON `role`.`name` = CONCAT('Master/', `rule`.`resource`)
+WHERE `rule`.`verb` = 'Approve'
+  AND `rule`.`deleted_at` = 0
+  AND `role`.`deleted_at` = 0
+  AND NOT EXISTS (
+    SELECT 1 FROM `role_rule`
+    WHERE `role_rule`.`role_id` = `role`.`id`
+      AND `role_rule`.`rule_id` = `rule`.`id`
+      AND `role_rule`.`deleted_at` = 0
+  );
@@ -96,6 +96,9 @@ CREATE TABLE `app` (
     `name` varchar(100) NOT NULL COMMENT '应用名',
     `dept_id` int unsigned NOT NULL DEFAULT 0 COMMENT '所属部门ID',
     `creator_user` int unsigned DEFAULT 0 NOT NULL COMMENT '创建用户ID',
+    `quota_item_count` int unsigned NOT NULL DEFAULT 0 COMMENT 'namespace 最大 item 数量 0:使用全局设置',
+    `quota_value_size` int unsigned NOT NULL DEFAULT 0 COMMENT '单个值的最大字节数 0:使用全局设置',
+    `quota_release_size` bigint unsigned NOT NULL DEFAULT 0 COMMENT 'namespace 发布配置的最大字节数 0:使用全局设置',
     `deleted_at` bigint unsigned NOT NULL DEFAULT 0 COMMENT '删除时间 second',
     `created_at` timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP COMMENT '创建时间',
     `updated_at` timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP COMMENT '更新时间',
@@ -103,6 +106,23 @@ CREATE TABLE `app` (
     unique key `uk_app_id` (`app_id`, `deleted_at`)
 ) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4 COMMENT = '应用';
 
+-- 应用成员
+DROP TABLE IF EXISTS `app_member`;
+
+CREATE TABLE `app_member` (
+    `id` bigint unsigned AUTO_INCREMENT COMMENT '主键',
+    `app_id` varchar(80) NOT NULL COMMENT 'appID',
+    `user_id` int unsigned NOT NULL COMMENT '用户ID',
+    `role` tinyint unsigned NOT NULL DEFAULT 1 COMMENT '成员角色 0:owner 1:maintainer',
+    `creator_user` int unsigned NOT NULL DEFAULT 0 COMMENT '添加用户ID',
+    `deleted_at` bigint unsigned NOT NULL DEFAULT 0 COMMENT '删除时间 second',
+    `created_at` timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP COMMENT '创建时间',
+    `updated_at` timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP COMMENT '更新时间',
+    primary key (`id`),
+    unique key `uk_app_user` (`app_id`, `user_id`, `deleted_at`),
+    key `ix_user_id` (`user_id`)
+) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4 COMMENT = '应用成员';
+
 -- 用户应用收藏
 DROP TABLE IF EXISTS `user_favorite`;
 
@@ -110,6 +130,7 @@ CREATE TABLE `user_favorite` (
     `id` bigint unsigned AUTO_INCREMENT COMMENT '主键',
     `user_id` int unsigned NOT NULL COMMENT '用户ID',
     `app_id` int unsigned NOT NULL COMMENT '应用ID',
+    `sort` int unsigned NOT NULL DEFAULT 0 COMMENT '排序 越小越靠前',
     `deleted_at` bigint unsigned NOT NULL DEFAULT 0 COMMENT '删除时间 second',
     `created_at` timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP COMMENT '创建时间',
     `updated_at` timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP COMMENT '更新时间',
@@ -117,6 +138,42 @@ CREATE TABLE `user_favorite` (
     unique key `uk_user_app` (`user_id`, `app_id`, `deleted_at`)
 ) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4 COMMENT = '应用收藏';
 
+-- 最近访问应用
+DROP TABLE IF EXISTS `user_recent`;
+
+CREATE TABLE `user_recent` (
+    `id` bigint unsigned AUTO_INCREMENT COMMENT '主键',
+    `user_id` int unsigned NOT NULL COMMENT '用户ID',
+    `app_id` int unsigned NOT NULL COMMENT '应用ID',
+    `visited_at` bigint unsigned NOT NULL DEFAULT 0 COMMENT '最后访问时间 second',
+    `created_at` timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP COMMENT '创建时间',
+    `updated_at` timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP COMMENT '更新时间',
+    primary key (`id`),
+    unique key `uk_user_app` (`user_id`, `app_id`),
+    key `ix_app_id` (`app_id`)
+) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4 COMMENT = '最近访问应用';
+
+-- 环境
+DROP TABLE IF EXISTS `environment`;
+
+CREATE TABLE `environment` (
+    `id` int unsigned AUTO_INCREMENT COMMENT '主键',
+    `name` varchar(80) NOT NULL COMMENT '环境名称',
+    `description` varchar(255) NOT NULL DEFAULT '' COMMENT '环境描述',
+    `approval` tinyint unsigned NOT NULL DEFAULT 2 COMMENT '发布审批 1:开启 2:关闭',
+    `deleted_at` bigint unsigned NOT NULL DEFAULT 0 COMMENT '删除时间 second',
+    `created_at` timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP COMMENT '创建时间',
+    `updated_at` timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP COMMENT '更新时间',
+    primary key (`id`),
+    unique key `uk_name` (`name`, `deleted_at`)
+) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4 COMMENT = '环境';
+
+INSERT INTO `environment` (`name`, `description`, `approval`) VALUES
+    ('dev', '开发环境', 2),
+    ('test', '测试环境', 2),
+    ('staging', '预发布环境', 2),
+    ('prod', '生产环境', 1);
+
 -- app 集群环境
 DROP TABLE IF EXISTS `cluster`;
 
@@ -124,13 +181,16 @@ CREATE TABLE `cluster` (
     `id` bigint unsigned AUTO_INCREMENT COMMENT '主键',
     `app_id` varchar(80) NOT NULL COMMENT 'appID',
     `name` varchar(80) NOT NULL COMMENT '集群环境',
+    `env` varchar(80) NOT NULL DEFAULT 'dev' COMMENT '所属环境',
     `secret` varchar(36) NOT NULL COMMENT '密钥',
+    `approval` tinyint unsigned NOT NULL DEFAULT 0 COMMENT '发布审批 0:继承环境 1:开启 2:关闭',
     `creator_user` int unsigned NOT NULL COMMENT '创建的用户ID',
     `deleted_at` bigint unsigned NOT NULL DEFAULT 0 COMMENT '删除时间 second',
     `created_at` timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP COMMENT '创建时间',
     `updated_at` timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP COMMENT '更新时间',
     primary key (`id`),
-    unique key `uk_app_cluster` (`app_id`, `name`, `deleted_at`)
+    unique key `uk_app_cluster` (`app_id`, `name`, `deleted_at`),
+    key `ix_env` (`env`)
 ) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4 COMMENT = '集群环境';
 
 DROP TABLE IF EXISTS `namespace`;
@@ -141,8 +201,22 @@ CREATE TABLE `namespace` (
     `cluster` varchar(80) NOT NULL DEFAULT 'global' COMMENT '集群环境',
     `namespace` varchar(80) NOT NULL COMMENT '命名空间',
     `scope` tinyint unsigned NOT NULL DEFAULT 0 COMMENT '范围',
+    `key_pattern` varchar(255) NOT NULL DEFAULT '' COMMENT 'key 的正则规则 为空不限制',
+    `key_reserved_prefix` varchar(1024) NOT NULL DEFAULT '' COMMENT '保留的 key 前缀 逗号分隔',
+    `key_max_depth` tinyint unsigned NOT NULL DEFAULT 0 COMMENT 'key 的最大层级 0:不限制',
+    `edit_lock` tinyint unsigned NOT NULL DEFAULT 0 COMMENT '编辑锁 0:关闭 1:开启',
+    `lock_user_id` int unsigned NOT NULL DEFAULT 0 COMMENT '持有编辑锁的用户ID',
+    `lock_expired_at` bigint unsigned NOT NULL DEFAULT 0 COMMENT '编辑锁过期时间 second',
+    `approval` tinyint unsigned NOT NULL DEFAULT 0 COMMENT '发布审批 0:继承集群 1:开启 2:关闭',
+    `retain_count` int unsigned NOT NULL DEFAULT 0 COMMENT '保留的发布数量 0:使用全局设置',
+    `retain_days` int unsigned NOT NULL DEFAULT 0 COMMENT '保留的发布天数 0:使用全局设置',
+    `quota_item_count` int unsigned NOT NULL DEFAULT 0 COMMENT '最大 item 数量 0:使用应用设置',
+    `quota_value_size` int unsigned NOT NULL DEFAULT 0 COMMENT '单个值的最大字节数 0:使用应用设置',
+    `quota_release_size` bigint unsigned NOT NULL DEFAULT 0 COMMENT '发布配置的最大字节数 0:使用应用设置',
+    `polled_at` bigint unsigned NOT NULL DEFAULT 0 COMMENT '客户端最近拉取时间 second',
     `creator_user` int unsigned NOT NULL COMMENT '创建的用户ID',
     `deleted_at` bigint unsigned NOT NULL DEFAULT 0 COMMENT '删除时间 second',
+    `delete_batch` bigint unsigned NOT NULL DEFAULT 0 COMMENT '删除批次 随其删除的关联数据使用相同的批次',
     `created_at` timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP COMMENT '创建时间',
     `updated_at` timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP COMMENT '更新时间',
     primary key (`id`),
@@ -150,6 +224,27 @@ CREATE TABLE `namespace` (
     KEY `namespace` (`namespace`, `scope`, `deleted_at`)
 ) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4 COMMENT = '命名空间';
 
+-- namespace 模板
+DROP TABLE IF EXISTS `namespace_template`;
+
+CREATE TABLE `namespace_template` (
+    `id` bigint unsigned AUTO_INCREMENT COMMENT '主键',
+    `app_id` varchar(80) NOT NULL COMMENT '所属应用ID',
+    `name` varchar(80) NOT NULL COMMENT '模板名称',
+    `description` varchar(255) NOT NULL DEFAULT '' COMMENT '模板描述',
+    `scope` tinyint unsigned NOT NULL DEFAULT 0 COMMENT '范围',
+    `key_pattern` varchar(255) NOT NULL DEFAULT '' COMMENT 'key 的正则规则 为空不限制',
+    `key_reserved_prefix` varchar(1024) NOT NULL DEFAULT '' COMMENT '保留的 key 前缀 逗号分隔',
+    `key_max_depth` tinyint unsigned NOT NULL DEFAULT 0 COMMENT 'key 的最大层级 0:不限制',
+    `items` longtext NOT NULL COMMENT 'key 及默认值 json',
+    `creator_user` int unsigned NOT NULL COMMENT '创建的用户ID',
+    `deleted_at` bigint unsigned NOT NULL DEFAULT 0 COMMENT '删除时间 second',
+    `created_at` timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP COMMENT '创建时间',
+    `updated_at` timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP COMMENT '更新时间',
+    primary key (`id`),
+    unique key `uk_app_name` (`app_id`, `name`, `deleted_at`)
+) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4 COMMENT = 'namespace 模板';
+
 DROP TABLE IF EXISTS `app_extend`;
 
 CREATE TABLE `app_extend` (
@@ -159,6 +254,7 @@ CREATE TABLE `app_extend` (
     `namespace_name` varchar(80) NOT NULL COMMENT '命名空间',
     `creator_user` int unsigned NOT NULL COMMENT '创建的用户ID',
     `deleted_at` bigint unsigned NOT NULL DEFAULT 0 COMMENT '删除时间 second',
+    `delete_batch` bigint unsigned NOT NULL DEFAULT 0 COMMENT '随 namespace 删除的批次 0:未随 namespace 删除',
     `created_at` timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP COMMENT '创建时间',
     `updated_at` timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP COMMENT '更新时间',
     PRIMARY KEY (`id`),
@@ -177,12 +273,34 @@ CREATE TABLE `item` (
     `version` bigint unsigned NOT NULL DEFAULT 0 COMMENT '版本',
     `modify_user_id` int unsigned NOT NULL DEFAULT 0 COMMENT '最后修改用户',
     `deleted_at` bigint unsigned NOT NULL DEFAULT 0 COMMENT '删除时间 second',
+    `delete_batch` bigint unsigned NOT NULL DEFAULT 0 COMMENT '随 namespace 删除的批次 0:未随 namespace 删除',
     `created_at` timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP COMMENT '创建时间',
     `updated_at` timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP COMMENT '更新时间',
     PRIMARY KEY (`id`),
     KEY `ix_namespace` (`namespace_id`, `deleted_at`)
 ) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4 COMMENT = '配置表';
 
+DROP TABLE IF EXISTS `item_revision`;
+
+CREATE TABLE `item_revision` (
+    `id` bigint unsigned AUTO_INCREMENT COMMENT '主键',
+    `namespace_id` bigint unsigned NOT NULL COMMENT '关联的 namespace_id',
+    `item_id` bigint unsigned NOT NULL COMMENT '关联的 item_id',
+    `key` varchar(255) NOT NULL COMMENT '配置key',
+    `old_value` text NOT NULL COMMENT '修改前的value',
+    `new_value` text NOT NULL COMMENT '修改后的value',
+    `category` varchar(20) NOT NULL COMMENT 'value 类型',
+    `operation` tinyint unsigned NOT NULL DEFAULT 0 COMMENT '操作类型 0:创建 1:修改 2:删除 3:还原',
+    `version` bigint unsigned NOT NULL DEFAULT 0 COMMENT '修改后的版本',
+    `user_id` int unsigned NOT NULL DEFAULT 0 COMMENT '修改用户',
+    `deleted_at` bigint unsigned NOT NULL DEFAULT 0 COMMENT '删除时间 second',
+    `created_at` timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP COMMENT '创建时间',
+    `updated_at` timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP COMMENT '更新时间',
+    PRIMARY KEY (`id`),
+    KEY `ix_item` (`item_id`, `deleted_at`),
+    KEY `ix_namespace` (`namespace_id`, `deleted_at`)
+) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4 COMMENT = '配置修改记录';
+
 DROP TABLE IF EXISTS `release`;
 
 CREATE TABLE `release` (
@@ -192,9 +310,11 @@ CREATE TABLE `release` (
     `name` varchar(64) NOT NULL DEFAULT '' COMMENT '发布名字',
     `remark` varchar(255) DEFAULT NULL DEFAULT '' COMMENT '发布说明',
     `configurations` longtext NOT NULL COMMENT '发布配置',
+    `checksum` varchar(64) NOT NULL DEFAULT '' COMMENT '发布配置的 sha256 校验和',
     `is_abandoned` tinyint unsigned NOT NULL DEFAULT 0 COMMENT '是否废弃',
     `publish_user_id` int NOT NULL DEFAULT 0 COMMENT '用户身份标识',
     `deleted_at` bigint unsigned NOT NULL DEFAULT 0 COMMENT '删除时间 second',
+    `delete_batch` bigint unsigned NOT NULL DEFAULT 0 COMMENT '随 namespace 删除的批次 0:未随 namespace 删除',
     `created_at` timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP COMMENT '创建时间',
     `updated_at` timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP COMMENT '更新时间',
     PRIMARY KEY (`id`),
@@ -208,9 +328,94 @@ CREATE TABLE `release_history` (
     `namespace_id` bigint unsigned NOT NULL COMMENT '命名空间ID',
     `change` longtext NOT NULL COMMENT '变更集',
     `release_id` bigint unsigned NOT NULL COMMENT '对应release_id',
+    `operation` tinyint unsigned NOT NULL DEFAULT 0 COMMENT '操作 0:发布 1:回滚',
     `deleted_at` bigint unsigned NOT NULL DEFAULT 0 COMMENT '删除时间 second',
+    `delete_batch` bigint unsigned NOT NULL DEFAULT 0 COMMENT '随 namespace 删除的批次 0:未随 namespace 删除',
     `created_at` timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP COMMENT '创建时间',
     `updated_at` timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP COMMENT '更新时间',
     PRIMARY KEY (`id`),
     KEY `ix_namespace` (`namespace_id`, `deleted_at`)
-) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4 COMMENT = '发布';
\ No newline at end of file
+) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4 COMMENT = '发布';
+
+DROP TABLE IF EXISTS `publish_request`;
+
+CREATE TABLE `publish_request` (
+    `id` bigint unsigned NOT NULL AUTO_INCREMENT COMMENT '自增主键',
+    `namespace_id` bigint unsigned NOT NULL COMMENT '命名空间ID',
+    `name` varchar(64) NOT NULL DEFAULT '' COMMENT '发布名称',
+    `remark` varchar(255) NOT NULL DEFAULT '' COMMENT '发布说明',
+    `items` longtext NOT NULL COMMENT '待发布的 item 及版本',
+    `change` longtext NOT NULL COMMENT '与线上配置的差异',
+    `base_release_id` bigint unsigned NOT NULL DEFAULT 0 COMMENT '申请时的线上发布ID',
+    `rollback_release_id` bigint unsigned NOT NULL DEFAULT 0 COMMENT '回滚申请的目标发布ID 0:发布申请',
+    `status` tinyint unsigned NOT NULL DEFAULT 0 COMMENT '状态 0:待审批 1:通过 2:拒绝 3:取消',
+    `request_user_id` int unsigned NOT NULL COMMENT '申请人',
+    `review_user_id` int unsigned NOT NULL DEFAULT 0 COMMENT '审批人',
+    `review_remark` varchar(255) NOT NULL DEFAULT '' COMMENT '审批意见',
+    `release_id` bigint unsigned NOT NULL DEFAULT 0 COMMENT '审批通过后的发布ID',
+    `deleted_at` bigint unsigned NOT NULL DEFAULT 0 COMMENT '删除时间 second',
+    `delete_batch` bigint unsigned NOT NULL DEFAULT 0 COMMENT '随 namespace 删除的批次 0:未随 namespace 删除',
+    `created_at` timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP COMMENT '创建时间',
+    `updated_at` timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP COMMENT '更新时间',
+    PRIMARY KEY (`id`),
+    KEY `ix_namespace_status` (`namespace_id`, `status`, `deleted_at`)
+) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4 COMMENT = '发布审批';
+
+DROP TABLE IF EXISTS `publish_schedule`;
+
+CREATE TABLE `publish_schedule` (
+    `id` bigint unsigned NOT NULL AUTO_INCREMENT COMMENT '自增主键',
+    `namespace_id` bigint unsigned NOT NULL COMMENT '命名空间ID',
+    `name` varchar(64) NOT NULL DEFAULT '' COMMENT '发布名称',
+    `remark` varchar(255) NOT NULL DEFAULT '' COMMENT '发布说明',
+    `items` longtext NOT NULL COMMENT '待发布的 item 及版本',
+    `base_release_id` bigint unsigned NOT NULL DEFAULT 0 COMMENT '计划基于的发布ID',
+    `publish_at` bigint unsigned NOT NULL COMMENT '计划发布时间 second',
+    `status` tinyint unsigned NOT NULL DEFAULT 0 COMMENT '状态 0:等待 1:执行中 2:已发布 3:失败 4:取消',
+    `claimed_at` bigint unsigned NOT NULL DEFAULT 0 COMMENT '开始执行时间 second',
+    `reason` varchar(255) NOT NULL DEFAULT '' COMMENT '失败原因',
+    `release_id` bigint unsigned NOT NULL DEFAULT 0 COMMENT '发布ID',
+    `user_id` int unsigned NOT NULL COMMENT '创建人',
+    `deleted_at` bigint unsigned NOT NULL DEFAULT 0 COMMENT '删除时间 second',
+    `created_at` timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP COMMENT '创建时间',
+    `updated_at` timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP COMMENT '更新时间',
+    PRIMARY KEY (`id`),
+    KEY `ix_status_publish_at` (`status`, `publish_at`),
+    KEY `ix_namespace_id` (`namespace_id`, `deleted_at`)
+) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4 COMMENT = '定时发布';
+
+DROP TABLE IF EXISTS `webhook`;
+
+CREATE TABLE `webhook` (
+    `id` bigint unsigned NOT NULL AUTO_INCREMENT COMMENT '自增主键',
+    `app_id` varchar(80) NOT NULL COMMENT 'appID',
+    `namespace_id` bigint unsigned NOT NULL DEFAULT 0 COMMENT '命名空间ID 0:app 下所有 namespace',
+    `name` varchar(64) NOT NULL DEFAULT '' COMMENT '名称',
+    `url` varchar(1024) NOT NULL COMMENT '回调地址',
+    `secret` varchar(128) NOT NULL DEFAULT '' COMMENT '签名密钥',
+    `events` varchar(255) NOT NULL DEFAULT '' COMMENT '订阅的事件 逗号分隔 publish,rollback,item',
+    `creator_user` int unsigned NOT NULL DEFAULT 0 COMMENT '创建人',
+    `deleted_at` bigint unsigned NOT NULL DEFAULT 0 COMMENT '删除时间 second',
+    `delete_batch` bigint unsigned NOT NULL DEFAULT 0 COMMENT '随 namespace 删除的批次 0:未随 namespace 删除',
+    `created_at` timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP COMMENT '创建时间',
+    `updated_at` timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP COMMENT '更新时间',
+    PRIMARY KEY (`id`),
+    KEY `ix_app_namespace` (`app_id`, `namespace_id`, `deleted_at`)
+) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4 COMMENT = 'Webhook';
+
+DROP TABLE IF EXISTS `webhook_delivery`;
+
+CREATE TABLE `webhook_delivery` (
+    `id` bigint unsigned NOT NULL AUTO_INCREMENT COMMENT '自增主键',
+    `webhook_id` bigint unsigned NOT NULL COMMENT 'WebhookID',
+    `event` varchar(20) NOT NULL DEFAULT '' COMMENT '事件',
+    `payload` longtext NOT NULL COMMENT '请求内容',
+    `status` tinyint unsigned NOT NULL DEFAULT 0 COMMENT '状态 0:投递中 1:成功 2:失败',
+    `attempts` tinyint unsigned NOT NULL DEFAULT 0 COMMENT '已请求次数',
+    `response_code` smallint unsigned NOT NULL DEFAULT 0 COMMENT '最后一次请求的状态码',
+    `response` varchar(1024) NOT NULL DEFAULT '' COMMENT '最后一次请求的响应或错误信息',
+    `created_at` timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP COMMENT '创建时间',
+    `updated_at` timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP COMMENT '更新时间',
+    PRIMARY KEY (`id`),
+    KEY `ix_webhook_id` (`webhook_id`)
+) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4 COMMENT = 'Webhook 投递记录';
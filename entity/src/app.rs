@@ -13,6 +13,9 @@ pub struct Model {
     pub dept_id: u32, // 部门 ID
     #[serde(serialize_with = "super::confuse")]
     pub creator_user: u32, // 创建者ID
+    pub quota_item_count: u32, // namespace 最大 item 数量 0为使用全局设置
+    pub quota_value_size: u32, // 单个值的最大字节数 0为使用全局设置
+    pub quota_release_size: u64, // namespace 发布配置的最大字节数 0为使用全局设置
     #[serde(
         serialize_with = "super::format_time",
         skip_serializing_if = "super::is_zero"
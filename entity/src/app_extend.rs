@@ -12,6 +12,8 @@ pub struct Model {
     pub namespace_name: String, // app  namespace
     pub creator_user: u32,
     pub deleted_at: u64,
+    #[serde(skip)]
+    pub delete_batch: u64, // 随 namespace 删除的批次 0为未随 namespace 删除
     pub created_at: DateTimeWithTimeZone, // 创建时间
     pub updated_at: DateTimeWithTimeZone, // 更新时间
 }
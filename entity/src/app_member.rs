@@ -0,0 +1,79 @@
+use std::str::FromStr;
+
+use sea_orm::{entity::prelude::*, FromQueryResult};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Deserialize, Serialize)]
+#[sea_orm(table_name = "app_member")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    #[serde(serialize_with = "super::confuse")]
+    pub id: u64,
+    pub app_id: String, // app 唯一 ID
+    #[serde(serialize_with = "super::confuse")]
+    pub user_id: u32, // 成员用户ID
+    pub role: MemberRole, // 成员角色
+    #[serde(serialize_with = "super::confuse")]
+    pub creator_user: u32, // 添加者ID
+    #[serde(skip)]
+    pub deleted_at: u64, // 删除时间 为0则未删除
+    pub created_at: DateTimeWithTimeZone, // 创建时间
+    pub updated_at: DateTimeWithTimeZone, // 更新时间
+}
+
+#[derive(Copy, Clone, Debug, EnumIter)]
+pub enum Relation {
+    Users,
+}
+
+impl RelationTrait for Relation {
+    fn def(&self) -> RelationDef {
+        match self {
+            Self::Users => Entity::belongs_to(super::UsersEntity)
+                .from(Column::UserId)
+                .to(super::UsersColumn::Id)
+                .into(),
+        }
+    }
+}
+impl Related<super::UsersEntity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Users.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+// 应用成员角色 Owner 可管理应用及成员, Maintainer 仅作为维护人信息展示
+#[derive(Debug, Clone, PartialEq, Default, EnumIter, DeriveActiveEnum, Deserialize, Serialize)]
+#[sea_orm(rs_type = "u8", db_type = "TinyUnsigned")]
+pub enum MemberRole {
+    #[sea_orm(num_value = 0)]
+    #[serde(rename = "owner")]
+    Owner,
+    #[default]
+    #[sea_orm(num_value = 1)]
+    #[serde(rename = "maintainer")]
+    Maintainer,
+}
+
+impl FromStr for MemberRole {
+    type Err = ();
+    fn from_str(str: &str) -> Result<Self, Self::Err> {
+        match str.trim().to_lowercase().as_str() {
+            "owner" => Ok(Self::Owner),
+            "maintainer" => Ok(Self::Maintainer),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(FromQueryResult, Serialize, Debug)]
+pub struct MemberItem {
+    #[serde(serialize_with = "super::confuse")]
+    pub user_id: u32,
+    pub account: String,
+    pub nickname: String,
+    pub role: MemberRole,
+    pub created_at: DateTimeWithTimeZone,
+}
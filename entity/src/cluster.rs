@@ -1,3 +1,5 @@
+use super::common::Approval;
+
 use sea_orm::{entity::prelude::*, FromQueryResult};
 use serde::{Deserialize, Serialize};
 
@@ -9,7 +11,9 @@ pub struct Model {
     pub id: u64,
     pub app_id: String, // app 唯一 ID
     pub name: String,   // cluster name
+    pub env: String,    // 所属环境
     pub secret: String, // 连接 secret
+    pub approval: Approval, // 发布是否需要审批
     pub creator_user: u32,
     pub deleted_at: u64,
     pub created_at: DateTimeWithTimeZone, // 创建时间
@@ -29,6 +33,7 @@ impl ActiveModelBehavior for ActiveModel {}
 #[derive(FromQueryResult)]
 pub struct SecretData {
     pub secret: String,
+    pub env: String,
 }
 
 #[derive(FromQueryResult, Serialize, Debug)]
@@ -36,4 +41,5 @@ pub struct ClusterItem {
     #[serde(serialize_with = "super::confuse")]
     pub id: u64,
     pub name: String,
+    pub env: String,
 }
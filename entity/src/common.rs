@@ -1,4 +1,5 @@
 use std::fmt::Display;
+use std::str::FromStr;
 
 use sea_orm::{entity::prelude::*, FromQueryResult};
 use serde::{Deserialize, Serialize};
@@ -14,6 +15,16 @@ pub enum ItemCategory {
     Yaml,
     #[sea_orm(string_value = "Toml")]
     Toml,
+    #[sea_orm(string_value = "Number")]
+    Number,
+    #[sea_orm(string_value = "Boolean")]
+    Boolean,
+    #[sea_orm(string_value = "Properties")]
+    Properties,
+    #[sea_orm(string_value = "Xml")]
+    Xml,
+    #[sea_orm(string_value = "File")]
+    File, // base64 编码的二进制内容
 }
 
 impl Default for ItemCategory {
@@ -28,18 +39,31 @@ impl Display for ItemCategory {
             &Self::Json => "json",
             &Self::Toml => "toml",
             &Self::Yaml => "yaml",
+            &Self::Number => "number",
+            &Self::Boolean => "boolean",
+            &Self::Properties => "properties",
+            &Self::Xml => "xml",
+            &Self::File => "file",
         };
         write!(f, "{}", s)
     }
 }
 
-impl From<String> for ItemCategory {
-    fn from(str: String) -> Self {
-        match str.to_lowercase().as_str() {
-            "json" => Self::Json,
-            "yaml" => Self::Yaml,
-            "toml" => Self::Toml,
-            _ => Self::Text,
+// 未知的类型返回 Err
+impl FromStr for ItemCategory {
+    type Err = ();
+    fn from_str(str: &str) -> Result<Self, Self::Err> {
+        match str.trim().to_lowercase().as_str() {
+            "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            "yaml" => Ok(Self::Yaml),
+            "toml" => Ok(Self::Toml),
+            "number" => Ok(Self::Number),
+            "boolean" => Ok(Self::Boolean),
+            "properties" => Ok(Self::Properties),
+            "xml" => Ok(Self::Xml),
+            "file" => Ok(Self::File),
+            _ => Err(()),
         }
     }
 }
@@ -91,6 +115,34 @@ impl From<String> for Scope {
     }
 }
 
+// 发布是否需要审批 namespace 为 Inherit 时使用集群的配置
+#[derive(Debug, Clone, PartialEq, Default, EnumIter, DeriveActiveEnum, Deserialize, Serialize)]
+#[sea_orm(rs_type = "u8", db_type = "TinyUnsigned")]
+pub enum Approval {
+    #[default]
+    #[sea_orm(num_value = 0)]
+    #[serde(rename = "inherit")]
+    Inherit,
+    #[sea_orm(num_value = 1)]
+    #[serde(rename = "enable")]
+    Enable,
+    #[sea_orm(num_value = 2)]
+    #[serde(rename = "disable")]
+    Disable,
+}
+
+impl FromStr for Approval {
+    type Err = ();
+    fn from_str(str: &str) -> Result<Self, Self::Err> {
+        match str.trim().to_lowercase().as_str() {
+            "inherit" => Ok(Self::Inherit),
+            "enable" => Ok(Self::Enable),
+            "disable" => Ok(Self::Disable),
+            _ => Err(()),
+        }
+    }
+}
+
 #[derive(FromQueryResult, Default, Debug, Clone, Serialize)]
 pub struct Name {
     pub name: String,
@@ -116,6 +168,17 @@ pub struct ID {
     pub id: u64,
 }
 
+// 配额 各项为 0 时使用上级设置, 最终为 0 则不限制
+#[derive(FromQueryResult, Default, Debug, Clone, Serialize)]
+pub struct Quota {
+    #[serde(rename = "item_count")]
+    pub quota_item_count: u32, // item 数量
+    #[serde(rename = "value_size")]
+    pub quota_value_size: u32, // 单个值的字节数
+    #[serde(rename = "release_size")]
+    pub quota_release_size: u64, // 发布配置的字节数
+}
+
 #[derive(FromQueryResult, Default, Debug, Clone, Serialize)]
 pub struct IDu32 {
     #[serde(serialize_with = "super::confuse")]
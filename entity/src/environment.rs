@@ -0,0 +1,29 @@
+use super::common::Approval;
+
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Deserialize, Serialize)]
+#[sea_orm(table_name = "environment")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    #[serde(serialize_with = "super::confuse")]
+    pub id: u32,
+    pub name: String,        // 环境名称 如 dev test staging prod
+    pub description: String, // 环境描述
+    pub approval: Approval,  // 环境下集群发布是否需要审批
+    #[serde(skip)]
+    pub deleted_at: u64,
+    pub created_at: DateTimeWithTimeZone, // 创建时间
+    pub updated_at: DateTimeWithTimeZone, // 更新时间
+}
+
+#[derive(Copy, Clone, Debug, EnumIter)]
+pub enum Relation {}
+impl RelationTrait for Relation {
+    fn def(&self) -> RelationDef {
+        panic!("No RelationDef")
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
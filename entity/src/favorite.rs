@@ -9,6 +9,7 @@ pub struct Model {
     pub id: u64, // 用户ID
     pub user_id: u32,                     // 登录用户名
     pub app_id: u32,                      // 邮箱
+    pub sort: u32,                        // 排序 越小越靠前
     pub deleted_at: u64,                  // 删除时间
     pub created_at: DateTimeWithTimeZone, // 创建时间
     pub updated_at: DateTimeWithTimeZone, // 更新时间
@@ -1,7 +1,7 @@
 use super::common::ItemCategory;
 
 use sea_orm::{entity::prelude::*, FromQueryResult};
-use serde::{Deserialize, Serialize};
+use serde::{ser::SerializeStruct, Deserialize, Serialize, Serializer};
 
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Deserialize, Serialize)]
 #[sea_orm(table_name = "item")]
@@ -18,15 +18,30 @@ pub struct Model {
     pub version: u64,
     pub modify_user_id: u32, // 最后修改人
     pub deleted_at: u64,
+    #[serde(skip)]
+    pub delete_batch: u64, // 随 namespace 删除的批次 0为未随 namespace 删除
     pub created_at: DateTimeWithTimeZone, // 创建时间
     pub updated_at: DateTimeWithTimeZone, // 更新时间
 }
 
 #[derive(Copy, Clone, Debug, EnumIter)]
-pub enum Relation {}
+pub enum Relation {
+    Namespace,
+}
+
 impl RelationTrait for Relation {
     fn def(&self) -> RelationDef {
-        panic!("No RelationDef")
+        match self {
+            Self::Namespace => Entity::belongs_to(super::NamespaceEntity)
+                .from(Column::NamespaceId)
+                .to(super::NamespaceColumn::Id)
+                .into(),
+        }
+    }
+}
+impl Related<super::NamespaceEntity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Namespace.def()
     }
 }
 
@@ -34,6 +49,7 @@ impl ActiveModelBehavior for ActiveModel {}
 
 #[derive(FromQueryResult, Default, Serialize, Deserialize, Debug, Clone)]
 pub struct ItemDesc {
+    // 数据库ID 不写入发布的配置及发布记录
     #[serde(skip)]
     pub id: u64,
     pub key: String,
@@ -42,13 +58,41 @@ pub struct ItemDesc {
     pub version: u64,
 }
 
-#[derive(FromQueryResult, Default, Serialize, Deserialize, Debug, Clone)]
+#[derive(FromQueryResult, Default, Deserialize, Debug, Clone)]
 pub struct ConfigItem {
     pub key: String,
     pub value: String,
     pub category: ItemCategory,
 }
 
+// 下发给客户端时 number 与 boolean 类型输出为对应的 json 类型
+impl Serialize for ConfigItem {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("ConfigItem", 3)?;
+        s.serialize_field("key", &self.key)?;
+        match self.category {
+            ItemCategory::Number => {
+                if let Ok(v) = self.value.parse::<i64>() {
+                    s.serialize_field("value", &v)?;
+                } else if let Ok(v) = self.value.parse::<u64>() {
+                    s.serialize_field("value", &v)?;
+                } else if let Ok(v) = self.value.parse::<f64>() {
+                    s.serialize_field("value", &v)?;
+                } else {
+                    s.serialize_field("value", &self.value)?;
+                }
+            }
+            ItemCategory::Boolean => match self.value.parse::<bool>() {
+                Ok(v) => s.serialize_field("value", &v)?,
+                Err(_) => s.serialize_field("value", &self.value)?,
+            },
+            _ => s.serialize_field("value", &self.value)?,
+        }
+        s.serialize_field("category", &self.category)?;
+        s.end()
+    }
+}
+
 #[derive(FromQueryResult, Default, Debug, Clone)]
 pub struct ItemData {
     pub id: u64,
@@ -59,3 +103,41 @@ pub struct ItemData {
     pub version: u64,
     pub deleted_at: u64,
 }
+
+// namespace 的 item 用量
+#[derive(FromQueryResult, Debug, Clone)]
+pub struct ItemUsage {
+    pub namespace_id: u64,
+    pub item_count: u64,
+    pub value_size: u64, // 最大的值字节数
+}
+
+impl From<ItemData> for ItemDesc {
+    fn from(item: ItemData) -> Self {
+        ItemDesc {
+            id: item.id,
+            key: item.key,
+            value: item.value,
+            category: item.category,
+            version: item.version,
+        }
+    }
+}
+
+#[derive(FromQueryResult, Serialize, Debug, Clone)]
+pub struct ItemSearch {
+    // 已发布的配置中不含 item ID
+    #[serde(
+        serialize_with = "super::confuse",
+        skip_serializing_if = "super::is_zero"
+    )]
+    pub id: u64,
+    #[serde(serialize_with = "super::confuse")]
+    pub namespace_id: u64,
+    pub app_id: String,
+    pub cluster: String,
+    pub namespace: String,
+    pub key: String,
+    pub value: String,
+    pub category: ItemCategory,
+}
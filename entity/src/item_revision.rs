@@ -0,0 +1,96 @@
+use super::common::ItemCategory;
+
+use sea_orm::{entity::prelude::*, FromQueryResult};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Deserialize, Serialize)]
+#[sea_orm(table_name = "item_revision")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    #[serde(serialize_with = "super::confuse")]
+    pub id: u64,
+    #[serde(serialize_with = "super::confuse")]
+    pub namespace_id: u64,
+    #[serde(serialize_with = "super::confuse")]
+    pub item_id: u64,
+    pub key: String,
+    pub old_value: String, // 修改前的值
+    pub new_value: String, // 修改后的值
+    pub category: ItemCategory,
+    pub operation: Operation, // 操作类型
+    pub version: u64,         // 修改后 item 的版本
+    #[serde(serialize_with = "super::confuse")]
+    pub user_id: u32, // 修改人
+    pub deleted_at: u64,
+    pub created_at: DateTimeWithTimeZone, // 创建时间
+    pub updated_at: DateTimeWithTimeZone, // 更新时间
+}
+
+#[derive(Copy, Clone, Debug, EnumIter)]
+pub enum Relation {
+    Users,
+}
+
+impl RelationTrait for Relation {
+    fn def(&self) -> RelationDef {
+        match self {
+            Self::Users => Entity::belongs_to(super::UsersEntity)
+                .from(Column::UserId)
+                .to(super::UsersColumn::Id)
+                .into(),
+        }
+    }
+}
+impl Related<super::UsersEntity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Users.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+#[derive(Debug, Clone, PartialEq, EnumIter, DeriveActiveEnum, Deserialize, Serialize)]
+#[sea_orm(rs_type = "u8", db_type = "TinyUnsigned")]
+pub enum Operation {
+    #[sea_orm(num_value = 0)]
+    #[serde(rename = "create")]
+    Create,
+    #[sea_orm(num_value = 1)]
+    #[serde(rename = "modify")]
+    Modify,
+    #[sea_orm(num_value = 2)]
+    #[serde(rename = "delete")]
+    Delete,
+    #[sea_orm(num_value = 3)]
+    #[serde(rename = "revert")]
+    Revert,
+}
+
+#[derive(FromQueryResult, Serialize, Debug, Clone)]
+pub struct RevisionItem {
+    #[serde(serialize_with = "super::confuse")]
+    pub id: u64,
+    pub key: String,
+    pub old_value: String,
+    pub new_value: String,
+    pub category: ItemCategory,
+    pub operation: Operation,
+    pub version: u64,
+    #[serde(serialize_with = "super::confuse")]
+    pub user_id: u32,
+    pub nickname: Option<String>,
+    pub created_at: DateTimeWithTimeZone,
+}
+
+#[derive(FromQueryResult, Serialize, Debug, Clone)]
+pub struct BlameItem {
+    #[serde(serialize_with = "super::confuse")]
+    pub item_id: u64,
+    pub key: String,
+    pub operation: Operation,
+    pub version: u64,
+    #[serde(serialize_with = "super::confuse")]
+    pub user_id: u32,
+    pub nickname: Option<String>,
+    pub created_at: DateTimeWithTimeZone,
+}
@@ -1,12 +1,18 @@
 //! 数据库表结构
 pub mod app;
 pub mod app_extend;
+pub mod app_member;
 pub mod cluster;
 pub mod common;
 pub mod constant;
 pub mod department;
+pub mod environment;
 pub mod item;
+pub mod item_revision;
 pub mod namespace;
+pub mod namespace_template;
+pub mod publish_request;
+pub mod publish_schedule;
 pub mod release;
 pub mod release_history;
 pub mod role;
@@ -15,7 +21,10 @@ pub mod rule;
 pub mod user_role;
 pub mod users;
 pub mod utils;
+pub mod webhook;
+pub mod webhook_delivery;
 pub mod favorite;
+pub mod recent;
 
 pub use sea_orm as orm;
 
@@ -24,7 +33,7 @@ pub use utils::is_zero;
 pub use utils::confuse;
 
 pub use cluster::SecretData;
-pub use common::{IDu32, ItemCategory, Scope, ID};
+pub use common::{Approval, IDu32, ItemCategory, Quota, Scope, ID};
 
 pub use users::ActiveModel as UsersActive;
 pub use users::Column as UsersColumn;
@@ -61,11 +70,26 @@ pub use app::Column as AppColumn;
 pub use app::Entity as AppEntity;
 pub use app::Model as AppModel;
 
+pub use app_member::ActiveModel as AppMemberActive;
+pub use app_member::Column as AppMemberColumn;
+pub use app_member::Entity as AppMemberEntity;
+pub use app_member::Model as AppMemberModel;
+
 pub use favorite::ActiveModel as FavoriteActive;
 pub use favorite::Column as FavoriteColumn;
 pub use favorite::Entity as FavoriteEntity;
 pub use favorite::Model as FavoriteModel;
 
+pub use recent::ActiveModel as RecentActive;
+pub use recent::Column as RecentColumn;
+pub use recent::Entity as RecentEntity;
+pub use recent::Model as RecentModel;
+
+pub use environment::ActiveModel as EnvironmentActive;
+pub use environment::Column as EnvironmentColumn;
+pub use environment::Entity as EnvironmentEntity;
+pub use environment::Model as EnvironmentModel;
+
 pub use cluster::ActiveModel as ClusterActive;
 pub use cluster::Column as ClusterColumn;
 pub use cluster::Entity as ClusterEntity;
@@ -81,11 +105,21 @@ pub use namespace::Column as NamespaceColumn;
 pub use namespace::Entity as NamespaceEntity;
 pub use namespace::Model as NamespaceModel;
 
+pub use namespace_template::ActiveModel as NamespaceTemplateActive;
+pub use namespace_template::Column as NamespaceTemplateColumn;
+pub use namespace_template::Entity as NamespaceTemplateEntity;
+pub use namespace_template::Model as NamespaceTemplateModel;
+
 pub use item::ActiveModel as ItemActive;
 pub use item::Column as ItemColumn;
 pub use item::Entity as ItemEntity;
 pub use item::Model as ItemModel;
 
+pub use item_revision::ActiveModel as ItemRevisionActive;
+pub use item_revision::Column as ItemRevisionColumn;
+pub use item_revision::Entity as ItemRevisionEntity;
+pub use item_revision::Model as ItemRevisionModel;
+
 pub use release::ActiveModel as ReleaseActive;
 pub use release::Column as ReleaseColumn;
 pub use release::Entity as ReleaseEntity;
@@ -95,3 +129,23 @@ pub use release_history::ActiveModel as ReleaseHistoryActive;
 pub use release_history::Column as ReleaseHistoryColumn;
 pub use release_history::Entity as ReleaseHistoryEntity;
 pub use release_history::Model as ReleaseHistoryModel;
+
+pub use publish_request::ActiveModel as PublishRequestActive;
+pub use publish_request::Column as PublishRequestColumn;
+pub use publish_request::Entity as PublishRequestEntity;
+pub use publish_request::Model as PublishRequestModel;
+
+pub use publish_schedule::ActiveModel as PublishScheduleActive;
+pub use publish_schedule::Column as PublishScheduleColumn;
+pub use publish_schedule::Entity as PublishScheduleEntity;
+pub use publish_schedule::Model as PublishScheduleModel;
+
+pub use webhook::ActiveModel as WebhookActive;
+pub use webhook::Column as WebhookColumn;
+pub use webhook::Entity as WebhookEntity;
+pub use webhook::Model as WebhookModel;
+
+pub use webhook_delivery::ActiveModel as WebhookDeliveryActive;
+pub use webhook_delivery::Column as WebhookDeliveryColumn;
+pub use webhook_delivery::Entity as WebhookDeliveryEntity;
+pub use webhook_delivery::Model as WebhookDeliveryModel;
@@ -1,4 +1,4 @@
-use super::common::Scope;
+use super::common::{Approval, Scope};
 
 use sea_orm::{entity::prelude::*, FromQueryResult};
 use serde::{Deserialize, Serialize};
@@ -13,8 +13,24 @@ pub struct Model {
     pub cluster: String,
     pub namespace: String,
     pub scope: Scope,
+    pub key_pattern: String,         // key 的正则规则
+    pub key_reserved_prefix: String, // 保留的 key 前缀 逗号分隔
+    pub key_max_depth: u8,           // key 以 . 分隔的最大层级 0为不限制
+    pub edit_lock: EditLock,         // 是否开启编辑锁
+    pub lock_user_id: u32,           // 持有编辑锁的用户
+    pub lock_expired_at: u64,        // 编辑锁过期时间 second
+    pub approval: Approval,          // 发布是否需要审批
+    pub retain_count: u32,           // 保留的发布数量 0为使用全局设置
+    pub retain_days: u32,            // 保留的发布天数 0为使用全局设置
+    pub quota_item_count: u32,       // 最大 item 数量 0为使用应用设置
+    pub quota_value_size: u32,       // 单个值的最大字节数 0为使用应用设置
+    pub quota_release_size: u64,     // 发布配置的最大字节数 0为使用应用设置
+    #[serde(skip)]
+    pub polled_at: u64, // 客户端最近拉取时间 second 各实例按间隔写入
     pub creator_user: u32,
     pub deleted_at: u64,
+    #[serde(skip)]
+    pub delete_batch: u64, // 删除批次 随其删除的关联数据使用相同的批次
     pub created_at: DateTimeWithTimeZone, // 创建时间
     pub updated_at: DateTimeWithTimeZone, // 更新时间
 }
@@ -29,6 +45,18 @@ impl RelationTrait for Relation {
 
 impl ActiveModelBehavior for ActiveModel {}
 
+#[derive(Debug, Clone, PartialEq, Default, EnumIter, DeriveActiveEnum, Deserialize, Serialize)]
+#[sea_orm(rs_type = "u8", db_type = "TinyUnsigned")]
+pub enum EditLock {
+    #[default]
+    #[sea_orm(num_value = 0)]
+    #[serde(rename = "disable")]
+    Disable,
+    #[sea_orm(num_value = 1)]
+    #[serde(rename = "enable")]
+    Enable,
+}
+
 #[derive(FromQueryResult, Serialize, Debug)]
 pub struct NamespaceItem {
     #[serde(serialize_with = "super::confuse")]
@@ -36,7 +64,7 @@ pub struct NamespaceItem {
     pub namespace: String,
 }
 
-#[derive(FromQueryResult, Serialize, Debug)]
+#[derive(FromQueryResult, Serialize, Debug, Clone)]
 pub struct NamespaceInfo {
     #[serde(serialize_with = "super::confuse")]
     pub id: u64,
@@ -44,3 +72,34 @@ pub struct NamespaceInfo {
     pub cluster: String,
     pub namespace: String,
 }
+
+#[derive(FromQueryResult, Serialize, Default, Debug, Clone)]
+pub struct KeyPolicy {
+    pub key_pattern: String,
+    pub key_reserved_prefix: String,
+    pub key_max_depth: u8,
+}
+
+#[derive(FromQueryResult, Serialize, Debug, Clone)]
+pub struct NamespaceLock {
+    pub edit_lock: EditLock,
+    #[serde(serialize_with = "super::confuse")]
+    pub lock_user_id: u32,
+    pub lock_expired_at: u64,
+}
+
+#[derive(FromQueryResult, Serialize, Debug, Clone)]
+pub struct NamespaceRetention {
+    #[serde(serialize_with = "super::confuse")]
+    pub id: u64,
+    pub retain_count: u32,
+    pub retain_days: u32,
+}
+
+#[derive(FromQueryResult, Serialize, Debug, Clone)]
+pub struct DeletedNamespace {
+    #[serde(serialize_with = "super::confuse")]
+    pub id: u64,
+    pub namespace: String,
+    pub deleted_at: u64,
+}
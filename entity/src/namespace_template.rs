@@ -0,0 +1,61 @@
+use super::common::{ItemCategory, Scope};
+
+use sea_orm::{entity::prelude::*, FromQueryResult};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Deserialize, Serialize)]
+#[sea_orm(table_name = "namespace_template")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    #[serde(serialize_with = "super::confuse")]
+    pub id: u64,
+    pub app_id: String,      // 所属应用 拥有应用查看权限的用户可使用
+    pub name: String,        // 模板名称
+    pub description: String, // 模板描述
+    pub scope: Scope,
+    pub key_pattern: String,         // key 的正则规则
+    pub key_reserved_prefix: String, // 保留的 key 前缀 逗号分隔
+    pub key_max_depth: u8,           // key 以 . 分隔的最大层级 0为不限制
+    #[serde(skip)]
+    pub items: String, // json 格式的 TemplateItem 列表
+    #[serde(serialize_with = "super::confuse")]
+    pub creator_user: u32,
+    #[serde(skip)]
+    pub deleted_at: u64,
+    pub created_at: DateTimeWithTimeZone, // 创建时间
+    pub updated_at: DateTimeWithTimeZone, // 更新时间
+}
+
+#[derive(Copy, Clone, Debug, EnumIter)]
+pub enum Relation {}
+impl RelationTrait for Relation {
+    fn def(&self) -> RelationDef {
+        panic!("No RelationDef")
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+// 模板中的 key 及默认值
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TemplateItem {
+    pub key: String,
+    #[serde(default)]
+    pub value: String, // 创建模板时未保存值则为空
+    pub category: ItemCategory,
+    #[serde(default)]
+    pub remark: String,
+}
+
+#[derive(FromQueryResult, Serialize, Debug, Clone)]
+pub struct TemplateInfo {
+    #[serde(serialize_with = "super::confuse")]
+    pub id: u64,
+    pub app_id: String,
+    pub name: String,
+    pub description: String,
+    pub scope: Scope,
+    #[serde(serialize_with = "super::confuse")]
+    pub creator_user: u32,
+    pub created_at: DateTimeWithTimeZone,
+}
@@ -0,0 +1,97 @@
+use sea_orm::{entity::prelude::*, FromQueryResult};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Deserialize, Serialize)]
+#[sea_orm(table_name = "publish_request")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    #[serde(serialize_with = "super::confuse")]
+    pub id: u64,
+    #[serde(serialize_with = "super::confuse")]
+    pub namespace_id: u64,
+    pub name: String,   // 发布名称
+    pub remark: String, // 发布说明
+    pub items: String,  // 待发布的 item 及版本
+    pub change: String, // 申请时与线上配置的差异
+    #[serde(serialize_with = "super::confuse")]
+    pub base_release_id: u64, // 申请时的线上发布
+    #[serde(serialize_with = "super::confuse")]
+    pub rollback_release_id: u64, // 回滚申请的目标发布 0为普通发布
+    pub status: RequestStatus,
+    #[serde(serialize_with = "super::confuse")]
+    pub request_user_id: u32, // 申请人
+    #[serde(serialize_with = "super::confuse")]
+    pub review_user_id: u32, // 审批人
+    pub review_remark: String,
+    #[serde(serialize_with = "super::confuse")]
+    pub release_id: u64, // 审批通过后生成的发布
+    pub deleted_at: u64,
+    #[serde(skip)]
+    pub delete_batch: u64, // 随 namespace 删除的批次 0为未随 namespace 删除
+    pub created_at: DateTimeWithTimeZone, // 创建时间
+    pub updated_at: DateTimeWithTimeZone, // 更新时间
+}
+
+#[derive(Copy, Clone, Debug, EnumIter)]
+pub enum Relation {
+    Users,
+}
+
+impl RelationTrait for Relation {
+    fn def(&self) -> RelationDef {
+        match self {
+            Self::Users => Entity::belongs_to(super::UsersEntity)
+                .from(Column::RequestUserId)
+                .to(super::UsersColumn::Id)
+                .into(),
+        }
+    }
+}
+impl Related<super::UsersEntity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Users.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+#[derive(Debug, Clone, PartialEq, Default, EnumIter, DeriveActiveEnum, Deserialize, Serialize)]
+#[sea_orm(rs_type = "u8", db_type = "TinyUnsigned")]
+pub enum RequestStatus {
+    #[default]
+    #[sea_orm(num_value = 0)]
+    #[serde(rename = "pending")]
+    Pending,
+    #[sea_orm(num_value = 1)]
+    #[serde(rename = "approved")]
+    Approved,
+    #[sea_orm(num_value = 2)]
+    #[serde(rename = "rejected")]
+    Rejected,
+    #[sea_orm(num_value = 3)]
+    #[serde(rename = "cancelled")]
+    Cancelled,
+}
+
+#[derive(FromQueryResult, Serialize, Debug, Clone)]
+pub struct RequestItem {
+    #[serde(serialize_with = "super::confuse")]
+    pub id: u64,
+    #[serde(serialize_with = "super::confuse")]
+    pub namespace_id: u64,
+    pub name: String,
+    pub remark: String,
+    pub change: String,
+    #[serde(serialize_with = "super::confuse")]
+    pub rollback_release_id: u64,
+    pub status: RequestStatus,
+    #[serde(serialize_with = "super::confuse")]
+    pub request_user_id: u32,
+    pub nickname: Option<String>,
+    #[serde(serialize_with = "super::confuse")]
+    pub review_user_id: u32,
+    pub review_remark: String,
+    #[serde(serialize_with = "super::confuse")]
+    pub release_id: u64,
+    pub created_at: DateTimeWithTimeZone,
+}
@@ -0,0 +1,90 @@
+use sea_orm::{entity::prelude::*, FromQueryResult};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Deserialize, Serialize)]
+#[sea_orm(table_name = "publish_schedule")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    #[serde(serialize_with = "super::confuse")]
+    pub id: u64,
+    #[serde(serialize_with = "super::confuse")]
+    pub namespace_id: u64,
+    pub name: String,   // 发布名称
+    pub remark: String, // 发布说明
+    pub items: String,  // 待发布的 item 及版本
+    #[serde(skip)]
+    pub base_release_id: u64, // 计划基于的发布ID 执行时已有新的发布则失败
+    pub publish_at: u64, // 计划发布时间
+    pub status: ScheduleStatus,
+    #[serde(skip)]
+    pub claimed_at: u64, // 开始执行时间 执行中超过租期视为中断
+    pub reason: String, // 失败原因
+    #[serde(serialize_with = "super::confuse")]
+    pub release_id: u64, // 发布成功后的发布ID
+    #[serde(serialize_with = "super::confuse")]
+    pub user_id: u32, // 创建人
+    pub deleted_at: u64,
+    pub created_at: DateTimeWithTimeZone, // 创建时间
+    pub updated_at: DateTimeWithTimeZone, // 更新时间
+}
+
+#[derive(Copy, Clone, Debug, EnumIter)]
+pub enum Relation {
+    Users,
+}
+
+impl RelationTrait for Relation {
+    fn def(&self) -> RelationDef {
+        match self {
+            Self::Users => Entity::belongs_to(super::UsersEntity)
+                .from(Column::UserId)
+                .to(super::UsersColumn::Id)
+                .into(),
+        }
+    }
+}
+impl Related<super::UsersEntity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Users.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+#[derive(Debug, Clone, PartialEq, Default, EnumIter, DeriveActiveEnum, Deserialize, Serialize)]
+#[sea_orm(rs_type = "u8", db_type = "TinyUnsigned")]
+pub enum ScheduleStatus {
+    #[default]
+    #[sea_orm(num_value = 0)]
+    #[serde(rename = "waiting")]
+    Waiting,
+    #[sea_orm(num_value = 1)]
+    #[serde(rename = "running")]
+    Running,
+    #[sea_orm(num_value = 2)]
+    #[serde(rename = "published")]
+    Published,
+    #[sea_orm(num_value = 3)]
+    #[serde(rename = "failed")]
+    Failed,
+    #[sea_orm(num_value = 4)]
+    #[serde(rename = "cancelled")]
+    Cancelled,
+}
+
+#[derive(FromQueryResult, Serialize, Debug, Clone)]
+pub struct ScheduleItem {
+    #[serde(serialize_with = "super::confuse")]
+    pub id: u64,
+    pub name: String,
+    pub remark: String,
+    pub publish_at: u64,
+    pub status: ScheduleStatus,
+    pub reason: String,
+    #[serde(serialize_with = "super::confuse")]
+    pub release_id: u64,
+    #[serde(serialize_with = "super::confuse")]
+    pub user_id: u32,
+    pub nickname: Option<String>,
+    pub created_at: DateTimeWithTimeZone,
+}
@@ -0,0 +1,38 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Deserialize, Serialize)]
+#[sea_orm(table_name = "user_recent")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    #[serde(serialize_with = "super::confuse")]
+    pub id: u64,
+    pub user_id: u32,                     // 用户ID
+    pub app_id: u32,                      // 应用ID
+    pub visited_at: u64,                  // 最后访问时间 second
+    pub created_at: DateTimeWithTimeZone, // 创建时间
+    pub updated_at: DateTimeWithTimeZone, // 更新时间
+}
+
+#[derive(Copy, Clone, Debug, EnumIter)]
+pub enum Relation {
+    App,
+}
+
+impl RelationTrait for Relation {
+    fn def(&self) -> RelationDef {
+        match self {
+            Self::App => Entity::belongs_to(super::AppEntity)
+                .from(Column::AppId)
+                .to(super::AppColumn::Id)
+                .into(),
+        }
+    }
+}
+impl Related<super::AppEntity> for Entity {
+    fn to() -> RelationDef {
+        Relation::App.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -12,9 +12,12 @@ pub struct Model {
     pub name: String,
     pub remark: String, // 备注
     pub configurations: String,
+    pub checksum: String,        // 发布配置的校验和
     pub is_abandoned: Effective, // 是否有效
     pub publish_user_id: u32,
     pub deleted_at: u64,
+    #[serde(skip)]
+    pub delete_batch: u64, // 随 namespace 删除的批次 0为未随 namespace 删除
     pub created_at: DateTimeWithTimeZone, // 创建时间
     pub updated_at: DateTimeWithTimeZone, // 更新时间
 }
@@ -29,15 +32,34 @@ pub enum Effective {
 }
 
 #[derive(Copy, Clone, Debug, EnumIter)]
-pub enum Relation {}
+pub enum Relation {
+    Namespace,
+    Users,
+}
+
 impl RelationTrait for Relation {
     fn def(&self) -> RelationDef {
-        panic!("No RelationDef")
+        match self {
+            Self::Namespace => Entity::belongs_to(super::NamespaceEntity)
+                .from(Column::NamespaceId)
+                .to(super::NamespaceColumn::Id)
+                .into(),
+            Self::Users => Entity::belongs_to(super::UsersEntity)
+                .from(Column::PublishUserId)
+                .to(super::UsersColumn::Id)
+                .into(),
+        }
+    }
+}
+impl Related<super::NamespaceEntity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Namespace.def()
     }
 }
 
 impl ActiveModelBehavior for ActiveModel {}
 
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct ReleaseItemVersion {
     pub id: u64,
     pub version: u64,
@@ -47,4 +69,41 @@ pub struct ReleaseItemVersion {
 pub struct ReleaseConfig {
     pub id: u64,
     pub configurations: String,
+    pub checksum: String,
+}
+
+#[derive(FromQueryResult, Debug, Clone)]
+pub struct ReleaseChecksum {
+    pub id: u64,
+    pub namespace_id: u64,
+    pub configurations: String,
+    pub checksum: String,
+}
+
+#[derive(FromQueryResult, Debug, Clone)]
+pub struct ReleaseSize {
+    pub id: u64,
+    pub size: u64,
+}
+
+#[derive(FromQueryResult, Debug, Clone)]
+pub struct NamespaceReleaseSize {
+    pub namespace_id: u64,
+    pub size: u64,
+}
+
+// 清理发布回收的数据
+#[derive(Serialize, Default, Debug, Clone)]
+pub struct Reclaimed {
+    pub releases: u64,  // 发布数量
+    pub histories: u64, // 发布记录数量
+    pub bytes: u64,     // 配置及变更集的字节数
+}
+
+impl Reclaimed {
+    pub fn merge(&mut self, other: &Reclaimed) {
+        self.releases += other.releases;
+        self.histories += other.histories;
+        self.bytes += other.bytes;
+    }
 }
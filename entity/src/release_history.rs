@@ -10,29 +10,61 @@ pub struct Model {
     #[serde(serialize_with = "super::confuse")]
     pub namespace_id: u64,
     pub release_id: u64,
+    pub operation: Operation, // 发布操作
     pub change: String,
     pub deleted_at: u64,
+    #[serde(skip)]
+    pub delete_batch: u64, // 随 namespace 删除的批次 0为未随 namespace 删除
     pub created_at: DateTimeWithTimeZone, // 创建时间
     pub updated_at: DateTimeWithTimeZone, // 更新时间
 }
 
 #[derive(Copy, Clone, Debug, EnumIter)]
-pub enum Relation {}
+pub enum Relation {
+    Release,
+}
 impl RelationTrait for Relation {
     fn def(&self) -> RelationDef {
-        panic!("No RelationDef")
+        match self {
+            Self::Release => Entity::belongs_to(super::ReleaseEntity)
+                .from(Column::ReleaseId)
+                .to(super::ReleaseColumn::Id)
+                .into(),
+        }
+    }
+}
+impl Related<super::ReleaseEntity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Release.def()
     }
 }
 
 impl ActiveModelBehavior for ActiveModel {}
 
-#[derive(FromQueryResult, Default, Serialize, Deserialize, Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Default, EnumIter, DeriveActiveEnum, Deserialize, Serialize)]
+#[sea_orm(rs_type = "u8", db_type = "TinyUnsigned")]
+pub enum Operation {
+    #[default]
+    #[sea_orm(num_value = 0)]
+    #[serde(rename = "publish")]
+    Publish,
+    #[sea_orm(num_value = 1)]
+    #[serde(rename = "rollback")]
+    Rollback,
+}
+
+#[derive(FromQueryResult, Debug, Clone)]
 pub struct HistoryItem {
-    #[serde(serialize_with = "super::confuse")]
     pub id: u64,
-    #[serde(serialize_with = "super::confuse")]
+    pub namespace_id: u64,
     pub release_id: u64,
+    pub operation: Operation,
     pub change: String,
+    pub name: Option<String>,
+    pub remark: Option<String>,
+    pub publish_user_id: Option<u32>,
+    pub nickname: Option<String>,
+    pub created_at: DateTimeWithTimeZone,
 }
 
 #[derive(FromQueryResult)]
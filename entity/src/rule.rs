@@ -55,4 +55,6 @@ pub enum Verb {
     ASSIGN, // 授权
     #[sea_orm(string_value = "Publish")]
     Publish, // 发布
+    #[sea_orm(string_value = "Approve")]
+    Approve, // 审批发布
 }
@@ -0,0 +1,80 @@
+use std::str::FromStr;
+
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Deserialize, Serialize)]
+#[sea_orm(table_name = "webhook")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    #[serde(serialize_with = "super::confuse")]
+    pub id: u64,
+    pub app_id: String,
+    #[serde(serialize_with = "super::confuse")]
+    pub namespace_id: u64, // 为 0 时订阅 app 下所有 namespace
+    pub name: String,
+    pub url: String,
+    #[serde(skip_serializing)]
+    pub secret: String, // 签名密钥
+    pub events: String, // 订阅的事件 逗号分隔
+    #[serde(serialize_with = "super::confuse")]
+    pub creator_user: u32,
+    pub deleted_at: u64,
+    #[serde(skip)]
+    pub delete_batch: u64, // 随 namespace 删除的批次 0为未随 namespace 删除
+    pub created_at: DateTimeWithTimeZone, // 创建时间
+    pub updated_at: DateTimeWithTimeZone, // 更新时间
+}
+
+#[derive(Copy, Clone, Debug, EnumIter)]
+pub enum Relation {}
+
+impl RelationTrait for Relation {
+    fn def(&self) -> RelationDef {
+        panic!("No RelationDef")
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+impl Model {
+    // 是否订阅了事件
+    pub fn subscribed(&self, event: &WebhookEvent) -> bool {
+        self.events
+            .split(',')
+            .any(|e| WebhookEvent::from_str(e).as_ref() == Ok(event))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WebhookEvent {
+    // 发布
+    Publish,
+    // 回滚
+    Rollback,
+    // item 修改
+    Item,
+}
+
+impl WebhookEvent {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Publish => "publish",
+            Self::Rollback => "rollback",
+            Self::Item => "item",
+        }
+    }
+}
+
+impl FromStr for WebhookEvent {
+    type Err = ();
+    fn from_str(str: &str) -> Result<Self, Self::Err> {
+        match str.trim().to_lowercase().as_str() {
+            "publish" => Ok(Self::Publish),
+            "rollback" => Ok(Self::Rollback),
+            "item" => Ok(Self::Item),
+            _ => Err(()),
+        }
+    }
+}
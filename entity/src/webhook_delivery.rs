@@ -0,0 +1,46 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Deserialize, Serialize)]
+#[sea_orm(table_name = "webhook_delivery")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    #[serde(serialize_with = "super::confuse")]
+    pub id: u64,
+    #[serde(serialize_with = "super::confuse")]
+    pub webhook_id: u64,
+    pub event: String,
+    pub payload: String, // 请求内容
+    pub status: DeliveryStatus,
+    pub attempts: u8,                     // 已请求次数
+    pub response_code: u16,               // 最后一次请求的状态码 请求失败时为 0
+    pub response: String,                 // 最后一次请求的响应或错误信息
+    pub created_at: DateTimeWithTimeZone, // 创建时间
+    pub updated_at: DateTimeWithTimeZone, // 更新时间
+}
+
+#[derive(Copy, Clone, Debug, EnumIter)]
+pub enum Relation {}
+
+impl RelationTrait for Relation {
+    fn def(&self) -> RelationDef {
+        panic!("No RelationDef")
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+#[derive(Debug, Clone, PartialEq, Default, EnumIter, DeriveActiveEnum, Deserialize, Serialize)]
+#[sea_orm(rs_type = "u8", db_type = "TinyUnsigned")]
+pub enum DeliveryStatus {
+    #[default]
+    #[sea_orm(num_value = 0)]
+    #[serde(rename = "pending")]
+    Pending,
+    #[sea_orm(num_value = 1)]
+    #[serde(rename = "success")]
+    Success,
+    #[sea_orm(num_value = 2)]
+    #[serde(rename = "failed")]
+    Failed,
+}
@@ -4,6 +4,9 @@ use once_cell::sync::OnceCell;
 
 static CONF: OnceCell<Config> = OnceCell::new();
 
+/// The default max depth of dotted key (unlimited), used when `PILOT_KEY_MAX_DEPTH` is unset or invalid
+pub const DEFAULT_KEY_MAX_DEPTH: u8 = 0;
+
 #[derive(Debug, Clone)]
 /// The global configuration
 pub struct Config {
@@ -18,6 +21,27 @@ pub struct Config {
     pub harsh: HarshConfig,
 
     pub jwt_secret: String,
+
+    /// The default key policy of new namespace
+    pub key_policy: KeyPolicyConfig,
+
+    /// The item configuration
+    pub item: ItemConfig,
+
+    /// The namespace configuration
+    pub namespace: NamespaceConfig,
+
+    /// The publication configuration
+    pub publish: PublishConfig,
+
+    /// The webhook configuration
+    pub webhook: WebhookConfig,
+
+    /// The release retention configuration
+    pub retention: RetentionConfig,
+
+    /// The default namespace quota
+    pub quota: QuotaConfig,
 }
 
 pub fn get_store() -> &'static StoreConfig {
@@ -40,6 +64,34 @@ pub fn get_jwt_secret() -> &'static String {
     &CONF.get().unwrap().jwt_secret
 }
 
+pub fn get_key_policy() -> &'static KeyPolicyConfig {
+    &CONF.get().unwrap().key_policy
+}
+
+pub fn get_item() -> &'static ItemConfig {
+    &CONF.get().unwrap().item
+}
+
+pub fn get_namespace() -> &'static NamespaceConfig {
+    &CONF.get().unwrap().namespace
+}
+
+pub fn get_publish() -> &'static PublishConfig {
+    &CONF.get().unwrap().publish
+}
+
+pub fn get_webhook() -> &'static WebhookConfig {
+    &CONF.get().unwrap().webhook
+}
+
+pub fn get_retention() -> &'static RetentionConfig {
+    &CONF.get().unwrap().retention
+}
+
+pub fn get_quota() -> &'static QuotaConfig {
+    &CONF.get().unwrap().quota
+}
+
 impl Config {
     pub fn init_env() {
         let addr = env::var("PILOT_LISTEN_ADDR").unwrap_or("0.0.0.0:8000".to_owned());
@@ -70,6 +122,62 @@ impl Config {
         let jwt_secret =
             env::var("PILOT_JWT_SECRET").unwrap_or("qpwoeirutyalskdjfhgmznxbcv".to_owned());
 
+        let key_pattern = env::var("PILOT_KEY_PATTERN").unwrap_or_default();
+        regex::Regex::new(&key_pattern).expect("Specify a valid PILOT_KEY_PATTERN");
+        let key_reserved_prefix = env::var("PILOT_KEY_RESERVED_PREFIX").unwrap_or_default();
+        let key_max_depth = env::var("PILOT_KEY_MAX_DEPTH")
+            .map(|s| s.parse::<u8>().unwrap_or(DEFAULT_KEY_MAX_DEPTH))
+            .unwrap_or(DEFAULT_KEY_MAX_DEPTH);
+
+        let file_max_size = env::var("PILOT_ITEM_FILE_MAX_SIZE")
+            .map(|s| s.parse::<usize>().unwrap_or(32768))
+            .unwrap_or(32768);
+        let lock_ttl = env::var("PILOT_NAMESPACE_LOCK_TTL")
+            .map(|s| s.parse::<u64>().unwrap_or(1800))
+            .unwrap_or(1800);
+        let restore_ttl = env::var("PILOT_NAMESPACE_RESTORE_TTL")
+            .map(|s| s.parse::<u64>().unwrap_or(604800))
+            .unwrap_or(604800);
+        let schedule_interval = env::var("PILOT_PUBLISH_SCHEDULE_INTERVAL")
+            .map(|s| s.parse::<u64>().unwrap_or(10))
+            .unwrap_or(10);
+        let schedule_lease = env::var("PILOT_PUBLISH_SCHEDULE_LEASE")
+            .map(|s| s.parse::<u64>().unwrap_or(600))
+            .unwrap_or(600);
+        let webhook_retry = env::var("PILOT_WEBHOOK_RETRY")
+            .map(|s| s.parse::<u8>().unwrap_or(3))
+            .unwrap_or(3);
+        let webhook_backoff = env::var("PILOT_WEBHOOK_BACKOFF")
+            .map(|s| s.parse::<u64>().unwrap_or(5))
+            .unwrap_or(5);
+        let webhook_timeout = env::var("PILOT_WEBHOOK_TIMEOUT")
+            .map(|s| s.parse::<u64>().unwrap_or(10))
+            .unwrap_or(10);
+        let webhook_allow_private = env::var("PILOT_WEBHOOK_ALLOW_PRIVATE")
+            .map(|s| s == "true" || s == "1")
+            .unwrap_or(false);
+        let retain_count = env::var("PILOT_RETAIN_COUNT")
+            .map(|s| s.parse::<u32>().unwrap_or(0))
+            .unwrap_or(0);
+        let retain_days = env::var("PILOT_RETAIN_DAYS")
+            .map(|s| s.parse::<u32>().unwrap_or(0))
+            .unwrap_or(0);
+        let purge_after = env::var("PILOT_RETAIN_PURGE_AFTER")
+            .map(|s| s.parse::<u64>().unwrap_or(604800))
+            .unwrap_or(604800);
+        let prune_interval = env::var("PILOT_RETAIN_PRUNE_INTERVAL")
+            .map(|s| s.parse::<u64>().unwrap_or(3600))
+            .unwrap_or(3600);
+        let quota_item_count = env::var("PILOT_QUOTA_ITEM_COUNT")
+            .map(|s| s.parse::<u32>().unwrap_or(0))
+            .unwrap_or(0);
+        let quota_value_size = env::var("PILOT_QUOTA_VALUE_SIZE")
+            .map(|s| s.parse::<u32>().unwrap_or(0))
+            .unwrap_or(0);
+        let quota_release_size = env::var("PILOT_QUOTA_RELEASE_SIZE")
+            .map(|s| s.parse::<u64>().unwrap_or(0))
+            .unwrap_or(0);
+
         let conf = Self {
             server: ServerConfig { addr },
             log: LogConfig { level: log_level },
@@ -86,6 +194,37 @@ impl Config {
                 slat: hasher_slat,
             },
             jwt_secret,
+            key_policy: KeyPolicyConfig {
+                pattern: key_pattern,
+                reserved_prefix: key_reserved_prefix,
+                max_depth: key_max_depth,
+            },
+            item: ItemConfig { file_max_size },
+            namespace: NamespaceConfig {
+                lock_ttl,
+                restore_ttl,
+            },
+            publish: PublishConfig {
+                schedule_interval,
+                schedule_lease,
+            },
+            webhook: WebhookConfig {
+                retry: webhook_retry,
+                backoff: webhook_backoff,
+                timeout: webhook_timeout,
+                allow_private: webhook_allow_private,
+            },
+            retention: RetentionConfig {
+                count: retain_count,
+                days: retain_days,
+                purge_after,
+                prune_interval,
+            },
+            quota: QuotaConfig {
+                item_count: quota_item_count,
+                value_size: quota_value_size,
+                release_size: quota_release_size,
+            },
         };
         tracing::info!("load config: {:?}", &conf);
         CONF.set(conf).ok().unwrap();
@@ -116,6 +255,72 @@ pub struct DatabaseCluster {
     pub max_lifetime: u64,
 }
 
+#[derive(Debug, Clone)]
+pub struct KeyPolicyConfig {
+    /// The regular expression of key, empty is unrestricted
+    pub pattern: String,
+    /// The reserved key prefixes, separated by comma
+    pub reserved_prefix: String,
+    /// The max depth of dotted key, 0 is unlimited, defaults to [`DEFAULT_KEY_MAX_DEPTH`]
+    pub max_depth: u8,
+}
+
+#[derive(Debug, Clone)]
+pub struct ItemConfig {
+    /// The max decoded size of file item in bytes
+    pub file_max_size: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct NamespaceConfig {
+    /// The lease of namespace edit lock in seconds
+    pub lock_ttl: u64,
+    /// The seconds a deleted namespace can be restored within
+    pub restore_ttl: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct PublishConfig {
+    /// The interval of checking due scheduled publishes in seconds
+    pub schedule_interval: u64,
+    /// The seconds after which an interrupted running scheduled publish is reclaimed
+    pub schedule_lease: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct WebhookConfig {
+    /// The max retries of a failed delivery
+    pub retry: u8,
+    /// The initial backoff between retries in seconds, doubled on each retry
+    pub backoff: u64,
+    /// The request timeout in seconds
+    pub timeout: u64,
+    /// Whether webhooks may target loopback, link-local and private addresses
+    pub allow_private: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct RetentionConfig {
+    /// The default number of latest releases to keep, 0 is unlimited
+    pub count: u32,
+    /// The default number of days to keep releases, 0 is unlimited
+    pub days: u32,
+    /// The seconds before pruned releases are deleted permanently
+    pub purge_after: u64,
+    /// The interval of pruning releases in seconds
+    pub prune_interval: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct QuotaConfig {
+    /// The default max number of items in a namespace, 0 is unlimited
+    pub item_count: u32,
+    /// The default max size of an item value in bytes, 0 is unlimited
+    pub value_size: u32,
+    /// The default max size of a namespace release in bytes, 0 is unlimited
+    pub release_size: u64,
+}
+
 #[derive(Debug, Clone)]
 pub struct LogConfig {
     /// The logging level
@@ -26,6 +26,9 @@ fn main() {
 
     rumtime.block_on(async {
         init_store(&config::get_store()).await;
+        web::api::backend::schedule::run_executor();
+        web::api::backend::retention::run_pruner();
+        web::api::backend::webhook::run_redeliver();
 
         let router = web::route::init_router().await;
         let svc = config::get_server();
@@ -1,17 +1,23 @@
+use std::str::FromStr;
+
+use super::cluster;
+use crate::config;
 use crate::web::api::check;
 use crate::web::api::permission::accredit;
 use crate::web::extract::json::ReqJson;
 use crate::web::extract::jwt::Claims;
 use crate::web::extract::query::ReqQuery;
 use crate::web::extract::response::{APIError, ApiResponse, Empty, ParamErrType};
-use crate::web::store::dao::{app, department};
+use crate::web::store::cache::CacheItem;
+use crate::web::store::dao::{app, app_member, department, namespace, users};
 use crate::web::APIResult;
 
-use axum::extract::Json;
+use axum::extract::{Extension, Json};
 use chrono::Local;
-use entity::common::Status;
+use entity::app_member::{MemberItem, MemberRole};
 use entity::orm::{ActiveModelTrait, IntoActiveModel, Set};
-use entity::{AppActive, AppModel, ID};
+use entity::users::UserLevel;
+use entity::AppModel;
 use serde::Deserialize;
 
 #[derive(Deserialize)]
@@ -52,8 +58,6 @@ pub async fn create(
 pub struct EditParam {
     pub app_id: Option<String>,
     pub name: Option<String>,
-    pub dept_id: Option<String>,
-    pub status: Option<String>,
 }
 
 pub async fn edit(
@@ -62,7 +66,7 @@ pub async fn edit(
 ) -> APIResult<Json<ApiResponse<AppModel>>> {
     let app_id = check::id_str(param.app_id, "app_id")?;
     let info = app::get_info(app_id.clone()).await?;
-    if info.is_none() {
+    if info.is_none() || info.as_ref().unwrap().deleted_at != 0 {
         return Err(APIError::new_param_err(ParamErrType::NotExist, "app_id"));
     }
     if !accredit::accredit(&auth, entity::rule::Verb::Modify, vec![&app_id]).await? {
@@ -79,40 +83,217 @@ pub async fn edit(
             active.name = Set(name);
         }
     }
-    if let Some(dept_id) = param.dept_id {
-        let dept_id = check::id_decode_rule::<u32>(&dept_id, "dept_id")?;
-        if dept_id != info.dept_id {
-            if !department::is_exist_id(dept_id).await? {
-                return Err(APIError::new_param_err(ParamErrType::NotExist, "dept_id"));
-            }
-            active.dept_id = Set(dept_id);
-        }
+    if !active.is_changed() {
+        return Ok(Json(ApiResponse::ok_data(info)));
     }
+    let model = app::update(active).await?;
+    Ok(Json(ApiResponse::ok_data(model)))
+}
 
-    if let Some(status) = param.status {
-        match Status::from(status) {
-            Status::Other => (),
-            Status::Normal => {
-                // 状态为delete 更改为正常
-                if info.deleted_at != 0 {
-                    active.deleted_at = Set(0);
-                }
-            }
-            Status::Delete => {
-                if info.deleted_at == 0 {
-                    active.deleted_at = Set(Local::now().timestamp() as u64);
-                }
-            }
-        }
-    };
+#[derive(Deserialize)]
+pub struct TransferParam {
+    pub app_id: Option<String>,
+    pub dept_id: Option<String>,
+}
 
-    if !active.is_changed() {
+// 将应用转移到其他部门 仅超级管理员可操作
+pub async fn transfer(
+    ReqJson(param): ReqJson<TransferParam>,
+    auth: Claims,
+) -> APIResult<Json<ApiResponse<AppModel>>> {
+    if !accredit::acc_admin(&auth) {
+        return Err(APIError::new_permission_forbidden());
+    }
+    let app_id = check::id_str(param.app_id, "app_id")?;
+    let dept_id = check::id_decode::<u32>(param.dept_id, "dept_id")?;
+    let info = app::get_info(app_id).await?;
+    if info.is_none() || info.as_ref().unwrap().deleted_at != 0 {
+        return Err(APIError::new_param_err(ParamErrType::NotExist, "app_id"));
+    }
+    let info = info.unwrap();
+    if info.dept_id == dept_id {
         return Ok(Json(ApiResponse::ok_data(info)));
     }
+    if !department::is_exist_id(dept_id).await? {
+        return Err(APIError::new_param_err(ParamErrType::NotExist, "dept_id"));
+    }
+    let mut active = info.into_active_model();
+    active.dept_id = Set(dept_id);
     let model = app::update(active).await?;
     Ok(Json(ApiResponse::ok_data(model)))
 }
 
+#[derive(Deserialize)]
+pub struct AppDeleteParam {
+    pub app_id: Option<String>,
+    pub force: Option<bool>, // 近期有客户端拉取时仍然执行
+}
+
+// 删除应用 应用下的集群、namespace、成员及收藏一同删除
+pub async fn delete(
+    Extension(cache): Extension<CacheItem>,
+    ReqJson(param): ReqJson<AppDeleteParam>,
+    auth: Claims,
+) -> APIResult<Json<ApiResponse<Empty>>> {
+    let app_id = check::id_str(param.app_id, "app_id")?;
+    if !accredit::acc_app_admin(&auth, &app_id).await? {
+        return Err(APIError::new_permission_forbidden());
+    }
+    let info = app::get_info(app_id.clone()).await?;
+    if info.is_none() || info.as_ref().unwrap().deleted_at != 0 {
+        return Err(APIError::new_param_err(ParamErrType::NotExist, "app_id"));
+    }
+    let namespace_ids = namespace::get_app_namespace_ids(app_id, 0).await?;
+    if !param.force.unwrap_or_default() {
+        cluster::in_use(&cache, &namespace_ids).await?;
+    }
+    let now = Local::now().timestamp() as u64;
+    if !app::delete(info.unwrap(), namespace_ids, now).await? {
+        return Err(APIError::new_param_err(ParamErrType::NotExist, "app_id"));
+    }
+    Ok(Json(ApiResponse::ok()))
+}
+
+#[derive(Deserialize)]
+pub struct AppRestoreParam {
+    pub app_id: Option<String>,
+}
+
+// 恢复已删除的应用 仅超级管理员及应用所属部门的部门管理员可操作
+pub async fn restore(
+    ReqJson(param): ReqJson<AppRestoreParam>,
+    auth: Claims,
+) -> APIResult<Json<ApiResponse<Empty>>> {
+    let app_id = check::id_str(param.app_id, "app_id")?;
+    let deleted = app::find_deleted(app_id.clone()).await?;
+    if deleted.is_none() {
+        return Err(APIError::new_param_err(ParamErrType::NotExist, "app_id"));
+    }
+    let deleted = deleted.unwrap();
+    let allowed = match auth.user_level {
+        UserLevel::Admin => true,
+        UserLevel::DeptAdmin => deleted.dept_id == auth.dept_id,
+        UserLevel::Normal => false,
+    };
+    if !allowed {
+        return Err(APIError::new_permission_forbidden());
+    }
+    // 超过恢复期限
+    let now = Local::now().timestamp() as u64;
+    if deleted.deleted_at + config::get_namespace().restore_ttl < now {
+        return Err(APIError::new_param_err(ParamErrType::NotExist, "app_id"));
+    }
+    // 删除后已创建同名应用
+    if app::is_exist(app_id.clone()).await? {
+        return Err(APIError::new_param_err(ParamErrType::Exist, "app_id"));
+    }
+    let namespace_ids = namespace::get_app_namespace_ids(app_id, deleted.deleted_at).await?;
+    if !app::restore(deleted, namespace_ids).await? {
+        return Err(APIError::new_param_err(ParamErrType::Changed, "app_id"));
+    }
+    Ok(Json(ApiResponse::ok()))
+}
+
+#[derive(Deserialize)]
+pub struct MemberQueryParam {
+    pub app_id: Option<String>,
+}
+
+// 获取应用 owner 及 maintainer 列表
+pub async fn members(
+    ReqQuery(param): ReqQuery<MemberQueryParam>,
+    auth: Claims,
+) -> APIResult<Json<ApiResponse<Vec<MemberItem>>>> {
+    let app_id = check::id_str(param.app_id, "app_id")?;
+    if !accredit::accredit(&auth, entity::rule::Verb::VIEW, vec![&app_id]).await? {
+        return Err(APIError::new_permission_forbidden());
+    }
+    let list = app_member::find_by_app(app_id).await?;
+    Ok(Json(ApiResponse::ok_data(list)))
+}
+
+#[derive(Deserialize)]
+pub struct MemberParam {
+    pub app_id: Option<String>,
+    pub user_id: Option<String>,
+    pub role: Option<String>, // owner | maintainer
+}
+
+// 添加应用成员 成员已存在时修改其角色
+pub async fn add_member(
+    ReqJson(param): ReqJson<MemberParam>,
+    auth: Claims,
+) -> APIResult<Json<ApiResponse<Empty>>> {
+    let app_id = check::id_str(param.app_id, "app_id")?;
+    let user_id = check::id_decode::<u32>(param.user_id, "user_id")?;
+    let role = match param.role {
+        Some(role) => MemberRole::from_str(&role)
+            .map_err(|_| APIError::new_param_err(ParamErrType::Invalid, "role"))?,
+        None => MemberRole::default(),
+    };
+    if !accredit::acc_app_admin(&auth, &app_id).await? {
+        return Err(APIError::new_permission_forbidden());
+    }
+    if !app::is_exist(app_id.clone()).await? {
+        return Err(APIError::new_param_err(ParamErrType::NotExist, "app_id"));
+    }
+    match users::get_info(user_id).await? {
+        Some(user) if user.deleted_at == 0 => (),
+        _ => return Err(APIError::new_param_err(ParamErrType::NotExist, "user_id")),
+    }
+    match app_member::get_member(app_id.clone(), user_id).await? {
+        Some(member) => {
+            if member.role == role {
+                return Ok(Json(ApiResponse::ok()));
+            }
+            if member.role == MemberRole::Owner {
+                last_owner(&app_id).await?;
+            }
+            app_member::update_role(member.id, role).await?;
+        }
+        None => {
+            app_member::add(app_id, user_id, role, auth.user_id).await?;
+        }
+    }
+    Ok(Json(ApiResponse::ok()))
+}
+
+#[derive(Deserialize)]
+pub struct MemberRemoveParam {
+    pub app_id: Option<String>,
+    pub user_id: Option<String>,
+}
+
+// 移除应用成员
+pub async fn remove_member(
+    ReqJson(param): ReqJson<MemberRemoveParam>,
+    auth: Claims,
+) -> APIResult<Json<ApiResponse<Empty>>> {
+    let app_id = check::id_str(param.app_id, "app_id")?;
+    let user_id = check::id_decode::<u32>(param.user_id, "user_id")?;
+    if !accredit::acc_app_admin(&auth, &app_id).await? {
+        return Err(APIError::new_permission_forbidden());
+    }
+    let member = app_member::get_member(app_id.clone(), user_id).await?;
+    if member.is_none() {
+        return Err(APIError::new_param_err(ParamErrType::NotExist, "user_id"));
+    }
+    let member = member.unwrap();
+    if member.role == MemberRole::Owner {
+        last_owner(&app_id).await?;
+    }
+    app_member::remove(member.id, Local::now().timestamp() as u64).await?;
+    Ok(Json(ApiResponse::ok()))
+}
+
+// 应用至少保留一个 owner
+async fn last_owner(app_id: &str) -> APIResult<()> {
+    if app_member::owner_count(app_id.to_owned()).await? <= 1 {
+        return Err(APIError::new_param_err(ParamErrType::Invalid, "user_id"));
+    }
+    Ok(())
+}
+
 #[derive(Deserialize)]
 pub struct QueryParam {
     pub page: Option<String>,
@@ -0,0 +1,358 @@
+use std::str::FromStr;
+
+use super::dao::{cluster, environment, namespace, publish_request, release, rule};
+use super::publication::{prepare, rollback_change, PublishResult, PublishStatus};
+use super::response::{APIError, ApiResponse, ParamErrType};
+use super::webhook;
+use super::APIResult;
+use super::{check, diff, ReqJson, ReqQuery};
+use crate::web::api::permission::accredit;
+use crate::web::extract::jwt::Claims;
+use crate::web::extract::response::APIErrorType;
+
+use axum::extract::Json;
+use entity::namespace::NamespaceInfo;
+use entity::orm::Set;
+use entity::publish_request::{RequestItem, RequestStatus};
+use entity::release::ReleaseItemVersion;
+use entity::rule::Verb;
+use entity::webhook::WebhookEvent;
+use entity::{Approval, PublishRequestActive, PublishRequestModel, ID};
+use serde::Deserialize;
+
+// namespace 发布是否需要审批, namespace 未设置时使用集群的设置, 集群未设置时使用所属环境的设置
+pub async fn approval_required(info: &NamespaceInfo) -> APIResult<bool> {
+    match namespace::get_approval(info.id).await? {
+        Some(Approval::Enable) => return Ok(true),
+        Some(Approval::Disable) => return Ok(false),
+        _ => (),
+    }
+    match cluster::get_approval(info.app_id.clone(), info.cluster.clone()).await? {
+        Some(Approval::Enable) => return Ok(true),
+        Some(Approval::Disable) => return Ok(false),
+        _ => (),
+    }
+    let approval = match cluster::get_env(info.app_id.clone(), info.cluster.clone()).await? {
+        Some(env) => environment::get_approval(env).await?,
+        None => None,
+    };
+    Ok(approval == Some(Approval::Enable))
+}
+
+// 解析审批设置
+pub fn approval(approval: Option<String>) -> APIResult<Approval> {
+    match approval {
+        Some(approval) => Approval::from_str(&approval)
+            .map_err(|_| APIError::new_param_err(ParamErrType::Invalid, "approval")),
+        None => Err(APIError::new_param_err(ParamErrType::Required, "approval")),
+    }
+}
+
+#[derive(Deserialize)]
+pub struct RequestQueryParam {
+    pub id: Option<String>, // namespace ID
+    // 未指定 namespace 时获取应用下指定环境集群的所有 namespace 的申请
+    pub app_id: Option<String>,
+    pub env: Option<String>,
+    pub status: Option<String>,
+    pub page: Option<String>,
+    pub page_size: Option<String>,
+}
+
+// 获取 namespace 或应用下指定环境的发布申请
+pub async fn list(
+    ReqQuery(param): ReqQuery<RequestQueryParam>,
+    auth: Claims,
+) -> APIResult<Json<ApiResponse<Vec<RequestItem>>>> {
+    let status = match param.status.unwrap_or_default().as_str() {
+        "" => None,
+        "pending" => Some(RequestStatus::Pending),
+        "approved" => Some(RequestStatus::Approved),
+        "rejected" => Some(RequestStatus::Rejected),
+        "cancelled" => Some(RequestStatus::Cancelled),
+        _ => return Err(APIError::new_param_err(ParamErrType::Invalid, "status")),
+    };
+    let (page, page_size) = check::page(param.page, param.page_size);
+    if param.id.is_none() && param.env.is_some() {
+        let app_id = check::id_str(param.app_id, "app_id")?;
+        let env = check::id_str(param.env, "env")?;
+        let list = env_request(&auth, app_id, env, status, page, page_size).await?;
+        let mut rsp = ApiResponse::ok_data(list);
+        rsp.set_page(page, page_size);
+        return Ok(Json(rsp));
+    }
+    let namespace_id = check::id_decode(param.id, "id")?;
+    let info = namespace_info(namespace_id).await?;
+    if !accredit::accredit(
+        &auth,
+        Verb::VIEW,
+        vec![&info.app_id, &info.cluster, &info.namespace],
+    )
+    .await?
+    {
+        return Err(APIError::new_permission_forbidden());
+    }
+    let list = publish_request::get_namespace_request(
+        namespace_id,
+        status,
+        (page - 1) * page_size,
+        page_size,
+    )
+    .await?;
+    let mut rsp = ApiResponse::ok_data(list);
+    rsp.set_page(page, page_size);
+    Ok(Json(rsp))
+}
+
+// 仅获取拥有查看权限的 namespace 的申请
+async fn env_request(
+    auth: &Claims,
+    app_id: String,
+    env: String,
+    status: Option<RequestStatus>,
+    page: u64,
+    page_size: u64,
+) -> APIResult<Vec<RequestItem>> {
+    let resource = if accredit::acc_app_admin(auth, &app_id).await? {
+        None
+    } else {
+        match accredit::accredit_resources(auth, Verb::VIEW).await? {
+            Some(resources) if resources.is_empty() => return Ok(vec![]),
+            Some(resources) => Some(rule::resource_condition(resources)),
+            None => None,
+        }
+    };
+    let list = publish_request::get_env_request(
+        app_id,
+        env,
+        resource,
+        status,
+        (page - 1) * page_size,
+        page_size,
+    )
+    .await?;
+    Ok(list)
+}
+
+#[derive(Deserialize)]
+pub struct ReviewParam {
+    pub id: Option<String>,
+    pub remark: Option<String>,
+}
+
+// 审批通过 生成发布
+pub async fn approve(
+    ReqJson(param): ReqJson<ReviewParam>,
+    auth: Claims,
+) -> APIResult<Json<ApiResponse<PublishResult>>> {
+    let remark = review_remark(param.remark)?;
+    let request = pending_request(param.id).await?;
+    let info = namespace_info(request.namespace_id).await?;
+    reviewable(&request, &info, &auth).await?;
+    let review = PublishRequestActive {
+        review_user_id: Set(auth.user_id),
+        review_remark: Set(remark),
+        ..Default::default()
+    };
+    if request.rollback_release_id != 0 {
+        return approve_rollback(request, info, review).await;
+    }
+
+    let items = match serde_json::from_str::<Vec<ReleaseItemVersion>>(&request.items) {
+        Ok(items) => items,
+        Err(err) => {
+            tracing::error!(
+                "failed to parse publish request items: {}, {:?}",
+                request.items,
+                err
+            );
+            return Err(APIError::new_server_error());
+        }
+    };
+    // 重新校验 item 版本, 申请后有修改时需要重新申请
+    let prepared = prepare(&items).await?;
+    if prepared.info.id != request.namespace_id {
+        return Err(APIError::new_param_err(ParamErrType::Changed, "items"));
+    }
+    // 申请后有新的发布 审批的变更已不是当前将发布的变更
+    if prepared.release_id != request.base_release_id {
+        return Err(APIError::new_param_err(ParamErrType::Changed, "release"));
+    }
+    // 以申请人发布
+    let active = release::new_release(
+        request.namespace_id,
+        request.name,
+        request.remark,
+        &prepared.config,
+        request.request_user_id,
+    )?;
+    let change = serde_json::to_string(&prepared.change).unwrap();
+    let keys = prepared.keys();
+    match publish_request::approve(request.id, review, prepared.release_id, active, change).await? {
+        Some(id) => {
+            webhook::notify(
+                WebhookEvent::Publish,
+                info,
+                Some(id),
+                request.request_user_id,
+                keys,
+            );
+            Ok(Json(ApiResponse::ok_data(PublishResult {
+                status: PublishStatus::Published,
+                id,
+            })))
+        }
+        // 发生新的发布或申请已被处理
+        None => Err(APIError::new_param_err(ParamErrType::Changed, "items")),
+    }
+}
+
+// 审批通过回滚申请 申请后有新的发布时需要重新申请
+async fn approve_rollback(
+    request: PublishRequestModel,
+    info: NamespaceInfo,
+    review: PublishRequestActive,
+) -> APIResult<Json<ApiResponse<PublishResult>>> {
+    let current = release::get_namespace_config(request.namespace_id).await?;
+    let current = match current {
+        Some(current) if current.id == request.base_release_id => current,
+        _ => return Err(APIError::new_param_err(ParamErrType::Changed, "release")),
+    };
+    let target = release::find_by_id(request.rollback_release_id).await?;
+    if target.is_none() {
+        return Err(APIError::new_param_err(ParamErrType::NotExist, "release"));
+    }
+    let target = target.unwrap();
+    let change = rollback_change(
+        &diff::parse_configurations(&current.configurations)?,
+        &diff::parse_configurations(&target.configurations)?,
+    );
+    let keys = change.iter().map(|i| i.key.clone()).collect();
+    let change = serde_json::to_string(&change).unwrap();
+    // 以申请人回滚
+    match publish_request::approve_rollback(
+        request.id,
+        review,
+        current.id,
+        target,
+        request.remark,
+        change,
+        request.request_user_id,
+    )
+    .await?
+    {
+        Some(id) => {
+            webhook::notify(
+                WebhookEvent::Rollback,
+                info,
+                Some(id),
+                request.request_user_id,
+                keys,
+            );
+            Ok(Json(ApiResponse::ok_data(PublishResult {
+                status: PublishStatus::Published,
+                id,
+            })))
+        }
+        // 发生新的发布或申请已被处理
+        None => Err(APIError::new_param_err(ParamErrType::Changed, "release")),
+    }
+}
+
+// 驳回申请
+pub async fn reject(
+    ReqJson(param): ReqJson<ReviewParam>,
+    auth: Claims,
+) -> APIResult<Json<ApiResponse<ID>>> {
+    let remark = review_remark(param.remark)?;
+    let request = pending_request(param.id).await?;
+    let info = namespace_info(request.namespace_id).await?;
+    reviewable(&request, &info, &auth).await?;
+    let active = PublishRequestActive {
+        status: Set(RequestStatus::Rejected),
+        review_user_id: Set(auth.user_id),
+        review_remark: Set(remark),
+        ..Default::default()
+    };
+    if !publish_request::update_pending(request.id, active).await? {
+        return Err(APIError::new_param_err(ParamErrType::Changed, "id"));
+    }
+    Ok(Json(ApiResponse::ok_data(ID::new(request.id))))
+}
+
+#[derive(Deserialize)]
+pub struct CancelParam {
+    pub id: Option<String>,
+}
+
+// 撤回申请 仅申请人可撤回
+pub async fn cancel(
+    ReqJson(param): ReqJson<CancelParam>,
+    auth: Claims,
+) -> APIResult<Json<ApiResponse<ID>>> {
+    let request = pending_request(param.id).await?;
+    if request.request_user_id != auth.user_id {
+        return Err(APIError::new_permission_forbidden());
+    }
+    let active = PublishRequestActive {
+        status: Set(RequestStatus::Cancelled),
+        ..Default::default()
+    };
+    if !publish_request::update_pending(request.id, active).await? {
+        return Err(APIError::new_param_err(ParamErrType::Changed, "id"));
+    }
+    Ok(Json(ApiResponse::ok_data(ID::new(request.id))))
+}
+
+fn review_remark(remark: Option<String>) -> APIResult<String> {
+    let remark = remark.unwrap_or_default();
+    if remark.len() > 255 {
+        return Err(APIError::new_param_err(ParamErrType::Len(0, 255), "remark"));
+    }
+    Ok(remark)
+}
+
+// 获取待审批的申请
+async fn pending_request(id: Option<String>) -> APIResult<PublishRequestModel> {
+    let id = check::id_decode(id, "id")?;
+    let request = publish_request::find_by_id(id).await?;
+    if request.is_none() {
+        return Err(APIError::new_param_err(ParamErrType::NotExist, "id"));
+    }
+    let request = request.unwrap();
+    if request.status != RequestStatus::Pending {
+        return Err(APIError::new_param_err(ParamErrType::Changed, "id"));
+    }
+    Ok(request)
+}
+
+async fn namespace_info(namespace_id: u64) -> APIResult<NamespaceInfo> {
+    match namespace::get_app_info(namespace_id).await? {
+        Some(info) => Ok(info),
+        None => Err(APIError::new_param_err(ParamErrType::NotExist, "namespace")),
+    }
+}
+
+// 校验审批权限 不允许审批自己的申请
+async fn reviewable(
+    request: &PublishRequestModel,
+    info: &NamespaceInfo,
+    auth: &Claims,
+) -> APIResult<()> {
+    if request.request_user_id == auth.user_id {
+        return Err(APIError::with_param(
+            APIErrorType::Forbidden,
+            Some("不允许审批自己的申请".to_owned()),
+        ));
+    }
+    if !accredit::accredit(
+        auth,
+        Verb::Approve,
+        vec![&info.app_id, &info.cluster, &info.namespace],
+    )
+    .await?
+    {
+        return Err(APIError::new_permission_forbidden());
+    }
+    Ok(())
+}
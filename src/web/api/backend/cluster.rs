@@ -1,15 +1,18 @@
 use std::collections::HashSet;
 
-use super::dao::cluster;
+use super::dao::{cluster, namespace};
 use super::response::{APIError, ApiResponse, ParamErrType};
 use super::APIResult;
+use super::{approval, favorite};
 use super::{check, ReqJson, ReqQuery};
 use crate::web::api::permission::accredit;
 use crate::web::extract::jwt::Claims;
 use crate::web::extract::response::Empty;
-use crate::web::store::dao::{app, rule, user_role};
+use crate::web::store::cache::CacheItem;
+use crate::web::store::dao::{app, environment, rule, user_role};
 
-use axum::extract::Json;
+use axum::extract::{Extension, Json};
+use chrono::Local;
 use entity::cluster::ClusterItem;
 use entity::orm::Set;
 use entity::rule::Verb;
@@ -18,6 +21,8 @@ use rand::{distributions::Alphanumeric, Rng};
 use serde::Deserialize;
 
 const SECRET_LEN: usize = 36;
+// 视为近期拉取的时间范围 second
+const RECENT_POLL: u64 = 600;
 
 #[derive(Deserialize, Debug)]
 pub struct ClusterParam {
@@ -25,17 +30,28 @@ pub struct ClusterParam {
     pub cluster: Option<String>,
 }
 
+#[derive(Deserialize, Debug)]
+pub struct ClusterCreateParam {
+    pub app_id: Option<String>,
+    pub cluster: Option<String>,
+    pub env: Option<String>, // 所属环境
+}
+
 // 创建app集群
 pub async fn create(
-    ReqJson(param): ReqJson<ClusterParam>,
+    ReqJson(param): ReqJson<ClusterCreateParam>,
     auth: Claims,
 ) -> APIResult<Json<ApiResponse<Empty>>> {
     // check param
     let cluster = check::id_str(param.cluster, "cluster")?;
     let app_id = check::id_str(param.app_id, "app_id")?;
+    let env = check::id_str(param.env, "env")?;
     if !app::is_exist(app_id.clone()).await? {
         return Err(APIError::new_param_err(ParamErrType::NotExist, "app_id"));
     }
+    if !environment::is_exist(env.clone()).await? {
+        return Err(APIError::new_param_err(ParamErrType::NotExist, "env"));
+    }
     // 校验权限
     if !accredit::accredit(&auth, entity::rule::Verb::Create, vec![&app_id]).await? {
         return Err(APIError::new_permission_forbidden());
@@ -49,6 +65,7 @@ pub async fn create(
     let data = ClusterActive {
         app_id: Set(app_id),
         name: Set(cluster),
+        env: Set(env),
         secret: Set(general_rand_secret()),
         creator_user: Set(auth.user_id),
         ..Default::default()
@@ -57,12 +74,142 @@ pub async fn create(
     Ok(Json(ApiResponse::ok()))
 }
 
+#[derive(Deserialize, Debug)]
+pub struct ClusterEditParam {
+    pub app_id: Option<String>,
+    pub cluster: Option<String>,
+    pub name: Option<String>, // 新的集群名称
+    pub force: Option<bool>,  // 近期有客户端拉取时仍然执行
+}
+
+// 重命名集群
 pub async fn edit(
-    ReqJson(param): ReqJson<ClusterParam>,
+    Extension(cache): Extension<CacheItem>,
+    ReqJson(param): ReqJson<ClusterEditParam>,
+    auth: Claims,
+) -> APIResult<Json<ApiResponse<Empty>>> {
+    let cluster = check::id_str(param.cluster, "cluster")?;
+    let app_id = check::id_str(param.app_id, "app_id")?;
+    let name = check::id_str(param.name, "name")?;
+    if name == cluster {
+        return Err(APIError::new_param_err(ParamErrType::Invalid, "name"));
+    }
+    // 校验权限
+    if !accredit::accredit(&auth, entity::rule::Verb::Modify, vec![&app_id]).await? {
+        return Err(APIError::new_permission_forbidden());
+    }
+    let id = cluster::find_app_cluster(app_id.clone(), cluster.clone())
+        .await?
+        .unwrap_or_default();
+    if id == 0 {
+        return Err(APIError::new_param_err(ParamErrType::NotExist, "cluster"));
+    }
+    if cluster::is_exist(app_id.clone(), name.clone()).await? {
+        return Err(APIError::new_param_err(ParamErrType::Exist, "name"));
+    }
+    let namespace_ids =
+        namespace::get_cluster_namespace_ids(app_id.clone(), cluster.clone()).await?;
+    if !param.force.unwrap_or_default() {
+        in_use(&cache, &namespace_ids).await?;
+    }
+    if !cluster::rename(id, app_id, cluster, name).await? {
+        return Err(APIError::new_param_err(ParamErrType::Changed, "cluster"));
+    }
+    Ok(Json(ApiResponse::ok()))
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ClusterDeleteParam {
+    pub app_id: Option<String>,
+    pub cluster: Option<String>,
+    pub force: Option<bool>, // 近期有客户端拉取时仍然执行
+}
+
+// 删除集群 集群下的 namespace 一同删除
+pub async fn delete(
+    Extension(cache): Extension<CacheItem>,
+    ReqJson(param): ReqJson<ClusterDeleteParam>,
+    auth: Claims,
+) -> APIResult<Json<ApiResponse<Empty>>> {
+    let cluster = check::id_str(param.cluster, "cluster")?;
+    let app_id = check::id_str(param.app_id, "app_id")?;
+    // 仅 app 管理员可删除
+    if !accredit::acc_app_admin(&auth, &app_id).await? {
+        return Err(APIError::new_permission_forbidden());
+    }
+    let id = cluster::find_app_cluster(app_id.clone(), cluster.clone())
+        .await?
+        .unwrap_or_default();
+    if id == 0 {
+        return Err(APIError::new_param_err(ParamErrType::NotExist, "cluster"));
+    }
+    let namespace_ids = namespace::get_cluster_namespace_ids(app_id, cluster).await?;
+    if !param.force.unwrap_or_default() {
+        in_use(&cache, &namespace_ids).await?;
+    }
+    let now = Local::now().timestamp() as u64;
+    if !cluster::delete(id, namespace_ids, now).await? {
+        return Err(APIError::new_param_err(ParamErrType::NotExist, "cluster"));
+    }
+    Ok(Json(ApiResponse::ok()))
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ClusterEnvParam {
+    pub app_id: Option<String>,
+    pub cluster: Option<String>,
+    pub env: Option<String>,
+}
+
+// 修改集群所属环境 仅 app 管理员可操作
+pub async fn edit_env(
+    ReqJson(param): ReqJson<ClusterEnvParam>,
     auth: Claims,
 ) -> APIResult<Json<ApiResponse<Empty>>> {
+    let cluster = check::id_str(param.cluster, "cluster")?;
+    let app_id = check::id_str(param.app_id, "app_id")?;
+    let env = check::id_str(param.env, "env")?;
+    if !accredit::acc_app_admin(&auth, &app_id).await? {
+        return Err(APIError::new_permission_forbidden());
+    }
+    let id = cluster::find_app_cluster(app_id, cluster)
+        .await?
+        .unwrap_or_default();
+    if id == 0 {
+        return Err(APIError::new_param_err(ParamErrType::NotExist, "cluster"));
+    }
+    if !environment::is_exist(env.clone()).await? {
+        return Err(APIError::new_param_err(ParamErrType::NotExist, "env"));
+    }
+    let active = ClusterActive {
+        env: Set(env),
+        ..Default::default()
+    };
+    cluster::update_by_id(active, id).await?;
+    Ok(Json(ApiResponse::ok()))
+}
 
-    Ok(Json(ApiResponse::ok())) 
+// namespace 近期被客户端拉取过或有等待中的客户端时拒绝操作
+// 拉取时间由各实例写入数据库共享, 等待中的客户端仅统计处理本请求的实例
+pub async fn in_use(cache: &CacheItem, namespace_ids: &[u64]) -> APIResult<()> {
+    let now = Local::now().timestamp() as u64;
+    let last = cache
+        .last_polled(namespace_ids)
+        .await
+        .max(namespace::last_polled(namespace_ids.to_vec()).await?);
+    let mut polling = last + RECENT_POLL >= now;
+    for &id in namespace_ids.iter() {
+        if polling {
+            break;
+        }
+        polling = cache.subscriber_count(id).await != 0;
+    }
+    if polling {
+        return Err(APIError::new_locked(
+            "近期有客户端拉取配置, 确认后可强制执行".to_owned(),
+        ));
+    }
+    Ok(())
 }
 
 // 重置密钥接口
@@ -90,9 +237,44 @@ pub async fn reset_secret(
     Ok(Json(ApiResponse::ok()))
 }
 
+#[derive(Deserialize, Debug)]
+pub struct ClusterApprovalParam {
+    pub app_id: Option<String>,
+    pub cluster: Option<String>,
+    pub approval: Option<String>, // enable | disable
+}
+
+// 修改集群的发布审批设置
+pub async fn edit_approval(
+    ReqJson(param): ReqJson<ClusterApprovalParam>,
+    auth: Claims,
+) -> APIResult<Json<ApiResponse<Empty>>> {
+    let cluster = check::id_str(param.cluster, "cluster")?;
+    let app_id = check::id_str(param.app_id, "app_id")?;
+    // inherit 时使用所属环境的设置
+    let approval = approval::approval(param.approval)?;
+    // 校验权限
+    if !accredit::accredit(&auth, entity::rule::Verb::ASSIGN, vec![&app_id, &cluster]).await? {
+        return Err(APIError::new_permission_forbidden());
+    }
+    let id = cluster::find_app_cluster(app_id, cluster)
+        .await?
+        .unwrap_or_default();
+    if id == 0 {
+        return Err(APIError::new_param_err(ParamErrType::NotExist, "cluster"));
+    }
+    let active = ClusterActive {
+        approval: Set(approval),
+        ..Default::default()
+    };
+    cluster::update_by_id(active, id).await?;
+    Ok(Json(ApiResponse::ok()))
+}
+
 #[derive(Deserialize, Debug)]
 pub struct ClusterQueryParam {
     pub app_id: Option<String>,
+    pub env: Option<String>,
     pub page: Option<String>,
     pub page_size: Option<String>,
 }
@@ -102,21 +284,38 @@ pub async fn list(
     auth: Claims,
 ) -> APIResult<Json<ApiResponse<Vec<ClusterItem>>>> {
     let app_id = check::id_str(param.app_id, "app_id")?;
+    let env = match param.env {
+        Some(env) => Some(check::id_str_rule(env, "env")?),
+        None => None,
+    };
+    let list = visible_cluster(&auth, app_id.clone(), env).await?;
+    // 仅记录有权限查看的应用
+    if !list.is_empty() {
+        favorite::visit(auth.user_id, app_id);
+    }
+    Ok(Json(ApiResponse::ok_data(list)))
+}
 
+// 获取用户拥有 View 权限的集群
+async fn visible_cluster(
+    auth: &Claims,
+    app_id: String,
+    env: Option<String>,
+) -> APIResult<Vec<ClusterItem>> {
     // 获取内容
-    let list = cluster::find_cluster_by_app(app_id.clone()).await?;
+    let list = cluster::find_cluster_by_app(app_id.clone(), env).await?;
     // 无内容直接返回
     if list.is_empty() {
-        return Ok(Json(ApiResponse::ok_data(list)));
+        return Ok(list);
     }
-    if accredit::acc_admin(&auth, Some(app_id.clone())) {
-        return Ok(Json(ApiResponse::ok_data(list)));
+    if accredit::acc_app_admin(auth, &app_id).await? {
+        return Ok(list);
     }
     // 获取用户角色ID
     let user_roles = user_role::get_user_role(auth.user_id).await?;
     if user_roles.is_empty() {
         // 返回空
-        return Ok(Json(ApiResponse::ok_data(vec![])));
+        return Ok(vec![]);
     }
     let user_role_set: HashSet<u32> = HashSet::from_iter(user_roles.into_iter());
 
@@ -125,43 +324,55 @@ pub async fn list(
     for r_id in role.iter() {
         // 拥有上级资源权限角色  直接返回
         if user_role_set.contains(r_id) {
-            return Ok(Json(ApiResponse::ok_data(list)));
+            return Ok(list);
         }
     }
 
-    // 获取此资源下级拥有View权限的所有角色
-    let role = rule::get_resource_prefix_role(Verb::VIEW, app_id.clone(), None).await?;
+    // 获取此资源下级及全局环境拥有View权限的所有角色
+    let mut role = rule::get_resource_prefix_role(Verb::VIEW, app_id.clone(), None).await?;
+    role.extend(rule::get_global_env_role(Verb::VIEW).await?);
     if role.is_empty() {
-        return Ok(Json(ApiResponse::ok_data(vec![])));
+        return Ok(vec![]);
     }
 
     let mut rules = HashSet::with_capacity(role.len());
+    let mut envs = HashSet::new();
     for r in role.into_iter() {
         if !user_role_set.contains(&r.role_id) {
             // 用户无此角色
             continue;
         }
+        // 全局环境 @env 拥有所有应用该环境集群的权限
+        if let Some(env) = rule::parse_env(&r.resource) {
+            envs.insert(env.to_owned());
+            continue;
+        }
         let mut rk = rule::parse_resource_kind(r.resource);
         // 仅2级权限资源有权限 1级资源权限之前已经校验
-        // app_id  app_id/cluster 拥有权限
+        // app_id  app_id/cluster app_id/@env 拥有权限
         // app_id/cluster/namespace 没有权限
         if rk.len() != 2 {
             // 下级资源权限过滤
             continue;
         }
-        // 拥有权限的 cluster
-        rk.pop().map(|x| rules.insert(x));
+        // 拥有权限的 cluster 或环境
+        if let Some(x) = rk.pop() {
+            match rule::parse_env(&x) {
+                Some(env) => envs.insert(env.to_owned()),
+                None => rules.insert(x),
+            };
+        }
     }
-    if rules.is_empty() {
+    if rules.is_empty() && envs.is_empty() {
         // 无相关权限
-        return Ok(Json(ApiResponse::ok_data(vec![])));
+        return Ok(vec![]);
     }
 
     let list: Vec<ClusterItem> = list
         .into_iter()
-        .filter(|c| rules.contains(&c.name))
+        .filter(|c| rules.contains(&c.name) || envs.contains(&c.env))
         .collect();
-    Ok(Json(ApiResponse::ok_data(list)))
+    Ok(list)
 }
 
 // 生成密钥
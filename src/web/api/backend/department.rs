@@ -42,7 +42,7 @@ pub async fn create(
     };
 
     // 仅超级管理员可操作
-    if !accredit::acc_admin(&auth, None) {
+    if !accredit::acc_admin(&auth) {
         return Err(APIError::new_permission_forbidden());
     }
 
@@ -0,0 +1,198 @@
+use super::approval;
+use super::dao::{app, cluster, environment, rule, users};
+use super::response::{APIError, ApiResponse, ParamErrType};
+use super::APIResult;
+use super::{check, ReqJson};
+use crate::web::api::permission::accredit;
+use crate::web::extract::jwt::Claims;
+use crate::web::extract::response::Empty;
+
+use axum::extract::Json;
+use chrono::Local;
+use entity::orm::{ActiveModelTrait, Set};
+use entity::rule::Verb;
+use entity::{Approval, EnvironmentActive, EnvironmentModel, ID};
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+pub struct EnvironmentParam {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub approval: Option<String>, // enable | disable
+}
+
+// 创建环境 仅超级管理员可操作
+pub async fn create(
+    ReqJson(param): ReqJson<EnvironmentParam>,
+    auth: Claims,
+) -> APIResult<Json<ApiResponse<ID>>> {
+    let name = check::id_str(param.name, "name")?;
+    let description = description(param.description)?;
+    let approval = match param.approval {
+        Some(approval) => env_approval(Some(approval))?,
+        None => Approval::Disable,
+    };
+    if !accredit::acc_admin(&auth) {
+        return Err(APIError::new_permission_forbidden());
+    }
+    if environment::is_exist(name.clone()).await? {
+        return Err(APIError::new_param_err(ParamErrType::Exist, "name"));
+    }
+    let env = EnvironmentActive {
+        name: Set(name),
+        description: Set(description),
+        approval: Set(approval),
+        ..Default::default()
+    };
+    let id = environment::add(env).await?;
+    Ok(Json(ApiResponse::ok_data(ID::new(id as u64))))
+}
+
+// 修改环境的描述及发布审批设置 仅超级管理员可操作
+pub async fn edit(
+    ReqJson(param): ReqJson<EnvironmentParam>,
+    auth: Claims,
+) -> APIResult<Json<ApiResponse<Empty>>> {
+    let name = check::id_str(param.name, "name")?;
+    if !accredit::acc_admin(&auth) {
+        return Err(APIError::new_permission_forbidden());
+    }
+    let env = environment::get_by_name(name).await?;
+    if env.is_none() {
+        return Err(APIError::new_param_err(ParamErrType::NotExist, "name"));
+    }
+    let mut active = EnvironmentActive {
+        ..Default::default()
+    };
+    if param.description.is_some() {
+        active.description = Set(description(param.description)?);
+    }
+    if param.approval.is_some() {
+        active.approval = Set(env_approval(param.approval)?);
+    }
+    if active.is_changed() {
+        environment::update_by_id(active, env.unwrap().id).await?;
+    }
+    Ok(Json(ApiResponse::ok()))
+}
+
+#[derive(Deserialize)]
+pub struct EnvironmentNameParam {
+    pub name: Option<String>,
+}
+
+// 删除环境 仍有集群属于此环境时不可删除
+pub async fn delete(
+    ReqJson(param): ReqJson<EnvironmentNameParam>,
+    auth: Claims,
+) -> APIResult<Json<ApiResponse<Empty>>> {
+    let name = check::id_str(param.name, "name")?;
+    if !accredit::acc_admin(&auth) {
+        return Err(APIError::new_permission_forbidden());
+    }
+    let env = environment::get_by_name(name.clone()).await?;
+    if env.is_none() {
+        return Err(APIError::new_param_err(ParamErrType::NotExist, "name"));
+    }
+    if cluster::env_in_use(name).await? {
+        return Err(APIError::new_locked("环境下仍有集群".to_owned()));
+    }
+    let now = Local::now().timestamp() as u64;
+    environment::delete(env.unwrap().id, now).await?;
+    Ok(Json(ApiResponse::ok()))
+}
+
+#[derive(Deserialize)]
+pub struct EnvRuleParam {
+    pub env: Option<String>,
+    pub app_id: Option<String>, // 为空时授权所有应用该环境的集群
+    pub verbs: Option<Vec<String>>,
+    pub user_id: Option<String>,
+}
+
+// 授予用户环境的权限
+// 应用的环境由应用管理员授权, 所有应用的环境仅超级管理员可授权
+pub async fn grant(
+    ReqJson(param): ReqJson<EnvRuleParam>,
+    auth: Claims,
+) -> APIResult<Json<ApiResponse<Empty>>> {
+    let env = check::id_str(param.env, "env")?;
+    let app_id = match param.app_id {
+        Some(app_id) => Some(check::id_str_rule(app_id, "app_id")?),
+        None => None,
+    };
+    let verbs = env_verbs(param.verbs)?;
+    let user_id = check::id_decode::<u32>(param.user_id, "user_id")?;
+    let allowed = match &app_id {
+        Some(app_id) => accredit::acc_app_admin(&auth, app_id).await?,
+        None => accredit::acc_admin(&auth),
+    };
+    if !allowed {
+        return Err(APIError::new_permission_forbidden());
+    }
+    if !environment::is_exist(env.clone()).await? {
+        return Err(APIError::new_param_err(ParamErrType::NotExist, "env"));
+    }
+    if let Some(app_id) = &app_id {
+        if !app::is_exist(app_id.clone()).await? {
+            return Err(APIError::new_param_err(ParamErrType::NotExist, "app_id"));
+        }
+    }
+    if users::get_info(user_id).await?.is_none() {
+        return Err(APIError::new_param_err(ParamErrType::NotExist, "user_id"));
+    }
+    let resource = match app_id {
+        Some(app_id) => rule::env_resource(&app_id, &env),
+        None => rule::global_env_resource(&env),
+    };
+    rule::grant(resource, verbs, user_id).await?;
+    Ok(Json(ApiResponse::ok()))
+}
+
+pub async fn list(_: Claims) -> APIResult<Json<ApiResponse<Vec<EnvironmentModel>>>> {
+    let list = environment::find_all().await?;
+    Ok(Json(ApiResponse::ok_data(list)))
+}
+
+fn description(description: Option<String>) -> APIResult<String> {
+    let description = check::trim(description.unwrap_or_default());
+    if description.len() > 255 {
+        return Err(APIError::new_param_err(
+            ParamErrType::Len(0, 255),
+            "description",
+        ));
+    }
+    Ok(description)
+}
+
+// 环境没有上级设置 不支持 inherit
+fn env_approval(approval: Option<String>) -> APIResult<Approval> {
+    let approval = approval::approval(approval)?;
+    if approval == Approval::Inherit {
+        return Err(APIError::new_param_err(ParamErrType::Invalid, "approval"));
+    }
+    Ok(approval)
+}
+
+// 解析授权的权限类型
+fn env_verbs(verbs: Option<Vec<String>>) -> APIResult<Vec<Verb>> {
+    let mut list = Vec::new();
+    for verb in verbs.unwrap_or_default().iter() {
+        let verb = match verb.as_str() {
+            "create" => Verb::Create,
+            "modify" => Verb::Modify,
+            "view" => Verb::VIEW,
+            "assign" => Verb::ASSIGN,
+            "publish" => Verb::Publish,
+            "approve" => Verb::Approve,
+            _ => return Err(APIError::new_param_err(ParamErrType::Invalid, "verbs")),
+        };
+        if !list.contains(&verb) {
+            list.push(verb);
+        }
+    }
+    if list.is_empty() {
+        return Err(APIError::new_param_err(ParamErrType::Required, "verbs"));
+    }
+    Ok(list)
+}
@@ -4,16 +4,22 @@ use crate::web::{
         json::ReqJson,
         jwt::Claims,
         query::ReqQuery,
-        response::{APIError, ApiResponse, ParamErrType},
+        response::{APIError, ApiResponse, Empty, ParamErrType},
     },
-    store::dao::{app, favorite},
+    store::dao::{app, favorite, recent},
     APIResult,
 };
 
 use axum::Json;
+use chrono::Local;
 use entity::{app::AppItem, ID};
 use serde::Deserialize;
 
+// 每个用户保留的最近访问应用数量
+const RECENT_LIMIT: u64 = 20;
+// 单次排序的最大收藏数量
+const SORT_LIMIT: usize = 200;
+
 #[derive(Deserialize)]
 pub struct FavoriteParam {
     pub app_id: Option<String>,
@@ -58,3 +64,88 @@ pub async fn list(
     rsp.set_page(page, page_size);
     Ok(Json(rsp))
 }
+
+// 取消收藏
+pub async fn delete(
+    ReqJson(param): ReqJson<FavoriteParam>,
+    auth: Claims,
+) -> APIResult<Json<ApiResponse<Empty>>> {
+    let app_id = check::id_str(param.app_id, "app_id")?;
+    let app_id = app::get_app_id(app_id).await?;
+    if app_id.is_none() {
+        return Err(APIError::new_param_err(ParamErrType::NotExist, "app_id"));
+    }
+    let now = Local::now().timestamp() as u64;
+    if !favorite::remove(app_id.unwrap(), auth.user_id, now).await? {
+        return Err(APIError::new_param_err(ParamErrType::NotExist, "app_id"));
+    }
+    Ok(Json(ApiResponse::ok()))
+}
+
+#[derive(Deserialize)]
+pub struct SortParam {
+    pub app_ids: Option<Vec<String>>,
+}
+
+// 按传入顺序排列收藏 未传入的收藏保持原有排序
+pub async fn sort(
+    ReqJson(param): ReqJson<SortParam>,
+    auth: Claims,
+) -> APIResult<Json<ApiResponse<Empty>>> {
+    let app_ids = match param.app_ids {
+        Some(app_ids) if !app_ids.is_empty() => app_ids,
+        _ => return Err(APIError::new_param_err(ParamErrType::Required, "app_ids")),
+    };
+    if app_ids.len() > SORT_LIMIT {
+        return Err(APIError::new_param_err(
+            ParamErrType::Len(1, SORT_LIMIT),
+            "app_ids",
+        ));
+    }
+    let mut ids = Vec::with_capacity(app_ids.len());
+    for app_id in app_ids.into_iter() {
+        let app_id = check::id_str_rule(app_id, "app_ids")?;
+        let id = match app::get_app_id(app_id).await? {
+            Some(id) => id,
+            None => return Err(APIError::new_param_err(ParamErrType::NotExist, "app_ids")),
+        };
+        if ids.contains(&id) {
+            return Err(APIError::new_param_err(ParamErrType::Invalid, "app_ids"));
+        }
+        if !favorite::is_exist(id, auth.user_id).await? {
+            return Err(APIError::new_param_err(ParamErrType::NotExist, "app_ids"));
+        }
+        ids.push(id);
+    }
+    favorite::sort(auth.user_id, ids).await?;
+    Ok(Json(ApiResponse::ok()))
+}
+
+// 获取用户最近访问的应用
+pub async fn recent(auth: Claims) -> APIResult<Json<ApiResponse<Vec<AppItem>>>> {
+    let list = recent::get_app(auth.user_id, RECENT_LIMIT).await?;
+    Ok(Json(ApiResponse::ok_data(list)))
+}
+
+// 记录用户访问应用, 在后台执行不阻塞请求
+pub fn visit(user_id: u32, app_id: String) {
+    tokio::spawn(async move {
+        let id = match app::get_app_id(app_id.clone()).await {
+            Ok(Some(id)) => id,
+            Ok(None) => return,
+            Err(err) => {
+                tracing::error!("failed to get app {}. err: {:?}", app_id, err);
+                return;
+            }
+        };
+        let now = Local::now().timestamp() as u64;
+        if let Err(err) = recent::visit(user_id, id, now, RECENT_LIMIT).await {
+            tracing::error!(
+                "failed to record recent app {} of user {}. err: {:?}",
+                app_id,
+                user_id,
+                err
+            );
+        }
+    });
+}
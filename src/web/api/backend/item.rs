@@ -1,16 +1,26 @@
-use super::dao::{item, namespace};
-use super::{check, ReqJson, ReqQuery};
+use std::collections::{HashMap, HashSet};
+
+use super::dao::{item, item_revision, namespace, release, rule, users};
+use super::{check, diff, ReqJson, ReqQuery};
+use super::{favorite, quota, webhook};
 use super::{
     response::{APIError, ApiResponse, ParamErrType},
     APIResult,
 };
+use crate::config;
+use crate::web::api::diff::ChangeKind;
 use crate::web::api::permission::accredit;
 use crate::web::extract::jwt::Claims;
 
 use axum::extract::Json;
-use entity::orm::Set;
-use entity::{ItemActive, ItemCategory, ItemModel, ID};
-use serde::Deserialize;
+use chrono::Local;
+use entity::item::{ItemDesc, ItemSearch};
+use entity::item_revision::{BlameItem, RevisionItem};
+use entity::namespace::EditLock;
+use entity::orm::{Condition, Set};
+use entity::webhook::WebhookEvent;
+use entity::{ItemActive, ItemModel, ID};
+use serde::{Deserialize, Serialize};
 
 #[derive(Deserialize)]
 pub struct ItemParam {
@@ -27,8 +37,13 @@ pub async fn create(
     auth: Claims,
 ) -> APIResult<Json<ApiResponse<ID>>> {
     let ns_id = check::id_decode(param.id, "id")?;
-    let key = check::id_str_len(param.key, "key", None, Some(255))?;
-    let category: ItemCategory = param.category.unwrap_or_default().into();
+    let key = match param.key {
+        Some(key) => key,
+        None => return Err(APIError::new_param_err(ParamErrType::Required, "key")),
+    };
+    let category = check::item_category(param.category, "category")?;
+    let value = param.value.unwrap_or_default();
+    check::item_value(&category, &value)?;
     let remark = param.remark.unwrap_or_default();
     if remark.len() > 255 {
         return Err(APIError::new_param_err(ParamErrType::Len(0, 255), "remark"));
@@ -49,16 +64,24 @@ pub async fn create(
     {
         return Err(APIError::new_permission_forbidden());
     }
+    // 校验 key 命名规则
+    key_rule(ns_id).await?.check(&key)?;
+    // 校验配额
+    let limit = quota::limit(&info.app_id, ns_id).await?;
+    quota::check_value(&limit, &value, "value")?;
+    quota::check_count(&limit, item::count(ns_id).await? + 1)?;
 
     // 检查是否已存在此key
     if item::is_key_exist(ns_id, key.clone()).await? {
         return Err(APIError::new_param_err(ParamErrType::Exist, "key"));
     }
+    // 校验通过后获取编辑锁
+    edit_lock(ns_id, &auth).await?;
 
     let data = ItemActive {
         namespace_id: Set(ns_id),
-        key: Set(key),
-        value: Set(param.value.unwrap_or_default()),
+        key: Set(key.clone()),
+        value: Set(value),
         category: Set(category),
         remark: Set(remark),
         version: Set(1u64),
@@ -67,6 +90,7 @@ pub async fn create(
     };
 
     let id = item::add(data).await?;
+    webhook::notify(WebhookEvent::Item, info, None, auth.user_id, vec![key]);
     Ok(Json(ApiResponse::ok_data(ID::new(id))))
 }
 
@@ -84,18 +108,10 @@ pub async fn edit(
         }
         None => return Err(APIError::new_param_err(ParamErrType::Required, "version")),
     };
-    if let Some(key) = &param.key {
-        check::id_str_len_rule(key, "key", None, Some(255))?;
-    }
-
-    let category: ItemCategory = param.category.clone().unwrap_or_default().into();
-    // 校验值类型 TODO
-    match category {
-        ItemCategory::Text => (),
-        ItemCategory::Json => (),
-        ItemCategory::Yaml => (),
-        ItemCategory::Toml => (),
-    }
+    let category = match param.category {
+        Some(category) => Some(check::item_category(Some(category), "category")?),
+        None => None,
+    };
     if let Some(remark) = &param.remark {
         if remark.len() > 255 {
             return Err(APIError::new_param_err(ParamErrType::Len(0, 255), "remark"));
@@ -112,6 +128,13 @@ pub async fn edit(
     if entity.deleted_at != 0 {
         return Err(APIError::new_param_err(ParamErrType::NotExist, "id"));
     }
+    // 校验修改后的值类型
+    if category.is_some() || param.value.is_some() {
+        check::item_value(
+            category.as_ref().unwrap_or(&entity.category),
+            param.value.as_ref().unwrap_or(&entity.value),
+        )?;
+    }
     // 校验权限
     let info = namespace::get_app_info(entity.namespace_id).await?;
     if info.is_none() {
@@ -127,25 +150,383 @@ pub async fn edit(
     {
         return Err(APIError::new_permission_forbidden());
     }
+    if let Some(value) = &param.value {
+        let limit = quota::limit(&info.app_id, entity.namespace_id).await?;
+        quota::check_value(&limit, value, "value")?;
+    }
+    if let Some(key) = &param.key {
+        if key != &entity.key {
+            key_rule(entity.namespace_id).await?.check(key)?;
+            if item::is_key_exist(entity.namespace_id, key.clone()).await? {
+                return Err(APIError::new_param_err(ParamErrType::Exist, "key"));
+            }
+        }
+    }
 
-    // let entity = entity.unwrap();
+    // 重命名时同时通知修改前后的 key
+    let mut keys = vec![entity.key.clone()];
+    if let Some(key) = &param.key {
+        if key != &entity.key {
+            keys.push(key.clone());
+        }
+    }
+    // 校验通过后获取编辑锁
+    edit_lock(entity.namespace_id, &auth).await?;
     let success = item::update(
         entity,
         param.key,
         param.value,
-        param.category,
+        category,
         param.remark,
         version,
         auth.user_id,
     )
     .await?;
     if success {
+        webhook::notify(WebhookEvent::Item, info, None, auth.user_id, keys);
         Ok(Json(ApiResponse::ok()))
     } else {
         Err(APIError::new_param_err(ParamErrType::Changed, "item"))
     }
 }
 
+// 获取 namespace 的 key 命名规则
+pub async fn key_rule(ns_id: u64) -> APIResult<check::KeyRule> {
+    match namespace::get_key_policy(ns_id).await? {
+        Some(policy) => check::KeyRule::new(policy),
+        None => Err(APIError::new_param_err(ParamErrType::NotExist, "namespace")),
+    }
+}
+
+// namespace 开启编辑锁时 获取或续期编辑锁
+pub async fn edit_lock(ns_id: u64, auth: &Claims) -> APIResult<()> {
+    let lock = match namespace::get_lock(ns_id).await? {
+        Some(lock) => lock,
+        None => return Err(APIError::new_param_err(ParamErrType::NotExist, "namespace")),
+    };
+    if lock.edit_lock == EditLock::Disable {
+        return Ok(());
+    }
+    let now = Local::now().timestamp() as u64;
+    let expired_at = now + config::get_namespace().lock_ttl;
+    if namespace::lock(ns_id, auth.user_id, expired_at, now).await? {
+        return Ok(());
+    }
+    // 获取锁失败 返回锁的持有者
+    let lock = namespace::get_lock(ns_id).await?;
+    let user_id = lock.map(|l| l.lock_user_id).unwrap_or_default();
+    if user_id == auth.user_id {
+        return Ok(());
+    }
+    let nickname = users::get_info(user_id)
+        .await?
+        .map(|u| u.nickname)
+        .unwrap_or_default();
+    Err(APIError::new_locked(format!(
+        "The namespace is locked by {}",
+        nickname
+    )))
+}
+
+#[derive(Deserialize)]
+pub struct ImportItemParam {
+    pub key: Option<String>,
+    pub value: Option<String>,
+    pub category: Option<String>,
+    pub remark: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct ImportParam {
+    pub namespace: Option<String>,
+    pub items: Vec<ImportItemParam>,
+}
+
+// 批量导入 item, 已存在的 key 更新值, 不存在的 key 新增
+pub async fn import(
+    ReqJson(param): ReqJson<ImportParam>,
+    auth: Claims,
+) -> APIResult<Json<ApiResponse<ID>>> {
+    let ns_id = check::id_decode(param.namespace, "namespace")?;
+    if param.items.is_empty() {
+        return Err(APIError::new_param_err(ParamErrType::Required, "items"));
+    }
+    let info = namespace::get_app_info(ns_id).await?;
+    if info.is_none() {
+        return Err(APIError::new_param_err(ParamErrType::NotExist, "namespace"));
+    }
+    let info = info.unwrap();
+    let rule = key_rule(ns_id).await?;
+    let limit = quota::limit(&info.app_id, ns_id).await?;
+
+    let mut live = HashMap::new();
+    for entity in item::find_by_namespace(ns_id).await?.into_iter() {
+        live.insert(entity.key.clone(), entity);
+    }
+    let live_count = live.len();
+    let mut keys = HashSet::with_capacity(param.items.len());
+    let mut creates = Vec::new();
+    let mut updates = Vec::new();
+    for i in param.items.into_iter() {
+        let key = match i.key {
+            Some(key) => key,
+            None => return Err(APIError::new_param_err(ParamErrType::Required, "items.key")),
+        };
+        rule.check(&key)?;
+        // 重复的 key
+        if !keys.insert(key.clone()) {
+            return Err(APIError::new_param_err(ParamErrType::Exist, "items.key"));
+        }
+        let remark = i.remark.unwrap_or_default();
+        if remark.len() > 255 {
+            return Err(APIError::new_param_err(
+                ParamErrType::Len(0, 255),
+                "items.remark",
+            ));
+        }
+        let category = check::item_category(i.category, "items.category")?;
+        let value = i.value.unwrap_or_default();
+        check::item_value(&category, &value)?;
+        quota::check_value(&limit, &value, "items.value")?;
+        match live.remove(&key) {
+            Some(entity) => {
+                if entity.value == value && entity.category == category {
+                    continue;
+                }
+                let mut active: ItemActive = entity.clone().into();
+                active.value = Set(value);
+                active.category = Set(category);
+                active.remark = Set(remark);
+                active.version = Set(entity.version + 1);
+                active.modify_user_id = Set(auth.user_id);
+                updates.push((entity, active));
+            }
+            None => creates.push(ItemActive {
+                namespace_id: Set(ns_id),
+                key: Set(key),
+                value: Set(value),
+                category: Set(category),
+                remark: Set(remark),
+                version: Set(1u64),
+                modify_user_id: Set(auth.user_id),
+                ..Default::default()
+            }),
+        }
+    }
+    let resource = vec![info.app_id.as_str(), &info.cluster, &info.namespace];
+    if !creates.is_empty()
+        && !accredit::accredit(&auth, entity::rule::Verb::Create, resource.clone()).await?
+    {
+        return Err(APIError::new_permission_forbidden());
+    }
+    if !updates.is_empty()
+        && !accredit::accredit(&auth, entity::rule::Verb::Modify, resource).await?
+    {
+        return Err(APIError::new_permission_forbidden());
+    }
+    if creates.is_empty() && updates.is_empty() {
+        return Ok(Json(ApiResponse::ok()));
+    }
+    if !creates.is_empty() {
+        quota::check_count(&limit, live_count + creates.len())?;
+    }
+    edit_lock(ns_id, &auth).await?;
+    let keys = creates
+        .iter()
+        .map(|c| c.key.clone().unwrap())
+        .chain(updates.iter().map(|(entity, _)| entity.key.clone()))
+        .collect();
+    if !item::import(creates, updates, vec![]).await? {
+        return Err(APIError::new_param_err(ParamErrType::Changed, "items"));
+    }
+    webhook::notify(WebhookEvent::Item, info, None, auth.user_id, keys);
+    Ok(Json(ApiResponse::ok()))
+}
+
+#[derive(Deserialize)]
+pub struct DeleteParam {
+    pub id: Option<String>,
+    pub version: Option<i64>,
+}
+
+pub async fn delete(
+    ReqJson(param): ReqJson<DeleteParam>,
+    auth: Claims,
+) -> APIResult<Json<ApiResponse<ID>>> {
+    let item_id = check::id_decode(param.id, "id")?;
+    let version = match param.version {
+        Some(version) => {
+            if version == 0 {
+                return Err(APIError::new_param_err(ParamErrType::Invalid, "version"));
+            }
+            version
+        }
+        None => return Err(APIError::new_param_err(ParamErrType::Required, "version")),
+    };
+    let entity = item::find_by_id(item_id).await?;
+    if entity.is_none() {
+        return Err(APIError::new_param_err(ParamErrType::NotExist, "id"));
+    }
+    let entity = entity.unwrap();
+    if entity.deleted_at != 0 {
+        return Err(APIError::new_param_err(ParamErrType::NotExist, "id"));
+    }
+    // 校验权限
+    let info = namespace::get_app_info(entity.namespace_id).await?;
+    if info.is_none() {
+        return Err(APIError::new_param_err(ParamErrType::NotExist, "id"));
+    }
+    let info = info.unwrap();
+    if !accredit::accredit(
+        &auth,
+        entity::rule::Verb::Modify,
+        vec![&info.app_id, &info.cluster, &info.namespace],
+    )
+    .await?
+    {
+        return Err(APIError::new_permission_forbidden());
+    }
+    edit_lock(entity.namespace_id, &auth).await?;
+
+    let key = entity.key.clone();
+    if !item::delete(entity, version, auth.user_id).await? {
+        return Err(APIError::new_param_err(ParamErrType::Changed, "item"));
+    }
+    webhook::notify(WebhookEvent::Item, info, None, auth.user_id, vec![key]);
+    Ok(Json(ApiResponse::ok()))
+}
+
+#[derive(Deserialize)]
+pub struct RevertParam {
+    pub id: Option<String>,
+    pub namespace: Option<String>,
+}
+
+// 放弃未发布的修改 还原至最后一次发布的状态
+// 传 id 还原单个 item, 传 namespace 还原整个 namespace
+pub async fn revert(
+    ReqJson(param): ReqJson<RevertParam>,
+    auth: Claims,
+) -> APIResult<Json<ApiResponse<ID>>> {
+    let (ns_id, target) = match param.id {
+        Some(id) => {
+            let item_id = check::id_decode_rule::<u64>(&id, "id")?;
+            // 已删除的 item 同样可以还原
+            let entity = item::find_by_id(item_id).await?;
+            if entity.is_none() {
+                return Err(APIError::new_param_err(ParamErrType::NotExist, "id"));
+            }
+            let entity = entity.unwrap();
+            (entity.namespace_id, Some(entity))
+        }
+        None => (check::id_decode(param.namespace, "namespace")?, None),
+    };
+    let info = namespace::get_app_info(ns_id).await?;
+    if info.is_none() {
+        return Err(APIError::new_param_err(ParamErrType::NotExist, "namespace"));
+    }
+    let info = info.unwrap();
+    if !accredit::accredit(
+        &auth,
+        entity::rule::Verb::Modify,
+        vec![&info.app_id, &info.cluster, &info.namespace],
+    )
+    .await?
+    {
+        return Err(APIError::new_permission_forbidden());
+    }
+
+    let mut published = match release::get_namespace_config(ns_id).await? {
+        Some(config) => diff::published_map(diff::parse_configurations(&config.configurations)?),
+        None => diff::published_map(vec![]),
+    };
+    let mut actions = Vec::new();
+    let live = item::find_by_namespace(ns_id).await?;
+    match target {
+        Some(entity) => {
+            let p = published.remove(&entity.key);
+            if entity.deleted_at == 0 {
+                match p {
+                    Some(p) => {
+                        if p.value != entity.value || p.category != entity.category {
+                            actions.push(item::Revert::Restore(entity, p));
+                        }
+                    }
+                    None => actions.push(item::Revert::Remove(entity)),
+                }
+            } else if let Some(p) = p {
+                // 已删除的 key 若已重新创建则无需恢复
+                if !live.iter().any(|i| i.key == entity.key) {
+                    actions.push(item::Revert::Restore(entity, p));
+                }
+            }
+        }
+        None => {
+            for entity in live.into_iter() {
+                match published.remove(&entity.key) {
+                    Some(p) => {
+                        if p.value != entity.value || p.category != entity.category {
+                            actions.push(item::Revert::Restore(entity, p));
+                        }
+                    }
+                    None => actions.push(item::Revert::Remove(entity)),
+                }
+            }
+            // 剩余已发布的 key 均已被删除
+            if !published.is_empty() {
+                let keys = published.keys().cloned().collect();
+                for entity in item::find_deleted_by_keys(ns_id, keys).await?.into_iter() {
+                    if let Some(p) = published.remove(&entity.key) {
+                        actions.push(item::Revert::Restore(entity, p));
+                    }
+                }
+                for (_, p) in published.into_iter() {
+                    actions.push(item::Revert::Create(p));
+                }
+            }
+        }
+    }
+    // 无需还原
+    if actions.is_empty() {
+        return Ok(Json(ApiResponse::ok()));
+    }
+    // 还原后的 item 同样受配额限制
+    let limit = quota::limit(&info.app_id, ns_id).await?;
+    let (mut added, mut removed) = (0, 0);
+    for a in actions.iter() {
+        match a {
+            item::Revert::Restore(entity, p) => {
+                quota::check_value(&limit, &p.value, "value")?;
+                if entity.deleted_at != 0 {
+                    added += 1;
+                }
+            }
+            item::Revert::Remove(_) => removed += 1,
+            item::Revert::Create(p) => {
+                quota::check_value(&limit, &p.value, "value")?;
+                added += 1;
+            }
+        }
+    }
+    if added > removed {
+        quota::check_count(&limit, item::count(ns_id).await? + added - removed)?;
+    }
+    edit_lock(ns_id, &auth).await?;
+    let keys = actions
+        .iter()
+        .map(|a| match a {
+            item::Revert::Restore(entity, _) => entity.key.clone(),
+            item::Revert::Remove(entity) => entity.key.clone(),
+            item::Revert::Create(p) => p.key.clone(),
+        })
+        .collect();
+    if !item::revert(ns_id, actions, auth.user_id).await? {
+        return Err(APIError::new_param_err(ParamErrType::Changed, "item"));
+    }
+    webhook::notify(WebhookEvent::Item, info, None, auth.user_id, keys);
+    Ok(Json(ApiResponse::ok()))
+}
+
 #[derive(Deserialize)]
 pub struct DetailsParam {
     pub namespace: Option<String>,
@@ -153,17 +534,134 @@ pub struct DetailsParam {
     pub page_size: Option<String>,
 }
 
+#[derive(Serialize)]
+pub struct ItemState {
+    #[serde(flatten)]
+    pub item: ItemModel,
+    // 与已发布配置对比的状态
+    pub status: ChangeKind,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub published_value: Option<String>,
+}
+
 pub async fn list(
     ReqQuery(param): ReqQuery<DetailsParam>,
     auth: Claims,
-) -> APIResult<Json<ApiResponse<Vec<ItemModel>>>> {
+) -> APIResult<Json<ApiResponse<Vec<ItemState>>>> {
     let ns_id = check::id_decode(param.namespace, "id")?;
-    // 校验权限
+    let app_id = namespace_viewable(ns_id, &auth).await?;
+    favorite::visit(auth.user_id, app_id);
+
+    let (page, page_size) = check::page(param.page, param.page_size);
+    let data: Vec<ItemModel> =
+        item::find_by_nsid_all(ns_id, (page - 1) * page_size, page_size).await?;
+
+    // 获取已发布的配置 标记是否发布
+    let published = match release::get_namespace_config(ns_id).await? {
+        Some(config) => diff::published_map(diff::parse_configurations(&config.configurations)?),
+        None => diff::published_map(vec![]),
+    };
+    let mut list = Vec::with_capacity(data.len());
+    for model in data.into_iter() {
+        let desc = ItemDesc {
+            id: model.id,
+            key: model.key.clone(),
+            value: model.value.clone(),
+            category: model.category.clone(),
+            version: model.version,
+        };
+        let p = published.get(&model.key);
+        list.push(ItemState {
+            status: diff::change_kind(&desc, p),
+            published_value: p.map(|p| p.value.clone()),
+            item: model,
+        });
+    }
+    let mut rsp = ApiResponse::ok_data(list);
+    rsp.set_page(page, page_size);
+    Ok(Json(rsp))
+}
+
+#[derive(Deserialize)]
+pub struct DeletedParam {
+    pub namespace: Option<String>,
+}
+
+// 获取已发布但已被删除, 下次发布将移除的 item
+pub async fn list_deleted(
+    ReqQuery(param): ReqQuery<DeletedParam>,
+    auth: Claims,
+) -> APIResult<Json<ApiResponse<Vec<ItemState>>>> {
+    let ns_id = check::id_decode(param.namespace, "id")?;
+    namespace_viewable(ns_id, &auth).await?;
+
+    let mut published = match release::get_namespace_config(ns_id).await? {
+        Some(config) => diff::published_map(diff::parse_configurations(&config.configurations)?),
+        None => diff::published_map(vec![]),
+    };
+    for i in item::get_namespace_items(ns_id).await?.iter() {
+        published.remove(&i.key);
+    }
+    let mut list = Vec::with_capacity(published.len());
+    if !published.is_empty() {
+        let keys = published.keys().cloned().collect();
+        for model in item::find_deleted_by_keys(ns_id, keys).await?.into_iter() {
+            // 同一 key 仅保留最后删除的记录
+            if let Some(p) = published.remove(&model.key) {
+                list.push(ItemState {
+                    item: model,
+                    status: ChangeKind::Deleted,
+                    published_value: Some(p.value),
+                });
+            }
+        }
+    }
+    Ok(Json(ApiResponse::ok_data(list)))
+}
+
+// 校验 namespace 的查看权限, 返回所属的 app_id
+async fn namespace_viewable(ns_id: u64, auth: &Claims) -> APIResult<String> {
     let info = namespace::get_app_info(ns_id).await?;
     if info.is_none() {
         return Err(APIError::new_param_err(ParamErrType::NotExist, "id"));
     }
     let info = info.unwrap();
+    if !accredit::accredit(
+        auth,
+        entity::rule::Verb::VIEW,
+        vec![&info.app_id, &info.cluster, &info.namespace],
+    )
+    .await?
+    {
+        return Err(APIError::new_permission_forbidden());
+    }
+    Ok(info.app_id)
+}
+
+#[derive(Deserialize)]
+pub struct RevisionParam {
+    pub id: Option<String>,
+    pub page: Option<String>,
+    pub page_size: Option<String>,
+}
+
+// 获取 item 修改记录
+pub async fn revision(
+    ReqQuery(param): ReqQuery<RevisionParam>,
+    auth: Claims,
+) -> APIResult<Json<ApiResponse<Vec<RevisionItem>>>> {
+    let item_id = check::id_decode(param.id, "id")?;
+    // 已删除的 item 也可查看修改记录
+    let entity = item::find_by_id(item_id).await?;
+    if entity.is_none() {
+        return Err(APIError::new_param_err(ParamErrType::NotExist, "id"));
+    }
+    let entity = entity.unwrap();
+    let info = namespace::get_app_info(entity.namespace_id).await?;
+    if info.is_none() {
+        return Err(APIError::new_param_err(ParamErrType::NotExist, "id"));
+    }
+    let info = info.unwrap();
     if !accredit::accredit(
         &auth,
         entity::rule::Verb::VIEW,
@@ -175,10 +673,139 @@ pub async fn list(
     }
 
     let (page, page_size) = check::page(param.page, param.page_size);
-    let data: Vec<ItemModel> =
-        item::find_by_nsid_all(ns_id, (page - 1) * page_size, page_size).await?;
-    let mut rsp = ApiResponse::ok_data(data);
-    // TODO 更新返回结构 待是否发布标记
+    let list = item_revision::get_item_revision(item_id, (page - 1) * page_size, page_size).await?;
+    let mut rsp = ApiResponse::ok_data(list);
+    rsp.set_page(page, page_size);
+    Ok(Json(rsp))
+}
+
+// 获取 namespace 下每个 key 的最后修改人及修改时间
+pub async fn blame(
+    ReqQuery(param): ReqQuery<DetailsParam>,
+    auth: Claims,
+) -> APIResult<Json<ApiResponse<Vec<BlameItem>>>> {
+    let ns_id = check::id_decode(param.namespace, "namespace")?;
+    let info = namespace::get_app_info(ns_id).await?;
+    if info.is_none() {
+        return Err(APIError::new_param_err(ParamErrType::NotExist, "namespace"));
+    }
+    let info = info.unwrap();
+    if !accredit::accredit(
+        &auth,
+        entity::rule::Verb::VIEW,
+        vec![&info.app_id, &info.cluster, &info.namespace],
+    )
+    .await?
+    {
+        return Err(APIError::new_permission_forbidden());
+    }
+
+    let list = item_revision::get_namespace_blame(ns_id).await?;
+    Ok(Json(ApiResponse::ok_data(list)))
+}
+
+#[derive(Deserialize)]
+pub struct SearchParam {
+    pub keyword: Option<String>,
+    pub app_id: Option<String>,
+    pub cluster: Option<String>,
+    pub env: Option<String>,
+    pub category: Option<String>,
+    // draft: 未发布的配置 published: 已发布的配置
+    pub scope: Option<String>,
+    pub page: Option<String>,
+    pub page_size: Option<String>,
+}
+
+// 在拥有查看权限的 namespace 中搜索 key 及 value
+pub async fn search(
+    ReqQuery(param): ReqQuery<SearchParam>,
+    auth: Claims,
+) -> APIResult<Json<ApiResponse<Vec<ItemSearch>>>> {
+    let keyword = match param.keyword {
+        Some(keyword) => {
+            let keyword = check::trim(keyword);
+            if keyword.len() < 2 || keyword.len() > 255 {
+                return Err(APIError::new_param_err(
+                    ParamErrType::Len(2, 255),
+                    "keyword",
+                ));
+            }
+            keyword
+        }
+        None => return Err(APIError::new_param_err(ParamErrType::Required, "keyword")),
+    };
+    let app_id = match param.app_id {
+        Some(app_id) => Some(check::id_str_rule(app_id, "app_id")?),
+        None => None,
+    };
+    let cluster = match param.cluster {
+        Some(cluster) => Some(check::id_str_rule(cluster, "cluster")?),
+        None => None,
+    };
+    let env = match param.env {
+        Some(env) => Some(check::id_str_rule(env, "env")?),
+        None => None,
+    };
+    let category = match param.category {
+        Some(category) => Some(check::item_category(Some(category), "category")?),
+        None => None,
+    };
+    let published = match param.scope.unwrap_or_default().as_str() {
+        "" | "draft" => false,
+        "published" => true,
+        _ => return Err(APIError::new_param_err(ParamErrType::Invalid, "scope")),
+    };
+    let (page, page_size) = check::page(param.page, param.page_size);
+
+    // 仅搜索拥有查看权限的资源
+    let resource = match accredit::accredit_resources(&auth, entity::rule::Verb::VIEW).await? {
+        Some(resources) => {
+            if resources.is_empty() {
+                let mut rsp = ApiResponse::ok_data(vec![]);
+                rsp.set_page(page, page_size);
+                return Ok(Json(rsp));
+            }
+            Some(rule::resource_condition(resources))
+        }
+        None => None,
+    };
+    // 仅搜索所属集群在指定环境的 namespace
+    let resource = match env {
+        Some(env) => {
+            let cond = Condition::all().add(rule::env_condition(&env));
+            Some(match resource {
+                Some(resource) => cond.add(resource),
+                None => cond,
+            })
+        }
+        None => resource,
+    };
+
+    let list = if published {
+        release::search_published(
+            keyword,
+            app_id,
+            cluster,
+            category,
+            resource,
+            (page - 1) * page_size,
+            page_size,
+        )
+        .await?
+    } else {
+        item::search(
+            keyword,
+            app_id,
+            cluster,
+            category,
+            resource,
+            (page - 1) * page_size,
+            page_size,
+        )
+        .await?
+    };
+    let mut rsp = ApiResponse::ok_data(list);
     rsp.set_page(page, page_size);
     Ok(Json(rsp))
 }
@@ -1,16 +1,25 @@
 pub mod app;
 pub mod app_extend;
+pub mod approval;
 pub mod cluster;
 pub mod item;
 pub mod namespace;
+pub mod promotion;
 pub mod publication;
+pub mod quota;
+pub mod retention;
+pub mod schedule;
+pub mod template;
 pub mod users;
+pub mod webhook;
 pub mod favorite;
 pub mod department;
+pub mod environment;
 
 use super::super::extract::response;
 use super::super::APIResult;
 use super::check;
+use super::diff;
 
 use super::{ReqJson, ReqQuery};
 
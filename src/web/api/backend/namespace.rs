@@ -1,19 +1,26 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
-use super::dao::{cluster, namespace};
+use super::dao::{cluster, item, namespace, release};
 use super::response::{APIError, ApiResponse, ParamErrType};
 use super::APIResult;
-use super::{check, ReqJson, ReqQuery};
+use super::{approval, favorite, quota, retention, template, webhook};
+use super::{check, diff, ReqJson, ReqQuery};
+use crate::config;
 use crate::web::api::permission::accredit;
 use crate::web::extract::jwt::Claims;
-use crate::web::store::dao::{rule, user_role};
+use crate::web::store::dao::{rule, user_role, users};
 
 use axum::extract::Json;
-use entity::namespace::{NamespaceInfo, NamespaceItem};
-use entity::orm::Set;
+use chrono::Local;
+use entity::item::ItemDesc;
+use entity::namespace::{
+    DeletedNamespace, EditLock, KeyPolicy, NamespaceInfo, NamespaceItem, NamespaceLock,
+};
+use entity::orm::{ActiveModelTrait, Set};
 use entity::rule::Verb;
-use entity::{NamespaceActive, Scope, ID};
-use serde::Deserialize;
+use entity::webhook::WebhookEvent;
+use entity::{ItemActive, NamespaceActive, Scope, ID};
+use serde::{Deserialize, Serialize};
 
 #[derive(Deserialize)]
 pub struct NamespaceParam {
@@ -21,6 +28,11 @@ pub struct NamespaceParam {
     pub cluster: Option<String>,
     pub namespace: Option<String>,
     pub scope: Option<String>,
+    pub key_pattern: Option<String>,
+    pub key_reserved_prefix: Option<String>,
+    pub key_max_depth: Option<u8>,
+    pub edit_lock: Option<bool>,
+    pub template: Option<String>, // 模板 ID 使用模板的 key 规则及草稿 item
 }
 
 pub async fn create(
@@ -29,7 +41,18 @@ pub async fn create(
 ) -> APIResult<Json<ApiResponse<ID>>> {
     let namespace = check::id_str(param.namespace, "namespace")?;
     let app_id = check::id_str(param.app_id, "app_id")?;
-    let scope = Scope::from(param.scope.unwrap_or_default());
+    let template = match param.template {
+        Some(id) => {
+            let id = check::id_decode_rule::<u64>(&id, "template")?;
+            Some(template::visible_template(&auth, id).await?)
+        }
+        None => None,
+    };
+    let scope = match (param.scope, template.as_ref()) {
+        (Some(scope), _) => Scope::from(scope),
+        (None, Some(template)) => template.scope.clone(),
+        (None, None) => Scope::default(),
+    };
     let cluster = match scope {
         // 私有的 集群字段必填
         Scope::Private => check::id_str(param.cluster, "cluster"),
@@ -42,6 +65,39 @@ pub async fn create(
             }
         }
     }?;
+    // 未指定 key 规则则使用模板或默认规则
+    let default_policy = match template.as_ref() {
+        Some(template) => KeyPolicy {
+            key_pattern: template.key_pattern.clone(),
+            key_reserved_prefix: template.key_reserved_prefix.clone(),
+            key_max_depth: template.key_max_depth,
+        },
+        None => {
+            let policy = config::get_key_policy();
+            KeyPolicy {
+                key_pattern: policy.pattern.clone(),
+                key_reserved_prefix: policy.reserved_prefix.clone(),
+                key_max_depth: policy.max_depth,
+            }
+        }
+    };
+    let policy = KeyPolicy {
+        key_pattern: check::key_pattern(param.key_pattern)?.unwrap_or(default_policy.key_pattern),
+        key_reserved_prefix: reserved_prefix(
+            param
+                .key_reserved_prefix
+                .unwrap_or(default_policy.key_reserved_prefix),
+        )?,
+        key_max_depth: param.key_max_depth.unwrap_or(default_policy.key_max_depth),
+    };
+    // 模板中的 key 需满足新 namespace 的 key 规则
+    let items = match template.as_ref() {
+        Some(template) => {
+            let rule = check::KeyRule::new(policy.clone())?;
+            template::draft_items(&rule, template::template_items(template)?, auth.user_id)?
+        }
+        None => vec![],
+    };
     // 校验权限
     if !accredit::accredit(&auth, entity::rule::Verb::Create, vec![&app_id, &cluster]).await? {
         return Err(APIError::new_permission_forbidden());
@@ -60,62 +116,625 @@ pub async fn create(
     }
 
     let data = NamespaceActive {
-        app_id: Set(app_id),
-        cluster: Set(cluster),
-        namespace: Set(namespace),
+        app_id: Set(app_id.clone()),
+        cluster: Set(cluster.clone()),
+        namespace: Set(namespace.clone()),
         scope: Set(scope),
+        key_pattern: Set(policy.key_pattern),
+        key_reserved_prefix: Set(policy.key_reserved_prefix),
+        key_max_depth: Set(policy.key_max_depth),
+        edit_lock: Set(lock_state(param.edit_lock.unwrap_or_default())),
         creator_user: Set(auth.user_id),
         ..Default::default()
     };
 
-    namespace::add(data).await?;
+    if items.is_empty() {
+        let id = namespace::add(data).await?;
+        return Ok(Json(ApiResponse::ok_data(ID::new(id))));
+    }
+    let info = NamespaceInfo {
+        id: 0,
+        app_id,
+        cluster,
+        namespace,
+    };
+    let id = create_with_items(data, items, info, auth.user_id).await?;
+    Ok(Json(ApiResponse::ok_data(ID::new(id))))
+}
+
+#[derive(Deserialize)]
+pub struct CloneParam {
+    pub id: Option<String>, // 源 namespace ID
+    pub app_id: Option<String>,
+    pub cluster: Option<String>,
+    pub namespace: Option<String>, // 为空时使用源 namespace 名称
+}
+
+// 复制 namespace 的设置及草稿 item 到其他应用或集群
+pub async fn clone(
+    ReqJson(param): ReqJson<CloneParam>,
+    auth: Claims,
+) -> APIResult<Json<ApiResponse<ID>>> {
+    let id = check::id_decode(param.id, "id")?;
+    let app_id = check::id_str(param.app_id, "app_id")?;
+    let cluster = check::id_str(param.cluster, "cluster")?;
+    let source = namespace::find_by_id(id).await?;
+    if source.is_none() {
+        return Err(APIError::new_param_err(ParamErrType::NotExist, "id"));
+    }
+    let source = source.unwrap();
+    let namespace = match param.namespace {
+        Some(namespace) => check::id_str_rule(namespace, "namespace")?,
+        None => source.namespace.clone(),
+    };
+    if !accredit::accredit(
+        &auth,
+        entity::rule::Verb::VIEW,
+        vec![&source.app_id, &source.cluster, &source.namespace],
+    )
+    .await?
+    {
+        return Err(APIError::new_permission_forbidden());
+    }
+    if !accredit::accredit(&auth, entity::rule::Verb::Create, vec![&app_id, &cluster]).await? {
+        return Err(APIError::new_permission_forbidden());
+    }
+    if !cluster::is_exist(app_id.clone(), cluster.clone()).await? {
+        return Err(APIError::new_param_err(
+            ParamErrType::NotExist,
+            "app_id, cluster",
+        ));
+    }
+    if namespace::is_exist(app_id.clone(), cluster.clone(), namespace.clone()).await? {
+        return Err(APIError::new_param_err(ParamErrType::Exist, "namespace"));
+    }
+    let rule = check::KeyRule::new(KeyPolicy {
+        key_pattern: source.key_pattern.clone(),
+        key_reserved_prefix: source.key_reserved_prefix.clone(),
+        key_max_depth: source.key_max_depth,
+    })?;
+    let items = item::find_by_namespace(id)
+        .await?
+        .into_iter()
+        .map(template::template_item)
+        .collect();
+    let items = template::draft_items(&rule, items, auth.user_id)?;
+    let data = NamespaceActive {
+        app_id: Set(app_id.clone()),
+        cluster: Set(cluster.clone()),
+        namespace: Set(namespace.clone()),
+        scope: Set(source.scope),
+        key_pattern: Set(source.key_pattern),
+        key_reserved_prefix: Set(source.key_reserved_prefix),
+        key_max_depth: Set(source.key_max_depth),
+        edit_lock: Set(source.edit_lock),
+        approval: Set(source.approval),
+        retain_count: Set(source.retain_count),
+        retain_days: Set(source.retain_days),
+        creator_user: Set(auth.user_id),
+        ..Default::default()
+    };
+    let info = NamespaceInfo {
+        id: 0,
+        app_id,
+        cluster,
+        namespace,
+    };
+    let id = create_with_items(data, items, info, auth.user_id).await?;
+    Ok(Json(ApiResponse::ok_data(ID::new(id))))
+}
+
+// 创建 namespace 及草稿 item 并通知订阅者
+async fn create_with_items(
+    data: NamespaceActive,
+    items: Vec<ItemActive>,
+    mut info: NamespaceInfo,
+    user_id: u32,
+) -> APIResult<u64> {
+    // 新 namespace 未设置配额 使用应用及全局的配额
+    let limit = quota::limit(&info.app_id, 0).await?;
+    quota::check_count(&limit, items.len())?;
+    for i in items.iter() {
+        quota::check_value(&limit, &i.value.clone().unwrap(), "value")?;
+    }
+    let keys = items.iter().map(|i| i.key.clone().unwrap()).collect();
+    let id = namespace::add_with_items(data, items).await?;
+    info.id = id;
+    webhook::notify(WebhookEvent::Item, info, None, user_id, keys);
+    Ok(id)
+}
+
+#[derive(Deserialize)]
+pub struct KeyPolicyParam {
+    pub id: Option<String>,
+    pub key_pattern: Option<String>,
+    pub key_reserved_prefix: Option<String>,
+    pub key_max_depth: Option<u8>,
+}
+
+// 修改 namespace 的 key 命名规则 仅对之后的修改生效
+pub async fn edit_key_policy(
+    ReqJson(param): ReqJson<KeyPolicyParam>,
+    auth: Claims,
+) -> APIResult<Json<ApiResponse<KeyPolicy>>> {
+    let id = check::id_decode(param.id, "id")?;
+    let key_pattern = check::key_pattern(param.key_pattern)?;
+    let key_reserved_prefix = match param.key_reserved_prefix {
+        Some(prefix) => Some(reserved_prefix(prefix)?),
+        None => None,
+    };
+    let info = namespace::get_app_info(id).await?;
+    if info.is_none() {
+        return Err(APIError::new_param_err(ParamErrType::NotExist, "id"));
+    }
+    let info = info.unwrap();
+    if !accredit::accredit(
+        &auth,
+        entity::rule::Verb::Modify,
+        vec![&info.app_id, &info.cluster, &info.namespace],
+    )
+    .await?
+    {
+        return Err(APIError::new_permission_forbidden());
+    }
+    let mut active = NamespaceActive {
+        ..Default::default()
+    };
+    if let Some(key_pattern) = key_pattern {
+        active.key_pattern = Set(key_pattern);
+    }
+    if let Some(key_reserved_prefix) = key_reserved_prefix {
+        active.key_reserved_prefix = Set(key_reserved_prefix);
+    }
+    if let Some(key_max_depth) = param.key_max_depth {
+        active.key_max_depth = Set(key_max_depth);
+    }
+    if active.is_changed() {
+        namespace::update_by_id(active, id).await?;
+    }
+    let policy = namespace::get_key_policy(id).await?;
+    Ok(Json(ApiResponse::ok_data(policy.unwrap_or_default())))
+}
+
+// 格式化保留前缀
+fn reserved_prefix(prefix: String) -> APIResult<String> {
+    let prefix = check::key_reserved_prefix(&prefix).join(",");
+    if prefix.len() > 1024 {
+        return Err(APIError::new_param_err(
+            ParamErrType::Len(0, 1024),
+            "key_reserved_prefix",
+        ));
+    }
+    Ok(prefix)
+}
+
+#[derive(Deserialize)]
+pub struct ApprovalParam {
+    pub id: Option<String>,
+    pub approval: Option<String>, // inherit | enable | disable
+}
+
+// 修改 namespace 的发布审批设置, inherit 时使用集群的设置
+pub async fn edit_approval(
+    ReqJson(param): ReqJson<ApprovalParam>,
+    auth: Claims,
+) -> APIResult<Json<ApiResponse<ID>>> {
+    let id = check::id_decode(param.id, "id")?;
+    let approval = approval::approval(param.approval)?;
+    let info = namespace::get_app_info(id).await?;
+    if info.is_none() {
+        return Err(APIError::new_param_err(ParamErrType::NotExist, "id"));
+    }
+    let info = info.unwrap();
+    if !accredit::accredit(
+        &auth,
+        entity::rule::Verb::ASSIGN,
+        vec![&info.app_id, &info.cluster, &info.namespace],
+    )
+    .await?
+    {
+        return Err(APIError::new_permission_forbidden());
+    }
+    let active = NamespaceActive {
+        approval: Set(approval),
+        ..Default::default()
+    };
+    namespace::update_by_id(active, id).await?;
+    Ok(Json(ApiResponse::ok_data(ID::new(id))))
+}
+
+#[derive(Deserialize)]
+pub struct RetentionParam {
+    pub id: Option<String>,
+    pub retain_count: Option<u32>, // 保留的发布数量 0为使用全局设置
+    pub retain_days: Option<u32>,  // 保留的发布天数 0为使用全局设置
+}
+
+// 修改 namespace 的发布保留策略
+pub async fn edit_retention(
+    ReqJson(param): ReqJson<RetentionParam>,
+    auth: Claims,
+) -> APIResult<Json<ApiResponse<ID>>> {
+    let id = check::id_decode(param.id, "id")?;
+    let (retain_count, retain_days) =
+        retention::retain_param(param.retain_count, param.retain_days)?;
+    let info = namespace::get_app_info(id).await?;
+    if info.is_none() {
+        return Err(APIError::new_param_err(ParamErrType::NotExist, "id"));
+    }
+    let info = info.unwrap();
+    if !accredit::accredit(
+        &auth,
+        entity::rule::Verb::ASSIGN,
+        vec![&info.app_id, &info.cluster, &info.namespace],
+    )
+    .await?
+    {
+        return Err(APIError::new_permission_forbidden());
+    }
+    let active = NamespaceActive {
+        retain_count: Set(retain_count),
+        retain_days: Set(retain_days),
+        ..Default::default()
+    };
+    namespace::update_by_id(active, id).await?;
+    Ok(Json(ApiResponse::ok_data(ID::new(id))))
+}
+
+#[derive(Deserialize)]
+pub struct NamespaceIdParam {
+    pub id: Option<String>,
+}
+
+// 删除 namespace, item、发布及关联随之删除, 期限内可恢复
+pub async fn delete(
+    ReqJson(param): ReqJson<NamespaceIdParam>,
+    auth: Claims,
+) -> APIResult<Json<ApiResponse<ID>>> {
+    let id = check::id_decode(param.id, "id")?;
+    let info = namespace::get_app_info(id).await?;
+    if info.is_none() {
+        return Err(APIError::new_param_err(ParamErrType::NotExist, "id"));
+    }
+    let info = info.unwrap();
+    if !accredit::accredit(
+        &auth,
+        entity::rule::Verb::ASSIGN,
+        vec![&info.app_id, &info.cluster, &info.namespace],
+    )
+    .await?
+    {
+        return Err(APIError::new_permission_forbidden());
+    }
+    let now = Local::now().timestamp() as u64;
+    if !namespace::delete(id, now).await? {
+        return Err(APIError::new_param_err(ParamErrType::NotExist, "id"));
+    }
+    Ok(Json(ApiResponse::ok_data(ID::new(id))))
+}
+
+// 恢复删除的 namespace
+pub async fn restore(
+    ReqJson(param): ReqJson<NamespaceIdParam>,
+    auth: Claims,
+) -> APIResult<Json<ApiResponse<ID>>> {
+    let id = check::id_decode(param.id, "id")?;
+    let deleted = namespace::find_deleted(id).await?;
+    if deleted.is_none() {
+        return Err(APIError::new_param_err(ParamErrType::NotExist, "id"));
+    }
+    let deleted = deleted.unwrap();
+    if !accredit::accredit(
+        &auth,
+        entity::rule::Verb::ASSIGN,
+        vec![&deleted.app_id, &deleted.cluster, &deleted.namespace],
+    )
+    .await?
+    {
+        return Err(APIError::new_permission_forbidden());
+    }
+    // 超过恢复期限
+    let now = Local::now().timestamp() as u64;
+    if deleted.deleted_at + config::get_namespace().restore_ttl < now {
+        return Err(APIError::new_param_err(ParamErrType::NotExist, "id"));
+    }
+    // 所在集群已被删除
+    if !cluster::is_exist(deleted.app_id.clone(), deleted.cluster.clone()).await? {
+        return Err(APIError::new_param_err(ParamErrType::NotExist, "cluster"));
+    }
+    // 删除后已创建同名 namespace
+    if namespace::is_exist(
+        deleted.app_id.clone(),
+        deleted.cluster.clone(),
+        deleted.namespace.clone(),
+    )
+    .await?
+    {
+        return Err(APIError::new_param_err(ParamErrType::Exist, "namespace"));
+    }
+    if !namespace::restore(id, deleted.deleted_at).await? {
+        return Err(APIError::new_param_err(ParamErrType::Changed, "id"));
+    }
+    Ok(Json(ApiResponse::ok_data(ID::new(id))))
+}
+
+#[derive(Deserialize)]
+pub struct DeletedQueryParam {
+    pub app_id: Option<String>,
+    pub cluster: Option<String>,
+}
+
+// 获取可恢复的 namespace
+pub async fn list_deleted(
+    ReqQuery(param): ReqQuery<DeletedQueryParam>,
+    auth: Claims,
+) -> APIResult<Json<ApiResponse<Vec<DeletedNamespace>>>> {
+    let app_id = check::id_str(param.app_id, "app_id")?;
+    let cluster = check::id_str(param.cluster, "cluster")?;
+    if !accredit::accredit(&auth, entity::rule::Verb::VIEW, vec![&app_id, &cluster]).await? {
+        return Err(APIError::new_permission_forbidden());
+    }
+    let now = Local::now().timestamp() as u64;
+    let deleted_after = now.saturating_sub(config::get_namespace().restore_ttl);
+    let list = namespace::get_deleted(app_id, cluster, deleted_after).await?;
+    Ok(Json(ApiResponse::ok_data(list)))
+}
+
+#[derive(Deserialize)]
+pub struct EditLockParam {
+    pub id: Option<String>,
+    pub enable: Option<bool>,
+}
+
+#[derive(Serialize)]
+pub struct LockState {
+    #[serde(flatten)]
+    pub lock: NamespaceLock,
+    pub nickname: String,
+}
+
+// 开启或关闭 namespace 的编辑锁
+pub async fn edit_lock(
+    ReqJson(param): ReqJson<EditLockParam>,
+    auth: Claims,
+) -> APIResult<Json<ApiResponse<LockState>>> {
+    let id = check::id_decode(param.id, "id")?;
+    let enable = match param.enable {
+        Some(enable) => enable,
+        None => return Err(APIError::new_param_err(ParamErrType::Required, "enable")),
+    };
+    let info = namespace::get_app_info(id).await?;
+    if info.is_none() {
+        return Err(APIError::new_param_err(ParamErrType::NotExist, "id"));
+    }
+    let info = info.unwrap();
+    if !accredit::accredit(
+        &auth,
+        entity::rule::Verb::Modify,
+        vec![&info.app_id, &info.cluster, &info.namespace],
+    )
+    .await?
+    {
+        return Err(APIError::new_permission_forbidden());
+    }
+    let mut active = NamespaceActive {
+        edit_lock: Set(lock_state(enable)),
+        ..Default::default()
+    };
+    // 关闭时同时释放已持有的锁
+    if !enable {
+        active.lock_user_id = Set(0);
+        active.lock_expired_at = Set(0);
+    }
+    namespace::update_by_id(active, id).await?;
+    lock_info(id)
+        .await
+        .map(|state| Json(ApiResponse::ok_data(state)))
+}
+
+#[derive(Deserialize)]
+pub struct LockParam {
+    pub id: Option<String>,
+}
+
+// 查看 namespace 编辑锁的持有者
+pub async fn lock(
+    ReqQuery(param): ReqQuery<LockParam>,
+    auth: Claims,
+) -> APIResult<Json<ApiResponse<LockState>>> {
+    let id = check::id_decode(param.id, "id")?;
+    let info = namespace::get_app_info(id).await?;
+    if info.is_none() {
+        return Err(APIError::new_param_err(ParamErrType::NotExist, "id"));
+    }
+    let info = info.unwrap();
+    if !accredit::accredit(
+        &auth,
+        entity::rule::Verb::VIEW,
+        vec![&info.app_id, &info.cluster, &info.namespace],
+    )
+    .await?
+    {
+        return Err(APIError::new_permission_forbidden());
+    }
+    lock_info(id)
+        .await
+        .map(|state| Json(ApiResponse::ok_data(state)))
+}
+
+// 释放编辑锁 仅锁的持有者与管理员可释放
+pub async fn unlock(
+    ReqJson(param): ReqJson<LockParam>,
+    auth: Claims,
+) -> APIResult<Json<ApiResponse<ID>>> {
+    let id = check::id_decode(param.id, "id")?;
+    let info = namespace::get_app_info(id).await?;
+    if info.is_none() {
+        return Err(APIError::new_param_err(ParamErrType::NotExist, "id"));
+    }
+    let info = info.unwrap();
+    let lock = namespace::get_lock(id).await?;
+    if lock.is_none() {
+        return Err(APIError::new_param_err(ParamErrType::NotExist, "id"));
+    }
+    let lock = lock.unwrap();
+    if lock.lock_user_id == 0 {
+        return Ok(Json(ApiResponse::ok()));
+    }
+    if lock.lock_user_id != auth.user_id && !accredit::acc_app_admin(&auth, &info.app_id).await? {
+        return Err(APIError::new_permission_forbidden());
+    }
+    namespace::unlock(id).await?;
     Ok(Json(ApiResponse::ok()))
 }
 
+async fn lock_info(id: u64) -> APIResult<LockState> {
+    let mut lock = match namespace::get_lock(id).await? {
+        Some(lock) => lock,
+        None => return Err(APIError::new_param_err(ParamErrType::NotExist, "id")),
+    };
+    // 已过期的锁视为未持有
+    if lock.lock_expired_at < Local::now().timestamp() as u64 {
+        lock.lock_user_id = 0;
+        lock.lock_expired_at = 0;
+    }
+    let nickname = match lock.lock_user_id {
+        0 => String::new(),
+        user_id => users::get_info(user_id)
+            .await?
+            .map(|u| u.nickname)
+            .unwrap_or_default(),
+    };
+    Ok(LockState { lock, nickname })
+}
+
+fn lock_state(enable: bool) -> EditLock {
+    if enable {
+        EditLock::Enable
+    } else {
+        EditLock::Disable
+    }
+}
+
 #[derive(Deserialize)]
 pub struct NamespaceQueryParam {
     pub app_id: Option<String>,
     pub cluster: Option<String>,
+    pub env: Option<String>, // 指定环境时集群可为空, 返回环境下所有集群的 namespace
     pub page: Option<String>,
     pub page_size: Option<String>,
 }
 
+#[derive(Serialize)]
+pub struct NamespaceState {
+    #[serde(flatten)]
+    pub namespace: NamespaceItem,
+    pub cluster: String,
+    // 未发布的修改数量
+    pub pending: usize,
+}
+
 pub async fn list(
     ReqQuery(param): ReqQuery<NamespaceQueryParam>,
     auth: Claims,
-) -> APIResult<Json<ApiResponse<Vec<NamespaceItem>>>> {
+) -> APIResult<Json<ApiResponse<Vec<NamespaceState>>>> {
     let app_id = check::id_str(param.app_id, "app_id")?;
-    let cluster = check::id_str(param.cluster, "cluster")?;
+    let clusters = match param.env {
+        Some(env) => {
+            let env = check::id_str_rule(env, "env")?;
+            let cluster = match param.cluster {
+                Some(cluster) => Some(check::id_str_rule(cluster, "cluster")?),
+                None => None,
+            };
+            cluster::find_cluster_by_app(app_id.clone(), Some(env))
+                .await?
+                .into_iter()
+                .map(|c| c.name)
+                .filter(|name| cluster.as_ref().is_none_or(|cluster| cluster == name))
+                .collect()
+        }
+        None => vec![check::id_str(param.cluster, "cluster")?],
+    };
+    let mut list = Vec::new();
+    for cluster in clusters.into_iter() {
+        let namespaces = visible_namespace(&auth, app_id.clone(), cluster.clone()).await?;
+        list.extend(namespaces.into_iter().map(|ns| (ns, cluster.clone())));
+    }
+    // 仅记录有权限查看的应用
+    if !list.is_empty() {
+        favorite::visit(auth.user_id, app_id);
+    }
+
+    let ids: Vec<u64> = list.iter().map(|(ns, _)| ns.id).collect();
+    let mut items = HashMap::with_capacity(list.len());
+    for i in item::get_namespaces_items(ids.clone()).await? {
+        items
+            .entry(i.namespace_id)
+            .or_insert_with(Vec::new)
+            .push(ItemDesc::from(i));
+    }
+    let mut configs = HashMap::with_capacity(list.len());
+    for c in release::get_namespaces_config(ids).await? {
+        configs.insert(c.namespace_id, c.configurations);
+    }
+    let mut data = Vec::with_capacity(list.len());
+    for (ns, cluster) in list.into_iter() {
+        let items = items.remove(&ns.id).unwrap_or_default();
+        let pending = match configs.get(&ns.id) {
+            Some(config) => diff::pending_count(&items, diff::parse_configurations(config)?),
+            // 未发布过 所有 item 均为新增
+            None => items.len(),
+        };
+        data.push(NamespaceState {
+            namespace: ns,
+            cluster,
+            pending,
+        });
+    }
+    Ok(Json(ApiResponse::ok_data(data)))
+}
+
+// 获取用户拥有 View 权限的 namespace
+async fn visible_namespace(
+    auth: &Claims,
+    app_id: String,
+    cluster: String,
+) -> APIResult<Vec<NamespaceItem>> {
     let list: Vec<NamespaceItem> =
         namespace::get_namespace_by_appcluster(app_id.clone(), cluster.clone()).await?;
     if list.is_empty() {
-        return Ok(Json(ApiResponse::ok_data(list)));
+        return Ok(list);
     }
     // 校验
-    if accredit::acc_admin(&auth, Some(app_id.clone())) {
-        return Ok(Json(ApiResponse::ok_data(list)));
+    if accredit::acc_app_admin(auth, &app_id).await? {
+        return Ok(list);
     }
     // 获取用户角色ID
     let user_roles = user_role::get_user_role(auth.user_id).await?;
     if user_roles.is_empty() {
         // 返回空
-        return Ok(Json(ApiResponse::ok_data(vec![])));
+        return Ok(vec![]);
     }
     let user_role_set: HashSet<u32> = HashSet::from_iter(user_roles.into_iter());
 
     // 获取上级资源权限 如果有则返回
-    let role = rule::get_resource_role(Verb::VIEW, vec![app_id.clone(), cluster.clone()]).await?;
+    let mut resources = vec![app_id.clone(), cluster.clone()];
+    if let Some(env) = accredit::cluster_env(auth, &app_id, &cluster).await? {
+        resources.push(rule::env_resource(&app_id, &env));
+        resources.push(rule::global_env_resource(&env));
+    }
+    let role = rule::get_resource_role(Verb::VIEW, resources).await?;
     for r_id in role.iter() {
         // 拥有上级资源权限角色  直接返回
         if user_role_set.contains(r_id) {
-            return Ok(Json(ApiResponse::ok_data(list)));
+            return Ok(list);
         }
     }
     // 获取此资源下级拥有View权限的所有角色
     let role =
         rule::get_resource_prefix_role(Verb::VIEW, app_id.clone(), Some(cluster.clone())).await?;
     if role.is_empty() {
-        return Ok(Json(ApiResponse::ok_data(vec![])));
+        return Ok(vec![]);
     }
 
     let mut rules = HashSet::with_capacity(role.len());
@@ -136,14 +755,14 @@ pub async fn list(
     }
     if rules.is_empty() {
         // 无相关权限
-        return Ok(Json(ApiResponse::ok_data(vec![])));
+        return Ok(vec![]);
     }
 
     let list: Vec<NamespaceItem> = list
         .into_iter()
         .filter(|c| rules.contains(&c.namespace))
         .collect();
-    Ok(Json(ApiResponse::ok_data(list)))
+    Ok(list)
 }
 
 #[derive(Deserialize)]
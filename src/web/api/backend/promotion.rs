@@ -0,0 +1,286 @@
+use std::collections::{HashMap, HashSet};
+
+use super::dao::{item, namespace, release};
+use super::item::{edit_lock, key_rule};
+use super::response::{APIError, ApiResponse, ParamErrType};
+use super::APIResult;
+use super::{check, diff, ReqJson, ReqQuery};
+use super::{quota, webhook};
+use crate::web::api::diff::{ChangeKind, KeyChange};
+use crate::web::api::permission::accredit;
+use crate::web::extract::jwt::Claims;
+
+use axum::extract::Json;
+use chrono::Local;
+use entity::item::ItemDesc;
+use entity::orm::Set;
+use entity::rule::Verb;
+use entity::webhook::WebhookEvent;
+use entity::{ItemActive, ItemModel};
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize)]
+pub struct PromoteQueryParam {
+    pub app_id: Option<String>,
+    pub namespace: Option<String>,
+    pub source: Option<String>, // 源集群
+    pub target: Option<String>, // 目标集群
+    pub scope: Option<String>,  // 源集群的配置 draft | published
+}
+
+#[derive(Deserialize)]
+pub struct PromoteParam {
+    pub app_id: Option<String>,
+    pub namespace: Option<String>,
+    pub source: Option<String>,
+    pub target: Option<String>,
+    pub scope: Option<String>,
+    pub keys: Vec<String>, // 需要应用的 key
+}
+
+#[derive(Serialize)]
+pub struct Promotion {
+    // 源集群的发布ID 对比草稿时为空
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source_release: Option<String>,
+    pub changes: Vec<KeyChange>,
+}
+
+struct PromoteContext {
+    app_id: String,
+    namespace: String,
+    source: String,
+    target: String,
+    target_id: u64,
+    source_release: Option<u64>,
+    target_items: Vec<ItemModel>,
+    changes: Vec<KeyChange>,
+}
+
+// 预览源集群与目标集群同名 namespace 的差异
+pub async fn preview(
+    ReqQuery(param): ReqQuery<PromoteQueryParam>,
+    auth: Claims,
+) -> APIResult<Json<ApiResponse<Promotion>>> {
+    let ctx = promote_context(
+        &auth,
+        param.app_id,
+        param.namespace,
+        param.source,
+        param.target,
+        param.scope,
+    )
+    .await?;
+    if !accredit::accredit(
+        &auth,
+        Verb::VIEW,
+        vec![&ctx.app_id, &ctx.target, &ctx.namespace],
+    )
+    .await?
+    {
+        return Err(APIError::new_permission_forbidden());
+    }
+    Ok(Json(ApiResponse::ok_data(Promotion {
+        source_release: ctx.source_release.map(entity::utils::encode_u64),
+        changes: ctx.changes,
+    })))
+}
+
+// 将选中的 key 以草稿的形式应用到目标集群
+pub async fn promote(
+    ReqJson(param): ReqJson<PromoteParam>,
+    auth: Claims,
+) -> APIResult<Json<ApiResponse<Promotion>>> {
+    if param.keys.is_empty() {
+        return Err(APIError::new_param_err(ParamErrType::Required, "keys"));
+    }
+    let ctx = promote_context(
+        &auth,
+        param.app_id,
+        param.namespace,
+        param.source,
+        param.target,
+        param.scope,
+    )
+    .await?;
+    let keys: HashSet<String> = HashSet::from_iter(param.keys);
+    let changes: Vec<KeyChange> = ctx
+        .changes
+        .into_iter()
+        .filter(|c| keys.contains(&c.key))
+        .collect();
+    if changes.is_empty() {
+        return Ok(Json(ApiResponse::ok_data(Promotion {
+            source_release: ctx.source_release.map(entity::utils::encode_u64),
+            changes,
+        })));
+    }
+
+    let resource = vec![ctx.app_id.as_str(), &ctx.target, &ctx.namespace];
+    if changes.iter().any(|c| c.kind == ChangeKind::New)
+        && !accredit::accredit(&auth, Verb::Create, resource.clone()).await?
+    {
+        return Err(APIError::new_permission_forbidden());
+    }
+    if changes.iter().any(|c| c.kind != ChangeKind::New)
+        && !accredit::accredit(&auth, Verb::Modify, resource).await?
+    {
+        return Err(APIError::new_permission_forbidden());
+    }
+    // 备注中记录来源
+    let remark = match ctx.source_release {
+        Some(id) => format!(
+            "promote from {} release {}",
+            ctx.source,
+            entity::utils::encode_u64(id)
+        ),
+        None => format!("promote from {} draft", ctx.source),
+    };
+    let rule = key_rule(ctx.target_id).await?;
+    let limit = quota::limit(&ctx.app_id, ctx.target_id).await?;
+    let mut target_items = HashMap::with_capacity(ctx.target_items.len());
+    for entity in ctx.target_items.into_iter() {
+        target_items.insert(entity.key.clone(), entity);
+    }
+    let now = Local::now().timestamp() as u64;
+    let mut creates = Vec::new();
+    let mut updates = Vec::new();
+    let mut deletes = Vec::new();
+    for c in changes.iter() {
+        match c.kind {
+            ChangeKind::New => {
+                rule.check(&c.key)?;
+                quota::check_value(&limit, c.new_value.as_deref().unwrap_or_default(), "value")?;
+                creates.push(ItemActive {
+                    namespace_id: Set(ctx.target_id),
+                    key: Set(c.key.clone()),
+                    value: Set(c.new_value.clone().unwrap_or_default()),
+                    category: Set(c.new_category.clone().unwrap_or_default()),
+                    remark: Set(remark.clone()),
+                    version: Set(1u64),
+                    modify_user_id: Set(auth.user_id),
+                    ..Default::default()
+                });
+            }
+            ChangeKind::Modified => {
+                if let Some(entity) = target_items.remove(&c.key) {
+                    quota::check_value(
+                        &limit,
+                        c.new_value.as_deref().unwrap_or_default(),
+                        "value",
+                    )?;
+                    let mut active: ItemActive = entity.clone().into();
+                    active.value = Set(c.new_value.clone().unwrap_or_default());
+                    active.category = Set(c.new_category.clone().unwrap_or_default());
+                    active.remark = Set(remark.clone());
+                    active.version = Set(entity.version + 1);
+                    active.modify_user_id = Set(auth.user_id);
+                    updates.push((entity, active));
+                }
+            }
+            ChangeKind::Deleted => {
+                if let Some(entity) = target_items.remove(&c.key) {
+                    let active = ItemActive {
+                        version: Set(entity.version + 1),
+                        modify_user_id: Set(auth.user_id),
+                        deleted_at: Set(now),
+                        ..Default::default()
+                    };
+                    deletes.push((entity, active));
+                }
+            }
+            ChangeKind::Normal => (),
+        }
+    }
+    if creates.len() > deletes.len() {
+        quota::check_count(
+            &limit,
+            item::count(ctx.target_id).await? + creates.len() - deletes.len(),
+        )?;
+    }
+    // 校验通过后获取编辑锁
+    edit_lock(ctx.target_id, &auth).await?;
+    if !item::import(creates, updates, deletes).await? {
+        return Err(APIError::new_param_err(ParamErrType::Changed, "items"));
+    }
+    if let Some(info) = namespace::get_app_info(ctx.target_id).await? {
+        let keys = changes.iter().map(|c| c.key.clone()).collect();
+        webhook::notify(WebhookEvent::Item, info, None, auth.user_id, keys);
+    }
+    Ok(Json(ApiResponse::ok_data(Promotion {
+        source_release: ctx.source_release.map(entity::utils::encode_u64),
+        changes,
+    })))
+}
+
+// 校验参数并对比源集群与目标集群的配置
+async fn promote_context(
+    auth: &Claims,
+    app_id: Option<String>,
+    namespace: Option<String>,
+    source: Option<String>,
+    target: Option<String>,
+    scope: Option<String>,
+) -> APIResult<PromoteContext> {
+    let app_id = check::id_str(app_id, "app_id")?;
+    let namespace = check::id_str(namespace, "namespace")?;
+    let source = check::id_str(source, "source")?;
+    let target = check::id_str(target, "target")?;
+    if source == target {
+        return Err(APIError::new_param_err(ParamErrType::Invalid, "target"));
+    }
+    let published = match scope.unwrap_or_default().as_str() {
+        "" | "draft" => false,
+        "published" => true,
+        _ => return Err(APIError::new_param_err(ParamErrType::Invalid, "scope")),
+    };
+    let source_id =
+        namespace::get_namespace_id(app_id.clone(), source.clone(), namespace.clone()).await?;
+    if source_id.is_none() {
+        return Err(APIError::new_param_err(ParamErrType::NotExist, "source"));
+    }
+    let source_id = source_id.unwrap();
+    let target_id =
+        namespace::get_namespace_id(app_id.clone(), target.clone(), namespace.clone()).await?;
+    if target_id.is_none() {
+        return Err(APIError::new_param_err(ParamErrType::NotExist, "target"));
+    }
+    let target_id = target_id.unwrap();
+    if !accredit::accredit(auth, Verb::VIEW, vec![&app_id, &source, &namespace]).await? {
+        return Err(APIError::new_permission_forbidden());
+    }
+
+    let (source_release, source_items) = if published {
+        match release::get_namespace_config(source_id).await? {
+            Some(config) => (
+                Some(config.id),
+                diff::parse_configurations(&config.configurations)?,
+            ),
+            None => return Err(APIError::new_param_err(ParamErrType::NotExist, "release")),
+        }
+    } else {
+        (None, item::get_namespace_items(source_id).await?)
+    };
+    let target_items = item::find_by_namespace(target_id).await?;
+    let target_desc = target_items
+        .iter()
+        .map(|i| ItemDesc {
+            id: i.id,
+            key: i.key.clone(),
+            value: i.value.clone(),
+            category: i.category.clone(),
+            version: i.version,
+        })
+        .collect();
+    let changes = diff::diff_items(target_desc, source_items);
+    Ok(PromoteContext {
+        app_id,
+        namespace,
+        source,
+        target,
+        target_id,
+        source_release,
+        target_items,
+        changes,
+    })
+}
@@ -1,19 +1,29 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
-use super::dao::{item, release_history};
+use super::dao::{item, publish_request, publish_schedule, release_history};
+use super::item::key_rule;
 use super::response::{APIError, ApiResponse, ParamErrType};
+use super::webhook;
 use super::APIResult;
-use super::{check, ReqJson, ReqQuery};
+use super::{approval, quota};
+use super::{check, diff, ReqJson, ReqQuery};
+use crate::web::api::diff::{ChangeKind, KeyChange};
 use crate::web::api::permission::accredit;
 use crate::web::extract::jwt::Claims;
+use crate::web::store::cache::CacheItem;
 use crate::web::store::dao::{namespace, release};
 
 use ahash::RandomState;
-use axum::extract::Json;
+use axum::extract::{Extension, Json};
+use chrono::{Local, TimeZone};
 use entity::item::ItemDesc;
+use entity::namespace::NamespaceInfo;
+use entity::orm::prelude::DateTimeWithTimeZone;
+use entity::orm::Set;
 use entity::release::ReleaseItemVersion;
-use entity::release_history::HistoryItem;
-use entity::ID;
+use entity::release_history::{HistoryItem, Operation};
+use entity::webhook::WebhookEvent;
+use entity::{PublishRequestActive, PublishScheduleActive};
 use serde::{Deserialize, Serialize};
 
 #[derive(Deserialize, Serialize)]
@@ -27,17 +37,48 @@ pub struct PublicationParam {
     pub items: Vec<PublicationItemParam>,
     pub name: Option<String>, // 发布说明
     pub remark: Option<String>,
+    pub publish_at: Option<u64>, // 定时发布时间 second
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PublishStatus {
+    // 已发布
+    Published,
+    // 等待审批
+    Pending,
+    // 等待定时发布
+    Scheduled,
+}
+
+#[derive(Serialize)]
+pub struct PublishResult {
+    pub status: PublishStatus,
+    // 发布ID 待审批的申请ID 或定时发布ID
+    #[serde(serialize_with = "entity::confuse")]
+    pub id: u64,
 }
 
 pub async fn publish(
     ReqJson(param): ReqJson<PublicationParam>,
     auth: Claims,
-) -> APIResult<Json<ApiResponse<ID>>> {
-    if param.items.len() == 0 {
+) -> APIResult<Json<ApiResponse<PublishResult>>> {
+    let publish_at = check_publish_at(param.publish_at)?;
+    let new_items = release_items(param.items)?;
+    let (release_name, remark) = release_text(param.name, param.remark)?;
+    let prepared = prepare(&new_items).await?;
+    submit(prepared, new_items, release_name, remark, publish_at, &auth)
+        .await
+        .map(|r| Json(ApiResponse::ok_data(r)))
+}
+
+// 解析待发布的 item 及版本
+fn release_items(items: Vec<PublicationItemParam>) -> APIResult<Vec<ReleaseItemVersion>> {
+    if items.is_empty() {
         return Err(APIError::new_param_err(ParamErrType::NotExist, "items"));
     }
-    let mut new_items = Vec::with_capacity(param.items.len());
-    for item_param in param.items.into_iter() {
+    let mut new_items = Vec::with_capacity(items.len());
+    for item_param in items.into_iter() {
         if let Some(id) = item_param.id {
             let id = check::id_decode(Some(id), "items.id")?;
             let version = item_param.version.unwrap_or_default();
@@ -53,14 +94,347 @@ pub async fn publish(
     if new_items.is_empty() {
         return Err(APIError::new_param_err(ParamErrType::Invalid, "items"));
     }
-    let release_name = param.name.unwrap_or("publish".to_owned());
-    let remark = param.remark.unwrap_or("publish".to_owned());
+    Ok(new_items)
+}
+
+#[derive(Serialize)]
+pub struct DryRunError {
+    // 与 key 无关的错误为空
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+    pub message: String,
+}
+
+#[derive(Serialize, Default)]
+pub struct DryRun {
+    // 无校验错误 可以发布
+    pub publishable: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub namespace: Option<NamespaceInfo>,
+    // 当前线上发布ID
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub base_release: Option<String>,
+    pub approval_required: bool,
+    // 与线上配置的差异
+    pub changes: Vec<KeyChange>,
+    pub errors: Vec<DryRunError>,
+    // 处理本请求的实例上等待配置更新的客户端数量, 多实例部署时仅为部分客户端
+    pub instance_clients: usize,
+    // 所有实例中客户端最近一次拉取的时间 second, 按间隔记录, 0 为未被拉取过
+    pub polled_at: u64,
+}
+
+impl DryRun {
+    fn error(&mut self, key: Option<String>, err: APIError) {
+        self.errors.push(DryRunError {
+            key,
+            message: err.message.unwrap_or_else(|| "内部服务异常".to_owned()),
+        });
+    }
+}
+
+// 模拟发布 执行与发布相同的校验并返回发布后的差异, 不写入任何数据
+pub async fn dry_run(
+    Extension(cache): Extension<CacheItem>,
+    ReqJson(param): ReqJson<PublicationParam>,
+    auth: Claims,
+) -> APIResult<Json<ApiResponse<DryRun>>> {
+    let new_items = release_items(param.items)?;
+    let mut report = DryRun::default();
+    if let Err(err) = release_text(param.name, param.remark) {
+        report.error(None, err);
+    }
+    // 需要有查看权限
+    let namespace_id = match item::find_by_id(new_items[0].id).await? {
+        Some(entity) => entity.namespace_id,
+        None => return Err(APIError::new_param_err(ParamErrType::NotExist, "items")),
+    };
+    let info = namespace::get_app_info(namespace_id).await?;
+    if info.is_none() {
+        return Err(APIError::new_param_err(ParamErrType::NotExist, "namespace"));
+    }
+    let info = info.unwrap();
+    let resource = vec![info.app_id.as_str(), &info.cluster, &info.namespace];
+    if !accredit::accredit(&auth, entity::rule::Verb::VIEW, resource.clone()).await? {
+        return Err(APIError::new_permission_forbidden());
+    }
+    if !accredit::accredit(&auth, entity::rule::Verb::Publish, resource).await? {
+        report.error(None, APIError::new_permission_forbidden());
+    }
+    report.approval_required = approval::approval_required(&info).await?;
+    report.instance_clients = cache.subscriber_count(namespace_id).await;
+    report.polled_at = namespace::last_polled(vec![namespace_id])
+        .await?
+        .max(cache.last_polled(&[namespace_id]).await);
+    report.namespace = Some(info);
+
+    // 校验版本并生成发布后的配置
+    let prepared = match prepare(&new_items).await {
+        Ok(prepared) => prepared,
+        Err(err) => {
+            report.error(None, err);
+            return Ok(Json(ApiResponse::ok_data(report)));
+        }
+    };
+    if prepared.release_id != 0 {
+        report.base_release = Some(entity::utils::encode_u64(prepared.release_id));
+    }
+    let changes = diff::diff_items(prepared.published, prepared.config);
+    // 校验值类型及 key 命名规则
+    let rule = key_rule(namespace_id).await?;
+    for c in changes.iter() {
+        if let (Some(value), Some(category)) = (&c.new_value, &c.new_category) {
+            if let Err(err) = check::item_value(category, value) {
+                report.error(Some(c.key.clone()), err);
+            }
+        }
+        if c.kind == ChangeKind::New {
+            if let Err(err) = rule.check(&c.key) {
+                report.error(Some(c.key.clone()), err);
+            }
+        }
+    }
+    report.changes = changes;
+    report.publishable = report.errors.is_empty();
+    Ok(Json(ApiResponse::ok_data(report)))
+}
+
+#[derive(Deserialize)]
+pub struct NamespacePublicationParam {
+    pub namespace: Option<String>,
+    pub base_release: Option<String>, // 预期的线上发布ID 未发布过时为空
+    pub name: Option<String>,
+    pub remark: Option<String>,
+    pub publish_at: Option<u64>,
+}
+
+// 发布 namespace 下所有未发布的修改, 线上发布与 base_release 不一致时失败
+pub async fn publish_namespace(
+    ReqJson(param): ReqJson<NamespacePublicationParam>,
+    auth: Claims,
+) -> APIResult<Json<ApiResponse<PublishResult>>> {
+    let namespace_id = check::id_decode(param.namespace, "namespace")?;
+    let base_release = match param.base_release {
+        Some(base_release) if !base_release.is_empty() => {
+            check::id_decode_rule::<u64>(&base_release, "base_release")?
+        }
+        _ => 0,
+    };
+    let publish_at = check_publish_at(param.publish_at)?;
+    let (release_name, remark) = release_text(param.name, param.remark)?;
+    let info = namespace::get_app_info(namespace_id).await?;
+    if info.is_none() {
+        return Err(APIError::new_param_err(ParamErrType::NotExist, "namespace"));
+    }
+    let info = info.unwrap();
+    if !accredit::accredit(
+        &auth,
+        entity::rule::Verb::Publish,
+        vec![&info.app_id, &info.cluster, &info.namespace],
+    )
+    .await?
+    {
+        return Err(APIError::new_permission_forbidden());
+    }
+
+    // 收集新增 修改 删除的 item
+    let config = release::get_namespace_config(namespace_id).await?;
+    let release_id = config.as_ref().map(|c| c.id).unwrap_or_default();
+    if release_id != base_release {
+        return Err(APIError::new_param_err(
+            ParamErrType::Changed,
+            "base_release",
+        ));
+    }
+    let mut published = match config {
+        Some(config) => diff::published_map(diff::parse_configurations(&config.configurations)?),
+        None => diff::published_map(vec![]),
+    };
+    let mut new_items = Vec::new();
+    for entity in item::find_by_namespace(namespace_id).await?.into_iter() {
+        let desc = ItemDesc {
+            id: entity.id,
+            key: entity.key,
+            value: entity.value,
+            category: entity.category,
+            version: entity.version,
+        };
+        if diff::change_kind(&desc, published.get(&desc.key)) != ChangeKind::Normal {
+            new_items.push(ReleaseItemVersion {
+                id: desc.id,
+                version: desc.version,
+            });
+        }
+        published.remove(&desc.key);
+    }
+    // 剩余已发布的 key 均已被删除 优先使用最后删除的 item
+    if !published.is_empty() {
+        let keys = published.keys().cloned().collect();
+        let mut deleted = item::find_deleted_by_keys(namespace_id, keys).await?;
+        deleted.sort_by_key(|d| std::cmp::Reverse(d.deleted_at));
+        for entity in deleted.into_iter() {
+            if published.remove(&entity.key).is_some() {
+                new_items.push(ReleaseItemVersion {
+                    id: entity.id,
+                    version: entity.version,
+                });
+            }
+        }
+    }
+    if new_items.is_empty() {
+        return Err(APIError::new_param_err(ParamErrType::NotExist, "items"));
+    }
+    let prepared = prepare(&new_items).await?;
+    if prepared.release_id != base_release {
+        return Err(APIError::new_param_err(
+            ParamErrType::Changed,
+            "base_release",
+        ));
+    }
+    submit(prepared, new_items, release_name, remark, publish_at, &auth)
+        .await
+        .map(|r| Json(ApiResponse::ok_data(r)))
+}
+
+// 定时发布的时间需晚于当前时间
+fn check_publish_at(publish_at: Option<u64>) -> APIResult<Option<u64>> {
+    if let Some(publish_at) = publish_at {
+        if publish_at <= Local::now().timestamp() as u64 {
+            return Err(APIError::new_param_err(ParamErrType::Invalid, "publish_at"));
+        }
+    }
+    Ok(publish_at)
+}
+
+// 发布名称及说明
+fn release_text(name: Option<String>, remark: Option<String>) -> APIResult<(String, String)> {
+    let release_name = name.unwrap_or("publish".to_owned());
+    let remark = remark.unwrap_or("publish".to_owned());
     if release_name.len() > 64 {
         return Err(APIError::new_param_err(ParamErrType::Len(0, 64), "name"));
     }
     if remark.len() > 255 {
         return Err(APIError::new_param_err(ParamErrType::Len(0, 255), "remark"));
     }
+    Ok((release_name, remark))
+}
+
+// 校验权限后发布, 指定时间时生成定时发布, 需要审批时生成发布申请
+async fn submit(
+    prepared: Prepared,
+    new_items: Vec<ReleaseItemVersion>,
+    release_name: String,
+    remark: String,
+    publish_at: Option<u64>,
+    auth: &Claims,
+) -> APIResult<PublishResult> {
+    let info = &prepared.info;
+    // 权限验证
+    if !accredit::accredit(
+        auth,
+        entity::rule::Verb::Publish,
+        vec![&info.app_id, &info.cluster, &info.namespace],
+    )
+    .await?
+    {
+        return Err(APIError::new_permission_forbidden());
+    }
+    let approval_required = approval::approval_required(info).await?;
+    // 定时发布 到期后由执行器发布
+    if let Some(publish_at) = publish_at {
+        // 需要审批的 namespace 不支持定时发布
+        if approval_required {
+            return Err(APIError::new_param_err(ParamErrType::Invalid, "publish_at"));
+        }
+        let active = PublishScheduleActive {
+            namespace_id: Set(info.id),
+            name: Set(release_name),
+            remark: Set(remark),
+            items: Set(serde_json::to_string(&new_items).unwrap()),
+            base_release_id: Set(prepared.release_id),
+            publish_at: Set(publish_at),
+            user_id: Set(auth.user_id),
+            ..Default::default()
+        };
+        let id = publish_schedule::add(active).await?;
+        return Ok(PublishResult {
+            status: PublishStatus::Scheduled,
+            id,
+        });
+    }
+    // 需要审批 生成发布申请
+    if approval_required {
+        let change = diff::diff_items(prepared.published, prepared.config);
+        let active = PublishRequestActive {
+            namespace_id: Set(info.id),
+            name: Set(release_name),
+            remark: Set(remark),
+            items: Set(serde_json::to_string(&new_items).unwrap()),
+            change: Set(serde_json::to_string(&change).unwrap()),
+            base_release_id: Set(prepared.release_id),
+            request_user_id: Set(auth.user_id),
+            ..Default::default()
+        };
+        let id = publish_request::add(active).await?;
+        return Ok(PublishResult {
+            status: PublishStatus::Pending,
+            id,
+        });
+    }
+
+    // 发布
+    let keys = prepared.keys();
+    match release::publication_item(
+        prepared.release_id,
+        release_name,
+        info.id,
+        remark,
+        prepared.config,
+        prepared.change,
+        auth.user_id,
+    )
+    .await?
+    {
+        Some(id) => {
+            webhook::notify(
+                WebhookEvent::Publish,
+                info.clone(),
+                Some(id),
+                auth.user_id,
+                keys,
+            );
+            Ok(PublishResult {
+                status: PublishStatus::Published,
+                id,
+            })
+        }
+        // 发生更新  终止发布
+        None => Err(APIError::new_param_err(ParamErrType::Changed, "items")),
+    }
+}
+
+// 校验后待发布的配置
+pub struct Prepared {
+    pub info: NamespaceInfo,
+    // 线上发布ID 未发布过为 0
+    pub release_id: u64,
+    // 线上配置
+    pub published: Vec<ItemDesc>,
+    // 发布后的配置
+    pub config: Vec<ItemDesc>,
+    // 本次发布的 item
+    pub change: Vec<ItemDesc>,
+}
+
+impl Prepared {
+    // 本次发布的 key
+    pub fn keys(&self) -> Vec<String> {
+        self.change.iter().map(|i| i.key.clone()).collect()
+    }
+}
+
+// 校验待发布 item 的 namespace 与版本, 与线上配置合并生成发布后的配置
+pub async fn prepare(new_items: &[ReleaseItemVersion]) -> APIResult<Prepared> {
     // 获取 item_id 的 namespace
     let item_ids = new_items.iter().map(|i| i.id).collect();
     let db_items = item::get_item_by_ids(item_ids).await?;
@@ -69,7 +443,7 @@ pub async fn publish(
         return Err(APIError::new_param_err(ParamErrType::NotExist, "items"));
     }
     let mut version_map = HashMap::with_capacity_and_hasher(new_items.len(), RandomState::new());
-    for i in new_items.into_iter() {
+    for i in new_items.iter() {
         version_map.insert(i.id, i.version);
     }
 
@@ -77,6 +451,8 @@ pub async fn publish(
     let namespace_id = db_items.first().unwrap().namespace_id;
     // 校验 namespace,versoin 一致
     let mut items_map = HashMap::with_capacity_and_hasher(db_items.len(), RandomState::new());
+    // 已删除的 key
+    let mut deleted_keys = HashSet::with_capacity_and_hasher(db_items.len(), RandomState::new());
     // 校验完 namespace_id 后转为 ItemDesc 结构
     let mut db_items_desc = Vec::with_capacity(db_items.len());
     for ida in db_items.into_iter() {
@@ -97,50 +473,35 @@ pub async fn publish(
                 version: ida.version,
             };
             db_items_desc.push(item.clone());
-            items_map.insert(ida.id, item);
+            if ida.deleted_at != 0 {
+                deleted_keys.insert(ida.key);
+            } else {
+                items_map.insert(ida.key, item);
+            }
         } else {
             return Err(APIError::new_param_err(ParamErrType::NotExist, "items"));
         }
     }
 
-    // 权限校验
     // 检查 namespace_id 是否存在
     let info = namespace::get_app_info(namespace_id).await?;
     if info.is_none() {
         return Err(APIError::new_param_err(ParamErrType::NotExist, "namespace"));
     }
     let info = info.unwrap();
-    // 权限验证 TODO
-    if !accredit::accredit(
-        &auth,
-        entity::rule::Verb::Publish,
-        vec![&info.app_id, &info.cluster, &info.namespace],
-    )
-    .await?
-    {
-        return Err(APIError::new_permission_forbidden());
-    }
     // 获取最后一次发布的配置及配置ID
     let config = release::get_namespace_config(namespace_id).await?;
-    let (release_id, release_config) = match config {
+    let (release_id, published, release_config) = match config {
         Some(config) => {
-            let config_item: Result<Vec<ItemDesc>, serde_json::Error> =
-                serde_json::from_str(&config.configurations);
-            if config_item.is_err() {
-                tracing::error!(
-                    "failed to parse release config data: {}, {:?}",
-                    &config.configurations,
-                    config_item
-                );
-                return Err(APIError::new_param_err(ParamErrType::Invalid, "namespace"));
-            }
-
-            let mut config_item = config_item.unwrap();
+            let published = diff::parse_configurations(&config.configurations)?;
+            let mut config_item = published.clone();
+            // 移除已删除的 key
+            config_item.retain(|i| !deleted_keys.contains(&i.key));
             for i in config_item.iter_mut() {
-                if let Some(d) = items_map.remove(&i.id) {
-                    // 如果已发布的 version >= 将要发布的version 则可能已经被发布过
-                    // 数据可能不一致
-                    if i.version >= d.version {
+                if let Some(d) = items_map.remove(&i.key) {
+                    // 如果已发布的 version >= 将要发布的version 且值相同 则可能已经被发布过
+                    // 重新创建的 key 版本从 1 开始, 以值区分
+                    if i.version >= d.version && i.value == d.value && i.category == d.category {
                         return Err(APIError::new_param_err(ParamErrType::Changed, "items"));
                     }
                     *i = d;
@@ -150,53 +511,60 @@ pub async fn publish(
             for (_, i) in items_map.into_iter() {
                 config_item.push(i);
             }
-            (config.id, config_item)
+            (config.id, published, config_item)
         }
-        None => (0, db_items_desc.clone()),
+        None => (0, vec![], items_map.into_values().collect()),
     };
-
-    // 发布
-    if !release::publication_item(
+    let limit = quota::limit(&info.app_id, info.id).await?;
+    quota::check_release(&limit, &release_config)?;
+    Ok(Prepared {
+        info,
         release_id,
-        release_name,
-        namespace_id,
-        remark,
-        release_config,
-        db_items_desc,
-        auth.user_id,
-    )
-    .await?
-    {
-        // 发生更新  终止发布
-        return Err(APIError::new_param_err(ParamErrType::Changed, "items"));
-    }
-
-    Ok(Json(ApiResponse::ok()))
+        published,
+        config: release_config,
+        change: db_items_desc,
+    })
 }
 
 #[derive(Deserialize, Serialize)]
 pub struct RollbackParam {
-    pub id: Option<String>,
+    pub id: Option<String>,      // 发布记录ID
+    pub release: Option<String>, // 发布ID 优先使用
     pub remark: Option<String>,
 }
 
+// 回滚至之前的任意一次发布, 也可回滚至已失效的发布
+// 需要审批的 namespace 生成回滚申请
 pub async fn rollback(
     ReqJson(param): ReqJson<RollbackParam>,
     auth: Claims,
-) -> APIResult<Json<ApiResponse<ID>>> {
-    let history_id = check::id_decode(param.id, "id")?;
-    let namespace_id = release_history::get_namespace_id(history_id).await?;
-    if namespace_id.is_none() {
-        return Err(APIError::new_param_err(ParamErrType::NotExist, "id"));
+) -> APIResult<Json<ApiResponse<PublishResult>>> {
+    let release_id = match param.release {
+        Some(release) => check::id_decode_rule::<u64>(&release, "release")?,
+        None => {
+            let history_id = check::id_decode(param.id, "id")?;
+            match release_history::find_by_id(history_id).await? {
+                Some(history) => history.release_id,
+                None => return Err(APIError::new_param_err(ParamErrType::NotExist, "id")),
+            }
+        }
+    };
+    let remark = param.remark.unwrap_or("rollback".to_owned());
+    if remark.len() > 255 {
+        return Err(APIError::new_param_err(ParamErrType::Len(0, 255), "remark"));
+    }
+    let target = release::find_by_id(release_id).await?;
+    if target.is_none() {
+        return Err(APIError::new_param_err(ParamErrType::NotExist, "release"));
     }
+    let target = target.unwrap();
     // 权限校验
     // 检查 namespace_id 是否存在
-    let info = namespace::get_app_info(namespace_id.unwrap()).await?;
+    let info = namespace::get_app_info(target.namespace_id).await?;
     if info.is_none() {
         return Err(APIError::new_param_err(ParamErrType::NotExist, "namespace"));
     }
     let info = info.unwrap();
-    // 权限验证 TODO
     if !accredit::accredit(
         &auth,
         entity::rule::Verb::Publish,
@@ -206,24 +574,156 @@ pub async fn rollback(
     {
         return Err(APIError::new_permission_forbidden());
     }
+    // 当前生效的发布
+    let current = release::get_namespace_config(target.namespace_id).await?;
+    if current.is_none() {
+        return Err(APIError::new_param_err(ParamErrType::NotExist, "release"));
+    }
+    let current = current.unwrap();
+    if current.id == target.id {
+        return Err(APIError::new_param_err(ParamErrType::Invalid, "release"));
+    }
+    let current_config = diff::parse_configurations(&current.configurations)?;
+    let target_config = diff::parse_configurations(&target.configurations)?;
+    // 需要审批 生成回滚申请
+    if approval::approval_required(&info).await? {
+        let change = diff::diff_items(current_config, target_config);
+        let active = PublishRequestActive {
+            namespace_id: Set(info.id),
+            name: Set(target.name),
+            remark: Set(remark),
+            items: Set("[]".to_owned()),
+            change: Set(serde_json::to_string(&change).unwrap()),
+            base_release_id: Set(current.id),
+            rollback_release_id: Set(target.id),
+            request_user_id: Set(auth.user_id),
+            ..Default::default()
+        };
+        let id = publish_request::add(active).await?;
+        return Ok(Json(ApiResponse::ok_data(PublishResult {
+            status: PublishStatus::Pending,
+            id,
+        })));
+    }
+    let change = rollback_change(&current_config, &target_config);
+    let keys = change.iter().map(|i| i.key.clone()).collect();
+    let change = serde_json::to_string(&change).unwrap();
+    match release::rollback(current.id, target, remark, change, auth.user_id).await? {
+        Some(id) => {
+            webhook::notify(WebhookEvent::Rollback, info, Some(id), auth.user_id, keys);
+            Ok(Json(ApiResponse::ok_data(PublishResult {
+                status: PublishStatus::Published,
+                id,
+            })))
+        }
+        // 发生新的发布 终止回滚
+        None => Err(APIError::new_param_err(ParamErrType::Changed, "release")),
+    }
+}
 
-    let remark = param.remark.unwrap_or("rollback".to_owned());
-    release::rollback_item(history_id, remark).await?;
-    Ok(Json(ApiResponse::ok()))
+// 回滚记录的变更 与发布记录一致为变更的 item
+// 回滚后新增或修改的 key 记录目标发布中的 item, 被移除的 key 记录回滚前的 item
+pub fn rollback_change(current: &[ItemDesc], target: &[ItemDesc]) -> Vec<ItemDesc> {
+    let mut items = HashMap::with_capacity_and_hasher(current.len(), RandomState::new());
+    for i in current.iter().chain(target.iter()) {
+        items.insert(i.key.as_str(), i);
+    }
+    diff::diff_items(current.to_vec(), target.to_vec())
+        .into_iter()
+        .filter_map(|c| items.get(c.key.as_str()).map(|&i| i.clone()))
+        .collect()
+}
+
+#[derive(Deserialize)]
+pub struct ReleaseDiffParam {
+    pub from: Option<String>,
+    pub to: Option<String>,
+}
+
+// 对比同一 namespace 的任意两次发布
+pub async fn release_diff(
+    ReqQuery(param): ReqQuery<ReleaseDiffParam>,
+    auth: Claims,
+) -> APIResult<Json<ApiResponse<Vec<KeyChange>>>> {
+    let from_id = check::id_decode(param.from, "from")?;
+    let to_id = check::id_decode(param.to, "to")?;
+    let from = release::find_by_id(from_id).await?;
+    if from.is_none() {
+        return Err(APIError::new_param_err(ParamErrType::NotExist, "from"));
+    }
+    let from = from.unwrap();
+    let to = release::find_by_id(to_id).await?;
+    if to.is_none() {
+        return Err(APIError::new_param_err(ParamErrType::NotExist, "to"));
+    }
+    let to = to.unwrap();
+    if from.namespace_id != to.namespace_id {
+        return Err(APIError::new_param_err(ParamErrType::Invalid, "to"));
+    }
+    let info = namespace::get_app_info(from.namespace_id).await?;
+    if info.is_none() {
+        return Err(APIError::new_param_err(ParamErrType::NotExist, "namespace"));
+    }
+    let info = info.unwrap();
+    if !accredit::accredit(
+        &auth,
+        entity::rule::Verb::VIEW,
+        vec![&info.app_id, &info.cluster, &info.namespace],
+    )
+    .await?
+    {
+        return Err(APIError::new_permission_forbidden());
+    }
+    let changes = diff::diff_items(
+        diff::parse_configurations(&from.configurations)?,
+        diff::parse_configurations(&to.configurations)?,
+    );
+    Ok(Json(ApiResponse::ok_data(changes)))
 }
 
 #[derive(Deserialize)]
 pub struct HistoryParam {
     id: Option<String>,
+    pub start_at: Option<u64>, // 发布时间范围 second
+    pub end_at: Option<u64>,
+    pub user: Option<String>, // 发布人ID
     pub page: Option<String>,
     pub page_size: Option<String>,
 }
+
+#[derive(Serialize)]
+pub struct HistoryEntry {
+    #[serde(serialize_with = "entity::confuse")]
+    pub id: u64,
+    #[serde(serialize_with = "entity::confuse")]
+    pub release_id: u64,
+    pub operation: Operation,
+    pub name: String,
+    pub remark: String,
+    #[serde(serialize_with = "entity::confuse")]
+    pub publish_user_id: u32,
+    pub nickname: String,
+    pub created_at: DateTimeWithTimeZone,
+    pub changes: Vec<KeyChange>,
+}
+
 // 获取item 发布记录
 pub async fn release_list(
     ReqQuery(param): ReqQuery<HistoryParam>,
     auth: Claims,
-) -> APIResult<Json<ApiResponse<Vec<HistoryItem>>>> {
+) -> APIResult<Json<ApiResponse<Vec<HistoryEntry>>>> {
     let namespace_id = check::id_decode(param.id, "id")?;
+    let start_at = history_time(param.start_at, "start_at")?;
+    let end_at = history_time(param.end_at, "end_at")?;
+    if let (Some(start_at), Some(end_at)) = (start_at, end_at) {
+        if start_at >= end_at {
+            return Err(APIError::new_param_err(ParamErrType::Invalid, "end_at"));
+        }
+    }
+    let user_id = match param.user {
+        Some(user) => Some(check::id_decode_rule::<u32>(&user, "user")?),
+        None => None,
+    };
     // 权限校验
     // 检查 namespace_id 是否存在
     let info = namespace::get_app_info(namespace_id).await?;
@@ -243,10 +743,189 @@ pub async fn release_list(
     }
 
     let (page, page_size) = check::page(param.page, param.page_size);
-    let history =
-        release_history::get_namespace_history(namespace_id, (page - 1) * page_size, page_size)
-            .await?;
-    let mut rsp = ApiResponse::ok_data(history);
+    let history = release_history::get_namespace_history(
+        namespace_id,
+        start_at,
+        end_at,
+        user_id,
+        (page - 1) * page_size,
+        page_size,
+    )
+    .await?;
+    let mut changes = history_changes(namespace_id, &history).await?;
+    let mut list = Vec::with_capacity(history.len());
+    for h in history.into_iter() {
+        let changes = changes.remove(&h.id).unwrap_or_default();
+        list.push(HistoryEntry {
+            id: h.id,
+            release_id: h.release_id,
+            operation: h.operation,
+            name: h.name.unwrap_or_default(),
+            remark: h.remark.unwrap_or_default(),
+            publish_user_id: h.publish_user_id.unwrap_or_default(),
+            nickname: h.nickname.unwrap_or_default(),
+            created_at: h.created_at,
+            changes,
+        });
+    }
+    let mut rsp = ApiResponse::ok_data(list);
     rsp.set_page(page, page_size);
     Ok(Json(rsp))
 }
+
+fn history_time(time: Option<u64>, field: &str) -> APIResult<Option<DateTimeWithTimeZone>> {
+    match time {
+        Some(time) => match Local.timestamp_opt(time as i64, 0).single() {
+            Some(time) => Ok(Some(time.into())),
+            None => Err(APIError::new_param_err(ParamErrType::Invalid, field)),
+        },
+        None => Ok(None),
+    }
+}
+
+// 发布记录中变更的 key, 发布与回滚均记录变更的 item
+#[derive(Deserialize)]
+struct ChangeKey {
+    key: String,
+}
+
+// 与前一次发布对比得到各 key 的新旧值, 返回发布记录ID与变更的映射
+// 前一次发布已被清理时 仅能从记录的变更集中得到新值
+async fn history_changes(
+    namespace_id: u64,
+    history: &[HistoryItem],
+) -> APIResult<HashMap<u64, Vec<KeyChange>, RandomState>> {
+    let mut changes = HashMap::with_capacity_and_hasher(history.len(), RandomState::new());
+    let (start, end) = match (
+        history.iter().map(|h| h.release_id).min(),
+        history.iter().map(|h| h.release_id).max(),
+    ) {
+        (Some(start), Some(end)) => (start, end),
+        _ => return Ok(changes),
+    };
+    // 各发布的前一次发布
+    let ids = release::get_ids_between(namespace_id, start, end).await?;
+    let mut previous = HashMap::with_capacity_and_hasher(ids.len(), RandomState::new());
+    for w in ids.windows(2) {
+        previous.insert(w[1], w[0]);
+    }
+    let mut load: HashSet<u64, RandomState> = history.iter().map(|h| h.release_id).collect();
+    load.extend(history.iter().filter_map(|h| previous.get(&h.release_id)));
+    let mut configs = HashMap::with_capacity_and_hasher(load.len(), RandomState::new());
+    for c in release::get_configs(load.into_iter().collect()).await? {
+        configs.insert(c.id, diff::parse_configurations(&c.configurations)?);
+    }
+
+    for h in history.iter() {
+        let current = match configs.get(&h.release_id) {
+            Some(current) => current.clone(),
+            None => continue,
+        };
+        let change = match previous.get(&h.release_id).and_then(|id| configs.get(id)) {
+            Some(previous) => diff::diff_items(previous.clone(), current),
+            None => {
+                let keys = match serde_json::from_str::<Vec<ChangeKey>>(&h.change) {
+                    Ok(keys) => keys,
+                    Err(err) => {
+                        tracing::error!(
+                            "failed to parse release history change: {}, {:?}",
+                            h.change,
+                            err
+                        );
+                        return Err(APIError::new_server_error());
+                    }
+                };
+                let keys: HashSet<String, RandomState> = keys.into_iter().map(|k| k.key).collect();
+                diff::diff_items(
+                    vec![],
+                    current
+                        .into_iter()
+                        .filter(|i| keys.contains(&i.key))
+                        .collect(),
+                )
+            }
+        };
+        changes.insert(h.id, change);
+    }
+    Ok(changes)
+}
+
+// 每次校验的发布数量
+const INTEGRITY_BATCH: u64 = 100;
+// 单次请求最多校验的发布数量
+const INTEGRITY_SCAN_MAX: u64 = 10000;
+// 单次请求最多返回的损坏发布数量
+const INTEGRITY_CORRUPTED_MAX: usize = 100;
+
+#[derive(Deserialize)]
+pub struct IntegrityParam {
+    pub after: Option<String>, // 从此发布之后开始校验 为空时从头开始
+}
+
+#[derive(Serialize, Default)]
+pub struct IntegrityReport {
+    pub checked: u64,                     // 校验的发布数量
+    pub missing: u64,                     // 未保存校验和的发布数量
+    pub corrupted: Vec<CorruptedRelease>, // 校验和不一致或无法解析的发布
+    #[serde(serialize_with = "entity::confuse")]
+    pub next: u64, // 下次请求的 after, 已校验完所有发布时为 0
+}
+
+#[derive(Serialize)]
+pub struct CorruptedRelease {
+    #[serde(serialize_with = "entity::confuse")]
+    pub id: u64,
+    #[serde(serialize_with = "entity::confuse")]
+    pub namespace_id: u64,
+    pub checksum: String, // 保存的校验和
+    pub actual: String,   // 重新计算的校验和, 配置无法解析时为空
+}
+
+// 重新计算发布的校验和 检测被损坏的发布
+// 单次请求校验的发布数量有上限, 未校验完时通过返回的 next 继续校验
+pub async fn integrity(
+    ReqQuery(param): ReqQuery<IntegrityParam>,
+    auth: Claims,
+) -> APIResult<Json<ApiResponse<IntegrityReport>>> {
+    let mut after_id = match param.after {
+        Some(after) => check::id_decode_rule::<u64>(&after, "after")?,
+        None => 0,
+    };
+    if !accredit::acc_admin(&auth) {
+        return Err(APIError::new_permission_forbidden());
+    }
+    let mut report = IntegrityReport::default();
+    loop {
+        let list = release::get_checksum_after(after_id, INTEGRITY_BATCH).await?;
+        for r in list.iter() {
+            report.checked += 1;
+            after_id = r.id;
+            let actual = release::checksum(&r.configurations).unwrap_or_default();
+            if r.checksum.is_empty() && !actual.is_empty() {
+                report.missing += 1;
+            } else if actual.is_empty() || actual != r.checksum {
+                report.corrupted.push(CorruptedRelease {
+                    id: r.id,
+                    namespace_id: r.namespace_id,
+                    checksum: r.checksum.clone(),
+                    actual,
+                });
+                if report.corrupted.len() >= INTEGRITY_CORRUPTED_MAX {
+                    break;
+                }
+            }
+        }
+        let finished = (list.len() as u64) < INTEGRITY_BATCH;
+        // 达到上限 剩余的发布由下次请求校验
+        if report.corrupted.len() >= INTEGRITY_CORRUPTED_MAX
+            || (!finished && report.checked >= INTEGRITY_SCAN_MAX)
+        {
+            report.next = after_id;
+            break;
+        }
+        if finished {
+            break;
+        }
+    }
+    Ok(Json(ApiResponse::ok_data(report)))
+}
@@ -0,0 +1,296 @@
+use std::collections::HashMap;
+
+use super::dao::{app, item, namespace, release};
+use super::response::{APIError, ApiResponse, ParamErrType};
+use super::APIResult;
+use super::{check, ReqJson, ReqQuery};
+use crate::config;
+use crate::web::api::permission::accredit;
+use crate::web::extract::jwt::Claims;
+use crate::web::extract::response::Empty;
+
+use axum::extract::Json;
+use entity::item::ItemDesc;
+use entity::orm::Set;
+use entity::rule::Verb;
+use entity::{NamespaceActive, NamespaceModel, Quota};
+use serde::{Deserialize, Serialize};
+
+// namespace 生效的配额, 未设置的项依次使用应用设置与全局设置
+pub async fn limit(app_id: &str, namespace_id: u64) -> APIResult<Quota> {
+    let ns = namespace::get_quota(namespace_id)
+        .await?
+        .unwrap_or_default();
+    let app = app::get_quota(app_id.to_owned()).await?.unwrap_or_default();
+    Ok(merge(&ns, &merge(&app, &global())))
+}
+
+// 全局默认配额
+fn global() -> Quota {
+    let conf = config::get_quota();
+    Quota {
+        quota_item_count: conf.item_count,
+        quota_value_size: conf.value_size,
+        quota_release_size: conf.release_size,
+    }
+}
+
+// 使用 parent 补全 quota 中未设置的项
+fn merge(quota: &Quota, parent: &Quota) -> Quota {
+    Quota {
+        quota_item_count: match quota.quota_item_count {
+            0 => parent.quota_item_count,
+            count => count,
+        },
+        quota_value_size: match quota.quota_value_size {
+            0 => parent.quota_value_size,
+            size => size,
+        },
+        quota_release_size: match quota.quota_release_size {
+            0 => parent.quota_release_size,
+            size => size,
+        },
+    }
+}
+
+// 校验单个值的字节数
+pub fn check_value(limit: &Quota, value: &str, field: &str) -> APIResult<()> {
+    let max = limit.quota_value_size as usize;
+    if max != 0 && value.len() > max {
+        return Err(APIError::new_param_err(ParamErrType::Len(0, max), field));
+    }
+    Ok(())
+}
+
+// 校验 namespace 的 item 数量
+pub fn check_count(limit: &Quota, count: usize) -> APIResult<()> {
+    let max = limit.quota_item_count as usize;
+    if max != 0 && count > max {
+        return Err(APIError::new_quota_exceeded(format!(
+            "The number of items exceeds the quota of {}",
+            max
+        )));
+    }
+    Ok(())
+}
+
+// 校验发布后配置的字节数
+pub fn check_release(limit: &Quota, config: &[ItemDesc]) -> APIResult<()> {
+    let max = limit.quota_release_size;
+    if max == 0 {
+        return Ok(());
+    }
+    let size = release_size(config);
+    if size > max {
+        return Err(APIError::new_quota_exceeded(format!(
+            "The size of release {} bytes exceeds the quota of {} bytes",
+            size, max
+        )));
+    }
+    Ok(())
+}
+
+// 与发布记录中保存的配置序列化方式一致
+fn release_size(config: &[ItemDesc]) -> u64 {
+    serde_json::to_string(config)
+        .map(|s| s.len() as u64)
+        .unwrap_or_default()
+}
+
+#[derive(Deserialize)]
+pub struct QuotaParam {
+    pub item_count: Option<u32>,   // 最大 item 数量 0为使用上级设置
+    pub value_size: Option<u32>,   // 单个值的最大字节数 0为使用上级设置
+    pub release_size: Option<u64>, // 发布配置的最大字节数 0为使用上级设置
+}
+
+impl QuotaParam {
+    fn quota(&self) -> Quota {
+        Quota {
+            quota_item_count: self.item_count.unwrap_or_default(),
+            quota_value_size: self.value_size.unwrap_or_default(),
+            quota_release_size: self.release_size.unwrap_or_default(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct NamespaceQuotaParam {
+    pub id: Option<String>,
+    #[serde(flatten)]
+    pub quota: QuotaParam,
+}
+
+// 修改 namespace 的配额 仅超级管理员可操作
+pub async fn edit_namespace(
+    ReqJson(param): ReqJson<NamespaceQuotaParam>,
+    auth: Claims,
+) -> APIResult<Json<ApiResponse<Empty>>> {
+    let id = check::id_decode(param.id, "id")?;
+    if !accredit::acc_admin(&auth) {
+        return Err(APIError::new_permission_forbidden());
+    }
+    if namespace::get_app_info(id).await?.is_none() {
+        return Err(APIError::new_param_err(ParamErrType::NotExist, "id"));
+    }
+    let quota = param.quota.quota();
+    let active = NamespaceActive {
+        quota_item_count: Set(quota.quota_item_count),
+        quota_value_size: Set(quota.quota_value_size),
+        quota_release_size: Set(quota.quota_release_size),
+        ..Default::default()
+    };
+    namespace::update_by_id(active, id).await?;
+    Ok(Json(ApiResponse::ok()))
+}
+
+#[derive(Deserialize)]
+pub struct AppQuotaParam {
+    pub app_id: Option<String>,
+    #[serde(flatten)]
+    pub quota: QuotaParam,
+}
+
+// 修改应用下 namespace 的默认配额 仅超级管理员可操作
+pub async fn edit_app(
+    ReqJson(param): ReqJson<AppQuotaParam>,
+    auth: Claims,
+) -> APIResult<Json<ApiResponse<Empty>>> {
+    let app_id = check::id_str(param.app_id, "app_id")?;
+    if !accredit::acc_admin(&auth) {
+        return Err(APIError::new_permission_forbidden());
+    }
+    if !app::is_exist(app_id.clone()).await? {
+        return Err(APIError::new_param_err(ParamErrType::NotExist, "app_id"));
+    }
+    app::update_quota(app_id, param.quota.quota()).await?;
+    Ok(Json(ApiResponse::ok()))
+}
+
+#[derive(Serialize, Default)]
+pub struct Usage {
+    pub item_count: u64,   // item 数量
+    pub value_size: u64,   // 最大的值字节数
+    pub release_size: u64, // 当前发布配置的字节数
+}
+
+#[derive(Serialize)]
+pub struct NamespaceQuota {
+    #[serde(serialize_with = "entity::confuse")]
+    pub id: u64,
+    pub cluster: String,
+    pub namespace: String,
+    pub quota: Quota, // namespace 的设置
+    pub limit: Quota, // 生效的配额
+    pub usage: Usage,
+}
+
+#[derive(Deserialize)]
+pub struct NamespaceIdParam {
+    pub id: Option<String>,
+}
+
+// 获取 namespace 的配额及用量
+pub async fn namespace_usage(
+    ReqQuery(param): ReqQuery<NamespaceIdParam>,
+    auth: Claims,
+) -> APIResult<Json<ApiResponse<NamespaceQuota>>> {
+    let id = check::id_decode(param.id, "id")?;
+    let ns = namespace::find_by_id(id).await?;
+    if ns.is_none() {
+        return Err(APIError::new_param_err(ParamErrType::NotExist, "id"));
+    }
+    let ns = ns.unwrap();
+    if !accredit::accredit(
+        &auth,
+        Verb::VIEW,
+        vec![&ns.app_id, &ns.cluster, &ns.namespace],
+    )
+    .await?
+    {
+        return Err(APIError::new_permission_forbidden());
+    }
+    let app = app::get_quota(ns.app_id.clone()).await?.unwrap_or_default();
+    let parent = merge(&app, &global());
+    let mut list = namespace_quota(vec![ns], &parent).await?;
+    Ok(Json(ApiResponse::ok_data(list.remove(0))))
+}
+
+#[derive(Serialize)]
+pub struct AppQuota {
+    pub quota: Quota, // 应用的设置
+    pub limit: Quota, // 应用下 namespace 默认生效的配额
+    pub namespaces: Vec<NamespaceQuota>,
+}
+
+#[derive(Deserialize)]
+pub struct AppIdParam {
+    pub app_id: Option<String>,
+}
+
+// 获取应用的配额及其下所有 namespace 的用量
+pub async fn app_usage(
+    ReqQuery(param): ReqQuery<AppIdParam>,
+    auth: Claims,
+) -> APIResult<Json<ApiResponse<AppQuota>>> {
+    let app_id = check::id_str(param.app_id, "app_id")?;
+    if !accredit::accredit(&auth, Verb::VIEW, vec![&app_id]).await? {
+        return Err(APIError::new_permission_forbidden());
+    }
+    let quota = app::get_quota(app_id.clone()).await?;
+    if quota.is_none() {
+        return Err(APIError::new_param_err(ParamErrType::NotExist, "app_id"));
+    }
+    let quota = quota.unwrap();
+    let limit = merge(&quota, &global());
+    let list = namespace::find_by_app(app_id).await?;
+    let namespaces = namespace_quota(list, &limit).await?;
+    Ok(Json(ApiResponse::ok_data(AppQuota {
+        quota,
+        limit,
+        namespaces,
+    })))
+}
+
+// 批量统计 namespace 的用量
+async fn namespace_quota(
+    list: Vec<NamespaceModel>,
+    parent: &Quota,
+) -> APIResult<Vec<NamespaceQuota>> {
+    let ids: Vec<u64> = list.iter().map(|ns| ns.id).collect();
+    let mut usage = HashMap::with_capacity(list.len());
+    for u in item::get_usage(ids.clone()).await? {
+        usage.insert(
+            u.namespace_id,
+            Usage {
+                item_count: u.item_count,
+                value_size: u.value_size,
+                release_size: 0,
+            },
+        );
+    }
+    for r in release::get_namespaces_size(ids).await? {
+        usage
+            .entry(r.namespace_id)
+            .or_insert_with(Usage::default)
+            .release_size = r.size;
+    }
+    Ok(list
+        .into_iter()
+        .map(|ns| {
+            let quota = Quota {
+                quota_item_count: ns.quota_item_count,
+                quota_value_size: ns.quota_value_size,
+                quota_release_size: ns.quota_release_size,
+            };
+            NamespaceQuota {
+                id: ns.id,
+                usage: usage.remove(&ns.id).unwrap_or_default(),
+                cluster: ns.cluster,
+                namespace: ns.namespace,
+                limit: merge(&quota, parent),
+                quota,
+            }
+        })
+        .collect())
+}
@@ -0,0 +1,226 @@
+use std::time::Duration;
+
+use super::dao::{namespace, publish_request, release};
+use super::response::{APIError, ApiResponse, ParamErrType};
+use super::APIResult;
+use super::{check, ReqJson};
+use crate::config;
+use crate::web::api::permission::accredit;
+use crate::web::extract::jwt::Claims;
+
+use axum::extract::Json;
+use chrono::{DateTime, FixedOffset, Local};
+use entity::namespace::NamespaceRetention;
+use entity::release::Reclaimed;
+use entity::rule::Verb;
+use serde::Deserialize;
+use tokio::time::{self, MissedTickBehavior};
+
+// 每次处理的最大数量
+const PRUNE_BATCH: u64 = 100;
+// 可设置的最大保留数量及天数
+const RETAIN_COUNT_MAX: u32 = 10000;
+const RETAIN_DAYS_MAX: u32 = 3650;
+
+// namespace 生效的保留策略 (数量, 天数), namespace 未设置时使用全局设置
+pub fn policy(retention: &NamespaceRetention) -> (u32, u32) {
+    let conf = config::get_retention();
+    let count = match retention.retain_count {
+        0 => conf.count,
+        count => count,
+    };
+    let days = match retention.retain_days {
+        0 => conf.days,
+        days => days,
+    };
+    (count, days)
+}
+
+// 校验 namespace 的保留策略参数, 0 为使用全局设置, 否则需在 1 至上限之间
+pub fn retain_param(count: Option<u32>, days: Option<u32>) -> APIResult<(u32, u32)> {
+    let count = count.unwrap_or_default();
+    if count > RETAIN_COUNT_MAX {
+        return Err(APIError::new_param_err(
+            ParamErrType::Invalid,
+            "retain_count",
+        ));
+    }
+    let days = days.unwrap_or_default();
+    if days > RETAIN_DAYS_MAX {
+        return Err(APIError::new_param_err(
+            ParamErrType::Invalid,
+            "retain_days",
+        ));
+    }
+    Ok((count, days))
+}
+
+#[derive(Deserialize)]
+pub struct PruneParam {
+    pub id: Option<String>, // namespace ID
+}
+
+// 立即按保留策略清理 namespace 的发布
+pub async fn prune(
+    ReqJson(param): ReqJson<PruneParam>,
+    auth: Claims,
+) -> APIResult<Json<ApiResponse<Reclaimed>>> {
+    let id = check::id_decode(param.id, "id")?;
+    let info = namespace::get_app_info(id).await?;
+    if info.is_none() {
+        return Err(APIError::new_param_err(ParamErrType::NotExist, "id"));
+    }
+    let info = info.unwrap();
+    if !accredit::accredit(
+        &auth,
+        Verb::ASSIGN,
+        vec![&info.app_id, &info.cluster, &info.namespace],
+    )
+    .await?
+    {
+        return Err(APIError::new_permission_forbidden());
+    }
+    let retention = namespace::get_retention(id).await?;
+    if retention.is_none() {
+        return Err(APIError::new_param_err(ParamErrType::NotExist, "id"));
+    }
+    let reclaimed = prune_namespace(&retention.unwrap()).await?;
+    Ok(Json(ApiResponse::ok_data(reclaimed)))
+}
+
+// 软删除保留策略之外的发布
+// 同时超出保留数量与保留天数的发布才会被清理, 当前发布及待审批申请所基于的发布始终保留
+async fn prune_namespace(retention: &NamespaceRetention) -> APIResult<Reclaimed> {
+    let mut reclaimed = Reclaimed::default();
+    let (count, days) = policy(retention);
+    if count == 0 && days == 0 {
+        return Ok(reclaimed);
+    }
+    let boundary = match release::get_retention_boundary(retention.id, count as u64).await? {
+        Some(boundary) => boundary,
+        None => return Ok(reclaimed),
+    };
+    let before = match days {
+        0 => None,
+        days => Some(DateTime::<FixedOffset>::from(
+            Local::now() - chrono::Duration::days(days as i64),
+        )),
+    };
+    let protected = publish_request::get_pending_base(retention.id).await?;
+    let now = Local::now().timestamp() as u64;
+    loop {
+        let pruned = release::prune(
+            retention.id,
+            boundary,
+            before,
+            protected.clone(),
+            now,
+            PRUNE_BATCH,
+        )
+        .await?;
+        reclaimed.merge(&pruned);
+        if pruned.releases < PRUNE_BATCH {
+            return Ok(reclaimed);
+        }
+    }
+}
+
+// 启动发布清理任务
+pub fn run_pruner() {
+    let interval = config::get_retention().prune_interval.max(1);
+    tokio::spawn(async move {
+        let mut tick = time::interval(Duration::from_secs(interval));
+        tick.set_missed_tick_behavior(MissedTickBehavior::Skip);
+        loop {
+            tick.tick().await;
+            let pruned = prune_all().await;
+            let purged = purge().await;
+            if pruned.releases != 0 || purged.releases != 0 {
+                tracing::info!(
+                    "pruned {} releases and {} histories ({} bytes), purged {} releases and {} histories ({} bytes)",
+                    pruned.releases,
+                    pruned.histories,
+                    pruned.bytes,
+                    purged.releases,
+                    purged.histories,
+                    purged.bytes
+                );
+            }
+            let namespaces = purge_namespaces().await;
+            if namespaces != 0 {
+                tracing::info!("purged {} deleted namespaces", namespaces);
+            }
+        }
+    });
+}
+
+// 清理所有 namespace
+async fn prune_all() -> Reclaimed {
+    let mut reclaimed = Reclaimed::default();
+    let mut after_id = 0;
+    loop {
+        let list = match namespace::get_retention_after(after_id, PRUNE_BATCH).await {
+            Ok(list) => list,
+            Err(err) => {
+                tracing::error!("failed to load namespace retention. err: {:?}", err);
+                return reclaimed;
+            }
+        };
+        for retention in list.iter() {
+            match prune_namespace(retention).await {
+                Ok(pruned) => reclaimed.merge(&pruned),
+                Err(err) => tracing::error!(
+                    "failed to prune releases of namespace {}. err: {:?}",
+                    retention.id,
+                    err.message
+                ),
+            }
+        }
+        match list.last() {
+            Some(last) if list.len() as u64 == PRUNE_BATCH => after_id = last.id,
+            _ => return reclaimed,
+        }
+    }
+}
+
+// 物理删除软删除超过保留期限的发布
+async fn purge() -> Reclaimed {
+    let mut reclaimed = Reclaimed::default();
+    let now = Local::now().timestamp() as u64;
+    let deleted_before = now.saturating_sub(config::get_retention().purge_after);
+    loop {
+        match release::purge(deleted_before, PRUNE_BATCH).await {
+            Ok(purged) => {
+                reclaimed.merge(&purged);
+                if purged.releases < PRUNE_BATCH {
+                    return reclaimed;
+                }
+            }
+            Err(err) => {
+                tracing::error!("failed to purge releases. err: {:?}", err);
+                return reclaimed;
+            }
+        }
+    }
+}
+
+// 物理删除超过恢复期限的已删除 namespace 及其所有数据
+async fn purge_namespaces() -> u64 {
+    let mut purged = 0;
+    let now = Local::now().timestamp() as u64;
+    let deleted_before = now.saturating_sub(config::get_namespace().restore_ttl);
+    loop {
+        match namespace::purge_deleted(deleted_before, PRUNE_BATCH).await {
+            Ok(count) => {
+                purged += count;
+                if count < PRUNE_BATCH {
+                    return purged;
+                }
+            }
+            Err(err) => {
+                tracing::error!("failed to purge deleted namespaces. err: {:?}", err);
+                return purged;
+            }
+        }
+    }
+}
@@ -0,0 +1,266 @@
+use std::time::Duration;
+
+use super::approval;
+use super::dao::{namespace, publish_schedule, release};
+use super::publication::prepare;
+use super::response::{APIError, ApiResponse, ParamErrType};
+use super::webhook;
+use super::APIResult;
+use super::{check, ReqJson, ReqQuery};
+use crate::config;
+use crate::web::api::permission::accredit;
+use crate::web::extract::jwt::Claims;
+use crate::web::extract::response::APIErrorType;
+
+use axum::extract::Json;
+use chrono::Local;
+use entity::namespace::NamespaceInfo;
+use entity::orm::Set;
+use entity::publish_schedule::{ScheduleItem, ScheduleStatus};
+use entity::release::ReleaseItemVersion;
+use entity::rule::Verb;
+use entity::webhook::WebhookEvent;
+use entity::{PublishScheduleActive, PublishScheduleModel, ID};
+use serde::Deserialize;
+use tokio::time::{self, MissedTickBehavior};
+
+// 每次执行的最大数量
+const SCHEDULE_BATCH: u64 = 100;
+
+#[derive(Deserialize)]
+pub struct ScheduleQueryParam {
+    pub id: Option<String>, // namespace ID
+    pub status: Option<String>,
+    pub page: Option<String>,
+    pub page_size: Option<String>,
+}
+
+// 获取 namespace 的定时发布
+pub async fn list(
+    ReqQuery(param): ReqQuery<ScheduleQueryParam>,
+    auth: Claims,
+) -> APIResult<Json<ApiResponse<Vec<ScheduleItem>>>> {
+    let namespace_id = check::id_decode(param.id, "id")?;
+    let status = match param.status.unwrap_or_default().as_str() {
+        "" => None,
+        "waiting" => Some(ScheduleStatus::Waiting),
+        "running" => Some(ScheduleStatus::Running),
+        "published" => Some(ScheduleStatus::Published),
+        "failed" => Some(ScheduleStatus::Failed),
+        "cancelled" => Some(ScheduleStatus::Cancelled),
+        _ => return Err(APIError::new_param_err(ParamErrType::Invalid, "status")),
+    };
+    let info = namespace_info(namespace_id).await?;
+    if !accredit::accredit(
+        &auth,
+        Verb::VIEW,
+        vec![&info.app_id, &info.cluster, &info.namespace],
+    )
+    .await?
+    {
+        return Err(APIError::new_permission_forbidden());
+    }
+    let (page, page_size) = check::page(param.page, param.page_size);
+    let list = publish_schedule::get_namespace_schedule(
+        namespace_id,
+        status,
+        (page - 1) * page_size,
+        page_size,
+    )
+    .await?;
+    let mut rsp = ApiResponse::ok_data(list);
+    rsp.set_page(page, page_size);
+    Ok(Json(rsp))
+}
+
+#[derive(Deserialize)]
+pub struct CancelParam {
+    pub id: Option<String>,
+}
+
+// 取消等待中的定时发布
+pub async fn cancel(
+    ReqJson(param): ReqJson<CancelParam>,
+    auth: Claims,
+) -> APIResult<Json<ApiResponse<ID>>> {
+    let id = check::id_decode(param.id, "id")?;
+    let schedule = publish_schedule::find_by_id(id).await?;
+    if schedule.is_none() {
+        return Err(APIError::new_param_err(ParamErrType::NotExist, "id"));
+    }
+    let schedule = schedule.unwrap();
+    let info = namespace_info(schedule.namespace_id).await?;
+    if !accredit::accredit(
+        &auth,
+        Verb::Publish,
+        vec![&info.app_id, &info.cluster, &info.namespace],
+    )
+    .await?
+    {
+        return Err(APIError::new_permission_forbidden());
+    }
+    let active = PublishScheduleActive {
+        status: Set(ScheduleStatus::Cancelled),
+        ..Default::default()
+    };
+    // 已开始执行的无法取消
+    if !publish_schedule::update_status(id, ScheduleStatus::Waiting, active).await? {
+        return Err(APIError::new_param_err(ParamErrType::Changed, "id"));
+    }
+    Ok(Json(ApiResponse::ok_data(ID::new(id))))
+}
+
+async fn namespace_info(namespace_id: u64) -> APIResult<NamespaceInfo> {
+    match namespace::get_app_info(namespace_id).await? {
+        Some(info) => Ok(info),
+        None => Err(APIError::new_param_err(ParamErrType::NotExist, "namespace")),
+    }
+}
+
+// 启动定时发布执行器
+pub fn run_executor() {
+    let interval = config::get_publish().schedule_interval.max(1);
+    tokio::spawn(async move {
+        let mut tick = time::interval(Duration::from_secs(interval));
+        // 执行时间过长时跳过到期的定时 避免堆积
+        tick.set_missed_tick_behavior(MissedTickBehavior::Skip);
+        loop {
+            tick.tick().await;
+            let now = Local::now().timestamp() as u64;
+            let lease = config::get_publish().schedule_lease;
+            match publish_schedule::reclaim_stale(now.saturating_sub(lease)).await {
+                Ok(0) => (),
+                Ok(reclaimed) => {
+                    tracing::warn!("reclaimed {} interrupted scheduled publishes", reclaimed)
+                }
+                Err(err) => {
+                    tracing::error!("failed to reclaim scheduled publishes. err: {:?}", err)
+                }
+            }
+            let schedules = match publish_schedule::get_due(now, SCHEDULE_BATCH).await {
+                Ok(schedules) => schedules,
+                Err(err) => {
+                    tracing::error!("failed to load due scheduled publishes. err: {:?}", err);
+                    continue;
+                }
+            };
+            for schedule in schedules.into_iter() {
+                execute(schedule).await;
+            }
+        }
+    });
+}
+
+// 执行定时发布 并记录结果
+async fn execute(schedule: PublishScheduleModel) {
+    // 抢占执行 多实例部署时仅一个实例执行成功, 记录开始时间以便回收中断的执行
+    let running = PublishScheduleActive {
+        status: Set(ScheduleStatus::Running),
+        claimed_at: Set(Local::now().timestamp() as u64),
+        ..Default::default()
+    };
+    match publish_schedule::update_status(schedule.id, ScheduleStatus::Waiting, running).await {
+        Ok(true) => (),
+        Ok(false) => return,
+        Err(err) => {
+            tracing::error!(
+                "failed to run scheduled publish {}. err: {:?}",
+                schedule.id,
+                err
+            );
+            return;
+        }
+    }
+    let active = match publish(&schedule).await {
+        Ok(release_id) => {
+            tracing::info!(
+                "scheduled publish {} of namespace {} published release {}",
+                schedule.id,
+                schedule.namespace_id,
+                release_id
+            );
+            PublishScheduleActive {
+                status: Set(ScheduleStatus::Published),
+                release_id: Set(release_id),
+                ..Default::default()
+            }
+        }
+        Err(err) => {
+            let reason = err.message.unwrap_or_else(|| "内部服务异常".to_owned());
+            tracing::warn!(
+                "scheduled publish {} of namespace {} failed: {}",
+                schedule.id,
+                schedule.namespace_id,
+                reason
+            );
+            PublishScheduleActive {
+                status: Set(ScheduleStatus::Failed),
+                reason: Set(reason.chars().take(255).collect()),
+                ..Default::default()
+            }
+        }
+    };
+    if let Err(err) =
+        publish_schedule::update_status(schedule.id, ScheduleStatus::Running, active).await
+    {
+        tracing::error!(
+            "failed to save scheduled publish {} result. err: {:?}",
+            schedule.id,
+            err
+        );
+    }
+}
+
+// 与即时发布相同的校验, 计划创建后 item 有修改或有新的发布则发布失败
+async fn publish(schedule: &PublishScheduleModel) -> APIResult<u64> {
+    let items = match serde_json::from_str::<Vec<ReleaseItemVersion>>(&schedule.items) {
+        Ok(items) => items,
+        Err(err) => {
+            tracing::error!(
+                "failed to parse scheduled publish items: {}, {:?}",
+                schedule.items,
+                err
+            );
+            return Err(APIError::new_param_err(ParamErrType::Invalid, "items"));
+        }
+    };
+    let prepared = prepare(&items).await?;
+    if prepared.info.id != schedule.namespace_id {
+        return Err(APIError::new_param_err(ParamErrType::Changed, "items"));
+    }
+    // 计划后有新的发布
+    if prepared.release_id != schedule.base_release_id {
+        return Err(APIError::new_param_err(ParamErrType::Changed, "release"));
+    }
+    // 计划创建后开启了审批
+    if approval::approval_required(&prepared.info).await? {
+        return Err(APIError::with_param(
+            APIErrorType::Forbidden,
+            Some("发布需要审批".to_owned()),
+        ));
+    }
+    let keys = prepared.keys();
+    match release::publication_item(
+        prepared.release_id,
+        schedule.name.clone(),
+        schedule.namespace_id,
+        schedule.remark.clone(),
+        prepared.config,
+        prepared.change,
+        schedule.user_id,
+    )
+    .await?
+    {
+        Some(id) => {
+            webhook::notify(
+                WebhookEvent::Publish,
+                prepared.info,
+                Some(id),
+                schedule.user_id,
+                keys,
+            );
+            Ok(id)
+        }
+        None => Err(APIError::new_param_err(ParamErrType::Changed, "items")),
+    }
+}
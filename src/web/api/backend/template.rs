@@ -0,0 +1,226 @@
+use super::dao::{item, namespace, namespace_template};
+use super::response::{APIError, ApiResponse, ParamErrType};
+use super::APIResult;
+use super::{check, ReqJson, ReqQuery};
+use crate::web::api::permission::accredit;
+use crate::web::extract::jwt::Claims;
+use crate::web::extract::response::Empty;
+
+use axum::extract::Json;
+use chrono::Local;
+use entity::namespace_template::{TemplateInfo, TemplateItem};
+use entity::orm::Set;
+use entity::rule::Verb;
+use entity::{ItemActive, ItemModel, NamespaceTemplateActive, NamespaceTemplateModel, ID};
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize)]
+pub struct TemplateParam {
+    pub id: Option<String>, // namespace ID
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub with_value: Option<bool>, // 是否保存当前值作为默认值 默认仅保存 key, 仅应用管理员可保存值
+}
+
+// 将 namespace 的 key 及 key 规则保存为所属应用的模板, 指定时同时保存当前值作为默认值
+pub async fn create(
+    ReqJson(param): ReqJson<TemplateParam>,
+    auth: Claims,
+) -> APIResult<Json<ApiResponse<ID>>> {
+    let ns_id = check::id_decode(param.id, "id")?;
+    let name = match param.name {
+        Some(name) => {
+            let name = check::trim(name);
+            if name.len() < 2 || name.len() > 80 {
+                return Err(APIError::new_param_err(ParamErrType::Len(2, 80), "name"));
+            }
+            name
+        }
+        None => return Err(APIError::new_param_err(ParamErrType::Required, "name")),
+    };
+    let description = check::trim(param.description.unwrap_or_default());
+    if description.len() > 255 {
+        return Err(APIError::new_param_err(
+            ParamErrType::Len(0, 255),
+            "description",
+        ));
+    }
+    let source = namespace::find_by_id(ns_id).await?;
+    if source.is_none() {
+        return Err(APIError::new_param_err(ParamErrType::NotExist, "id"));
+    }
+    let source = source.unwrap();
+    if !accredit::accredit(
+        &auth,
+        Verb::VIEW,
+        vec![&source.app_id, &source.cluster, &source.namespace],
+    )
+    .await?
+    {
+        return Err(APIError::new_permission_forbidden());
+    }
+    // 模板对应用的所有查看者可见, 保存值时需为应用管理员, 避免值泄露给无权查看该 namespace 的用户
+    let with_value = param.with_value.unwrap_or_default();
+    if with_value && !accredit::acc_app_admin(&auth, &source.app_id).await? {
+        return Err(APIError::new_permission_forbidden());
+    }
+    if namespace_template::is_exist(source.app_id.clone(), name.clone()).await? {
+        return Err(APIError::new_param_err(ParamErrType::Exist, "name"));
+    }
+    let items: Vec<TemplateItem> = item::find_by_namespace(ns_id)
+        .await?
+        .into_iter()
+        .map(|i| {
+            let mut item = template_item(i);
+            if !with_value {
+                item.value.clear();
+            }
+            item
+        })
+        .collect();
+    let template = NamespaceTemplateActive {
+        app_id: Set(source.app_id),
+        name: Set(name),
+        description: Set(description),
+        scope: Set(source.scope),
+        key_pattern: Set(source.key_pattern),
+        key_reserved_prefix: Set(source.key_reserved_prefix),
+        key_max_depth: Set(source.key_max_depth),
+        items: Set(serde_json::to_string(&items).unwrap()),
+        creator_user: Set(auth.user_id),
+        ..Default::default()
+    };
+    let id = namespace_template::add(template).await?;
+    Ok(Json(ApiResponse::ok_data(ID::new(id))))
+}
+
+#[derive(Deserialize)]
+pub struct TemplateQueryParam {
+    pub app_id: Option<String>,
+    pub page: Option<String>,
+    pub page_size: Option<String>,
+}
+
+// 获取应用的模板
+pub async fn list(
+    ReqQuery(param): ReqQuery<TemplateQueryParam>,
+    auth: Claims,
+) -> APIResult<Json<ApiResponse<Vec<TemplateInfo>>>> {
+    let app_id = check::id_str(param.app_id, "app_id")?;
+    if !accredit::accredit(&auth, Verb::VIEW, vec![&app_id]).await? {
+        return Err(APIError::new_permission_forbidden());
+    }
+    let (page, page_size) = check::page(param.page, param.page_size);
+    let list = namespace_template::find_by_app(app_id, (page - 1) * page_size, page_size).await?;
+    let mut rsp = ApiResponse::ok_data(list);
+    rsp.set_page(page, page_size);
+    Ok(Json(rsp))
+}
+
+#[derive(Deserialize)]
+pub struct TemplateIdParam {
+    pub id: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct TemplateDetail {
+    #[serde(flatten)]
+    pub template: NamespaceTemplateModel,
+    pub items: Vec<TemplateItem>,
+}
+
+pub async fn detail(
+    ReqQuery(param): ReqQuery<TemplateIdParam>,
+    auth: Claims,
+) -> APIResult<Json<ApiResponse<TemplateDetail>>> {
+    let id = check::id_decode(param.id, "id")?;
+    let template = visible_template(&auth, id).await?;
+    let items = template_items(&template)?;
+    Ok(Json(ApiResponse::ok_data(TemplateDetail {
+        template,
+        items,
+    })))
+}
+
+// 删除模板 仅创建者及应用管理员可操作
+pub async fn delete(
+    ReqJson(param): ReqJson<TemplateIdParam>,
+    auth: Claims,
+) -> APIResult<Json<ApiResponse<Empty>>> {
+    let id = check::id_decode(param.id, "id")?;
+    let template = find_template(id).await?;
+    if template.creator_user != auth.user_id
+        && !accredit::acc_app_admin(&auth, &template.app_id).await?
+    {
+        return Err(APIError::new_permission_forbidden());
+    }
+    let now = Local::now().timestamp() as u64;
+    if !namespace_template::delete(id, now).await? {
+        return Err(APIError::new_param_err(ParamErrType::NotExist, "id"));
+    }
+    Ok(Json(ApiResponse::ok()))
+}
+
+pub async fn find_template(id: u64) -> APIResult<NamespaceTemplateModel> {
+    match namespace_template::find_by_id(id).await? {
+        Some(template) => Ok(template),
+        None => Err(APIError::new_param_err(ParamErrType::NotExist, "template")),
+    }
+}
+
+// 获取模板 需拥有模板所属应用的查看权限
+pub async fn visible_template(auth: &Claims, id: u64) -> APIResult<NamespaceTemplateModel> {
+    let template = find_template(id).await?;
+    if !accredit::accredit(auth, Verb::VIEW, vec![&template.app_id]).await? {
+        return Err(APIError::new_permission_forbidden());
+    }
+    Ok(template)
+}
+
+pub fn template_items(template: &NamespaceTemplateModel) -> APIResult<Vec<TemplateItem>> {
+    match serde_json::from_str::<Vec<TemplateItem>>(&template.items) {
+        Ok(items) => Ok(items),
+        Err(err) => {
+            tracing::error!(
+                "failed to parse template items: {}, {:?}",
+                template.items,
+                err
+            );
+            Err(APIError::new_server_error())
+        }
+    }
+}
+
+pub fn template_item(item: ItemModel) -> TemplateItem {
+    TemplateItem {
+        key: item.key,
+        value: item.value,
+        category: item.category,
+        remark: item.remark,
+    }
+}
+
+// 按目标 namespace 的 key 规则校验并生成草稿 item, 未保存值的 key 生成空值的草稿
+pub fn draft_items(
+    rule: &check::KeyRule,
+    items: Vec<TemplateItem>,
+    user_id: u32,
+) -> APIResult<Vec<ItemActive>> {
+    let mut drafts = Vec::with_capacity(items.len());
+    for i in items.into_iter() {
+        rule.check(&i.key)?;
+        if !i.value.is_empty() {
+            check::item_value(&i.category, &i.value)?;
+        }
+        drafts.push(ItemActive {
+            key: Set(i.key),
+            value: Set(i.value),
+            category: Set(i.category),
+            remark: Set(i.remark),
+            version: Set(1u64),
+            modify_user_id: Set(user_id),
+            ..Default::default()
+        });
+    }
+    Ok(drafts)
+}
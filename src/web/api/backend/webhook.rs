@@ -0,0 +1,528 @@
+use std::net::SocketAddr;
+use std::str::FromStr;
+use std::time::Duration;
+
+use super::dao::{namespace, users, webhook, webhook_delivery};
+use super::response::{APIError, ApiResponse, ParamErrType};
+use super::APIResult;
+use super::{check, ReqJson, ReqQuery};
+use crate::config;
+use crate::web::api::permission::accredit;
+use crate::web::extract::jwt::Claims;
+
+use axum::extract::Json;
+use chrono::Local;
+use entity::namespace::NamespaceInfo;
+use entity::orm::{ActiveModelTrait, Set};
+use entity::rule::Verb;
+use entity::webhook::WebhookEvent;
+use entity::webhook_delivery::DeliveryStatus;
+use entity::{WebhookActive, WebhookDeliveryActive, WebhookDeliveryModel, WebhookModel, ID};
+use hmac::{Hmac, Mac};
+use once_cell::sync::Lazy;
+use rand::{distributions::Alphanumeric, Rng};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use tokio::time::{self, MissedTickBehavior};
+
+const SECRET_LEN: usize = 32;
+const RESPONSE_MAX_LEN: usize = 1024;
+// 每次重新投递的最大数量
+const REDELIVER_BATCH: u64 = 100;
+// 检查未完成投递的间隔 second
+const REDELIVER_INTERVAL: u64 = 60;
+
+static CLIENT: Lazy<reqwest::Client> = Lazy::new(|| {
+    client_builder()
+        .build()
+        .expect("failed to build webhook client")
+});
+
+fn client_builder() -> reqwest::ClientBuilder {
+    reqwest::Client::builder()
+        .timeout(Duration::from_secs(config::get_webhook().timeout))
+        // 不跟随重定向 避免绕过地址校验
+        .redirect(reqwest::redirect::Policy::none())
+}
+
+#[derive(Deserialize)]
+pub struct WebhookParam {
+    pub id: Option<String>,
+    pub app_id: Option<String>,
+    pub namespace: Option<String>, // 为空时订阅 app 下所有 namespace
+    pub name: Option<String>,
+    pub url: Option<String>,
+    pub secret: Option<String>,
+    pub events: Option<Vec<String>>, // publish | rollback | item
+}
+
+#[derive(Serialize)]
+pub struct WebhookSecret {
+    #[serde(serialize_with = "entity::confuse")]
+    pub id: u64,
+    pub secret: String,
+}
+
+// 创建 webhook, 未指定密钥时随机生成
+pub async fn create(
+    ReqJson(param): ReqJson<WebhookParam>,
+    auth: Claims,
+) -> APIResult<Json<ApiResponse<WebhookSecret>>> {
+    let app_id = check::id_str(param.app_id, "app_id")?;
+    let namespace_id = match param.namespace {
+        Some(namespace) => check::id_decode_rule::<u64>(&namespace, "namespace")?,
+        None => 0,
+    };
+    let name = param.name.unwrap_or_default();
+    if name.len() > 64 {
+        return Err(APIError::new_param_err(ParamErrType::Len(0, 64), "name"));
+    }
+    let url = check::webhook_url(param.url)?;
+    let secret = match param.secret {
+        Some(secret) => webhook_secret(secret)?,
+        None => rand::thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(SECRET_LEN)
+            .map(char::from)
+            .collect(),
+    };
+    let events = match param.events {
+        Some(events) => webhook_events(events)?,
+        None => "publish,rollback".to_owned(),
+    };
+    accredit_webhook(&auth, Verb::Modify, &app_id, namespace_id).await?;
+
+    let active = WebhookActive {
+        app_id: Set(app_id),
+        namespace_id: Set(namespace_id),
+        name: Set(name),
+        url: Set(url),
+        secret: Set(secret.clone()),
+        events: Set(events),
+        creator_user: Set(auth.user_id),
+        ..Default::default()
+    };
+    let id = webhook::add(active).await?;
+    Ok(Json(ApiResponse::ok_data(WebhookSecret { id, secret })))
+}
+
+pub async fn edit(
+    ReqJson(param): ReqJson<WebhookParam>,
+    auth: Claims,
+) -> APIResult<Json<ApiResponse<ID>>> {
+    let id = check::id_decode(param.id, "id")?;
+    let hook = find_webhook(id).await?;
+    accredit_webhook(&auth, Verb::Modify, &hook.app_id, hook.namespace_id).await?;
+
+    let mut active = WebhookActive {
+        ..Default::default()
+    };
+    if let Some(name) = param.name {
+        if name.len() > 64 {
+            return Err(APIError::new_param_err(ParamErrType::Len(0, 64), "name"));
+        }
+        active.name = Set(name);
+    }
+    if param.url.is_some() {
+        active.url = Set(check::webhook_url(param.url)?);
+    }
+    if let Some(secret) = param.secret {
+        active.secret = Set(webhook_secret(secret)?);
+    }
+    if let Some(events) = param.events {
+        active.events = Set(webhook_events(events)?);
+    }
+    if active.is_changed() {
+        webhook::update_by_id(active, id).await?;
+    }
+    Ok(Json(ApiResponse::ok_data(ID::new(id))))
+}
+
+#[derive(Deserialize)]
+pub struct WebhookIdParam {
+    pub id: Option<String>,
+}
+
+pub async fn delete(
+    ReqJson(param): ReqJson<WebhookIdParam>,
+    auth: Claims,
+) -> APIResult<Json<ApiResponse<ID>>> {
+    let id = check::id_decode(param.id, "id")?;
+    let hook = find_webhook(id).await?;
+    accredit_webhook(&auth, Verb::Modify, &hook.app_id, hook.namespace_id).await?;
+    if !webhook::delete(id).await? {
+        return Err(APIError::new_param_err(ParamErrType::NotExist, "id"));
+    }
+    Ok(Json(ApiResponse::ok_data(ID::new(id))))
+}
+
+#[derive(Deserialize)]
+pub struct WebhookQueryParam {
+    pub app_id: Option<String>,
+    pub page: Option<String>,
+    pub page_size: Option<String>,
+}
+
+// 获取 app 的 webhook
+pub async fn list(
+    ReqQuery(param): ReqQuery<WebhookQueryParam>,
+    auth: Claims,
+) -> APIResult<Json<ApiResponse<Vec<WebhookModel>>>> {
+    let app_id = check::id_str(param.app_id, "app_id")?;
+    if !accredit::accredit(&auth, Verb::VIEW, vec![&app_id]).await? {
+        return Err(APIError::new_permission_forbidden());
+    }
+    let (page, page_size) = check::page(param.page, param.page_size);
+    let list = webhook::get_app_webhook(app_id, (page - 1) * page_size, page_size).await?;
+    let mut rsp = ApiResponse::ok_data(list);
+    rsp.set_page(page, page_size);
+    Ok(Json(rsp))
+}
+
+#[derive(Deserialize)]
+pub struct DeliveryQueryParam {
+    pub id: Option<String>, // webhook ID
+    pub page: Option<String>,
+    pub page_size: Option<String>,
+}
+
+// 获取 webhook 的投递记录
+pub async fn delivery(
+    ReqQuery(param): ReqQuery<DeliveryQueryParam>,
+    auth: Claims,
+) -> APIResult<Json<ApiResponse<Vec<WebhookDeliveryModel>>>> {
+    let id = check::id_decode(param.id, "id")?;
+    let hook = find_webhook(id).await?;
+    accredit_webhook(&auth, Verb::VIEW, &hook.app_id, hook.namespace_id).await?;
+    let (page, page_size) = check::page(param.page, param.page_size);
+    let list =
+        webhook_delivery::get_webhook_delivery(id, (page - 1) * page_size, page_size).await?;
+    let mut rsp = ApiResponse::ok_data(list);
+    rsp.set_page(page, page_size);
+    Ok(Json(rsp))
+}
+
+async fn find_webhook(id: u64) -> APIResult<WebhookModel> {
+    match webhook::find_by_id(id).await? {
+        Some(hook) => Ok(hook),
+        None => Err(APIError::new_param_err(ParamErrType::NotExist, "id")),
+    }
+}
+
+// 订阅 namespace 时校验 namespace 的权限, 否则校验 app 的权限
+async fn accredit_webhook(
+    auth: &Claims,
+    verb: Verb,
+    app_id: &str,
+    namespace_id: u64,
+) -> APIResult<()> {
+    let allowed = if namespace_id == 0 {
+        accredit::accredit(auth, verb, vec![app_id]).await?
+    } else {
+        let info = namespace::get_app_info(namespace_id).await?;
+        if info.is_none() {
+            return Err(APIError::new_param_err(ParamErrType::NotExist, "namespace"));
+        }
+        let info = info.unwrap();
+        if info.app_id != app_id {
+            return Err(APIError::new_param_err(ParamErrType::Invalid, "namespace"));
+        }
+        accredit::accredit(
+            auth,
+            verb,
+            vec![&info.app_id, &info.cluster, &info.namespace],
+        )
+        .await?
+    };
+    if !allowed {
+        return Err(APIError::new_permission_forbidden());
+    }
+    Ok(())
+}
+
+fn webhook_secret(secret: String) -> APIResult<String> {
+    if secret.is_empty() || secret.len() > 128 {
+        return Err(APIError::new_param_err(ParamErrType::Len(1, 128), "secret"));
+    }
+    Ok(secret)
+}
+
+fn webhook_events(events: Vec<String>) -> APIResult<String> {
+    let mut list: Vec<&str> = Vec::with_capacity(events.len());
+    for event in events.iter() {
+        match WebhookEvent::from_str(event) {
+            Ok(event) => {
+                if !list.contains(&event.as_str()) {
+                    list.push(event.as_str());
+                }
+            }
+            Err(_) => return Err(APIError::new_param_err(ParamErrType::Invalid, "events")),
+        }
+    }
+    if list.is_empty() {
+        return Err(APIError::new_param_err(ParamErrType::Required, "events"));
+    }
+    Ok(list.join(","))
+}
+
+#[derive(Serialize)]
+pub struct Payload {
+    pub event: WebhookEvent,
+    pub app_id: String,
+    pub cluster: String,
+    pub namespace: String,
+    #[serde(serialize_with = "entity::confuse")]
+    pub namespace_id: u64,
+    // item 修改时为空
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub release_id: Option<String>,
+    #[serde(serialize_with = "entity::confuse")]
+    pub user_id: u32,
+    pub nickname: String,
+    pub keys: Vec<String>, // 变更的 key
+    pub timestamp: i64,
+}
+
+// 向订阅的 webhook 发送通知, 在后台执行不阻塞请求
+pub fn notify(
+    event: WebhookEvent,
+    info: NamespaceInfo,
+    release_id: Option<u64>,
+    user_id: u32,
+    keys: Vec<String>,
+) {
+    tokio::spawn(async move {
+        let hooks = match webhook::find_subscriber(info.app_id.clone(), info.id).await {
+            Ok(hooks) => hooks,
+            Err(err) => {
+                tracing::error!(
+                    "failed to load webhook of namespace {}. err: {:?}",
+                    info.id,
+                    err
+                );
+                return;
+            }
+        };
+        let hooks: Vec<WebhookModel> = hooks.into_iter().filter(|h| h.subscribed(&event)).collect();
+        if hooks.is_empty() {
+            return;
+        }
+        let nickname = match users::get_info(user_id).await {
+            Ok(user) => user.map(|u| u.nickname).unwrap_or_default(),
+            Err(_) => String::new(),
+        };
+        let payload = Payload {
+            event: event.clone(),
+            app_id: info.app_id,
+            cluster: info.cluster,
+            namespace: info.namespace,
+            namespace_id: info.id,
+            release_id: release_id.map(entity::utils::encode_u64),
+            user_id,
+            nickname,
+            keys,
+            timestamp: Local::now().timestamp(),
+        };
+        let body = serde_json::to_string(&payload).unwrap();
+        for hook in hooks.into_iter() {
+            tokio::spawn(deliver(hook, event.clone(), body.clone()));
+        }
+    });
+}
+
+// 记录投递并发送
+async fn deliver(hook: WebhookModel, event: WebhookEvent, body: String) {
+    let active = WebhookDeliveryActive {
+        webhook_id: Set(hook.id),
+        event: Set(event.as_str().to_owned()),
+        payload: Set(body.clone()),
+        ..Default::default()
+    };
+    let delivery_id = match webhook_delivery::add(active).await {
+        Ok(id) => id,
+        Err(err) => {
+            tracing::error!("failed to add webhook {} delivery. err: {:?}", hook.id, err);
+            return;
+        }
+    };
+    send(&hook, delivery_id, event.as_str(), &body, 0).await;
+}
+
+// 从第 attempted + 1 次开始请求并记录结果 失败时按指数退避重试
+async fn send(hook: &WebhookModel, delivery_id: u64, event: &str, body: &str, attempted: u8) {
+    let conf = config::get_webhook();
+    let signature = sign(&hook.secret, body);
+    let attempts = conf.retry.saturating_add(1);
+    for attempt in attempted.saturating_add(1)..=attempts {
+        let result = match resolve_url(&hook.url).await {
+            Ok(client) => client
+                .post(&hook.url)
+                .header("Content-Type", "application/json")
+                .header("X-Pilot-Event", event)
+                .header("X-Pilot-Delivery", entity::utils::encode_u64(delivery_id))
+                .header("X-Pilot-Signature", format!("sha256={}", signature))
+                .body(body.to_owned())
+                .send()
+                .await
+                .map_err(|err| err.to_string()),
+            Err(reason) => Err(reason),
+        };
+        let (success, code, response) = match result {
+            Ok(rsp) => {
+                let status = rsp.status();
+                let text = rsp.text().await.unwrap_or_default();
+                (status.is_success(), status.as_u16(), text)
+            }
+            Err(reason) => (false, 0, reason),
+        };
+        let status = if success {
+            DeliveryStatus::Success
+        } else if attempt == attempts {
+            DeliveryStatus::Failed
+        } else {
+            DeliveryStatus::Pending
+        };
+        let active = WebhookDeliveryActive {
+            status: Set(status),
+            attempts: Set(attempt),
+            response_code: Set(code),
+            response: Set(response.chars().take(RESPONSE_MAX_LEN).collect()),
+            ..Default::default()
+        };
+        if let Err(err) = webhook_delivery::update_by_id(active, delivery_id).await {
+            tracing::error!(
+                "failed to update webhook delivery {}. err: {:?}",
+                delivery_id,
+                err
+            );
+        }
+        if success {
+            return;
+        }
+        if attempt < attempts {
+            let backoff = conf.backoff.saturating_mul(1 << (attempt - 1).min(16));
+            tokio::time::sleep(Duration::from_secs(backoff)).await;
+        }
+    }
+    tracing::warn!("webhook {} delivery {} failed", hook.id, delivery_id);
+}
+
+// 解析 webhook 地址的域名, 未开启 allow_private 时拒绝解析到内网的地址
+// 并返回固定使用已校验地址的 client, 避免请求时重新解析到其他地址
+async fn resolve_url(url: &str) -> Result<reqwest::Client, String> {
+    if config::get_webhook().allow_private {
+        return Ok(CLIENT.clone());
+    }
+    let parsed = reqwest::Url::parse(url).map_err(|err| err.to_string())?;
+    let host = parsed.host_str().unwrap_or_default();
+    let domain = host.trim_start_matches('[').trim_end_matches(']');
+    let port = parsed.port_or_known_default().unwrap_or(80);
+    let addrs: Vec<SocketAddr> = tokio::net::lookup_host((domain, port))
+        .await
+        .map_err(|err| format!("failed to resolve {}: {}", host, err))?
+        .collect();
+    if addrs.is_empty() {
+        return Err(format!("failed to resolve {}", host));
+    }
+    for addr in addrs.iter() {
+        if !check::public_ip(&addr.ip()) {
+            return Err(format!("{} resolves to a private address", host));
+        }
+    }
+    client_builder()
+        .resolve_to_addrs(domain, &addrs)
+        .build()
+        .map_err(|err| err.to_string())
+}
+
+// 启动未完成投递的重新投递任务, 投递中的实例中断后由任一实例接管
+pub fn run_redeliver() {
+    tokio::spawn(async move {
+        let mut tick = time::interval(Duration::from_secs(REDELIVER_INTERVAL));
+        tick.set_missed_tick_behavior(MissedTickBehavior::Skip);
+        loop {
+            tick.tick().await;
+            redeliver().await;
+        }
+    });
+}
+
+// 重新投递超过最长重试间隔仍未更新的投递
+async fn redeliver() {
+    let conf = config::get_webhook();
+    let backoff = conf
+        .backoff
+        .saturating_mul(1 << conf.retry.saturating_sub(1).min(16));
+    let stale = conf.timeout.saturating_add(backoff) + REDELIVER_INTERVAL;
+    let updated_before = Local::now() - chrono::Duration::seconds(stale as i64);
+    let list = match webhook_delivery::get_stale(updated_before.into(), REDELIVER_BATCH).await {
+        Ok(list) => list,
+        Err(err) => {
+            tracing::error!("failed to load pending webhook deliveries. err: {:?}", err);
+            return;
+        }
+    };
+    for delivery in list.into_iter() {
+        // 抢占投递 多实例部署时仅一个实例接管
+        match webhook_delivery::claim(&delivery).await {
+            Ok(true) => (),
+            Ok(false) => continue,
+            Err(err) => {
+                tracing::error!(
+                    "failed to claim webhook delivery {}. err: {:?}",
+                    delivery.id,
+                    err
+                );
+                continue;
+            }
+        }
+        let hook = match webhook::find_by_id(delivery.webhook_id).await {
+            Ok(Some(hook)) => hook,
+            Ok(None) => {
+                let active = WebhookDeliveryActive {
+                    status: Set(DeliveryStatus::Failed),
+                    response: Set("webhook 已删除".to_owned()),
+                    ..Default::default()
+                };
+                if let Err(err) = webhook_delivery::update_by_id(active, delivery.id).await {
+                    tracing::error!(
+                        "failed to update webhook delivery {}. err: {:?}",
+                        delivery.id,
+                        err
+                    );
+                }
+                continue;
+            }
+            Err(err) => {
+                tracing::error!(
+                    "failed to load webhook {}. err: {:?}",
+                    delivery.webhook_id,
+                    err
+                );
+                continue;
+            }
+        };
+        tokio::spawn(async move {
+            send(
+                &hook,
+                delivery.id,
+                &delivery.event,
+                &delivery.payload,
+                delivery.attempts,
+            )
+            .await
+        });
+    }
+}
+
+// HMAC-SHA256 签名 以十六进制表示
+fn sign(secret: &str, body: &str) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC can take key of any size");
+    mac.update(body.as_bytes());
+    mac.finalize()
+        .into_bytes()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
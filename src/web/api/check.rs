@@ -1,17 +1,26 @@
-use crate::web::extract::response::{APIError, ParamErrType};
+use crate::web::extract::response::{APIError, APIErrorType, ParamErrType};
 
+use crate::config;
+
+use std::net::IpAddr;
+
+use entity::namespace::KeyPolicy;
+use entity::ItemCategory;
 use once_cell::sync::Lazy;
 use regex::Regex;
 
 const ID_MIN_LEN: usize = 2;
 const ID_MAX_LEN: usize = 80;
+const KEY_MAX_LEN: usize = 255;
+const KEY_DEPTH_PAT: char = '.';
+const VALUE_MAX_LEN: usize = 65535;
+const URL_MAX_LEN: usize = 1024;
 
 struct Re {
     id_str: Regex,
     account: Regex,
     password: Regex,
     email: Regex,
-    key: Regex,
 }
 
 static RE: Lazy<Re> = Lazy::new(|| Re {
@@ -23,8 +32,6 @@ static RE: Lazy<Re> = Lazy::new(|| Re {
         .expect("Failed to initialize the [password] regular expression"),
     email: Regex::new(r"\w+([-+.]\w+)*@\w+([-.]\w+)*\.\w+([-.]\w+)*")
         .expect("Failed to initialize the [email] regular expression"),
-    key: Regex::new(r"^[a-z0-9_-]{1,255}$")
-        .expect("Failed to initialize the [key] regular expression"),
 });
 
 pub fn account(account: Option<String>) -> Result<String, APIError> {
@@ -112,29 +119,6 @@ pub fn trim(f: String) -> String {
     f.trim_start().trim_end().to_owned()
 }
 
-pub fn id_str_len(
-    id: Option<String>,
-    field: &str,
-    min: Option<usize>,
-    max: Option<usize>,
-) -> Result<String, APIError> {
-    match id {
-        Some(id) => {
-            if id.len() < min.unwrap_or(ID_MIN_LEN) || id.len() > max.unwrap_or(ID_MAX_LEN) {
-                return Err(APIError::new_param_err(
-                    ParamErrType::Len(ID_MIN_LEN, ID_MAX_LEN),
-                    field,
-                ));
-            }
-            if !RE.key.is_match(&id) {
-                return Err(APIError::new_param_err(ParamErrType::Invalid, field));
-            }
-            Ok(id)
-        }
-        None => return Err(APIError::new_param_err(ParamErrType::Required, field)),
-    }
-}
-
 pub fn id_str(id: Option<String>, field: &str) -> Result<String, APIError> {
     match id {
         Some(id) => {
@@ -153,24 +137,6 @@ pub fn id_str(id: Option<String>, field: &str) -> Result<String, APIError> {
     }
 }
 
-pub fn id_str_len_rule(
-    id: &String,
-    field: &str,
-    min: Option<usize>,
-    max: Option<usize>,
-) -> Result<(), APIError> {
-    if id.len() < min.unwrap_or(ID_MIN_LEN) || id.len() > max.unwrap_or(ID_MAX_LEN) {
-        return Err(APIError::new_param_err(
-            ParamErrType::Len(ID_MIN_LEN, ID_MAX_LEN),
-            field,
-        ));
-    }
-    if !RE.key.is_match(id) {
-        return Err(APIError::new_param_err(ParamErrType::Invalid, field));
-    }
-    Ok(())
-}
-
 pub fn id_str_rule(id: String, field: &str) -> Result<String, APIError> {
     if id.len() < ID_MIN_LEN || id.len() > ID_MAX_LEN {
         return Err(APIError::new_param_err(
@@ -223,3 +189,338 @@ pub fn page(page: Option<String>, page_size: Option<String>) -> (u64, u64) {
     }
     (page, page_size)
 }
+
+// namespace 的 key 命名规则
+pub struct KeyRule {
+    pattern: Regex,
+    reserved_prefix: Vec<String>,
+    max_depth: usize,
+}
+
+impl KeyRule {
+    pub fn new(policy: KeyPolicy) -> Result<Self, APIError> {
+        let pattern = match Regex::new(&policy.key_pattern) {
+            Ok(pattern) => pattern,
+            Err(err) => {
+                tracing::error!("invalid key pattern {}: {}", &policy.key_pattern, err);
+                return Err(APIError::new_param_err(
+                    ParamErrType::Invalid,
+                    "key_pattern",
+                ));
+            }
+        };
+        Ok(Self {
+            pattern,
+            reserved_prefix: key_reserved_prefix(&policy.key_reserved_prefix),
+            max_depth: policy.key_max_depth as usize,
+        })
+    }
+
+    pub fn check(&self, key: &str) -> Result<(), APIError> {
+        if key.is_empty() || key.len() > KEY_MAX_LEN {
+            return Err(APIError::new_param_err(
+                ParamErrType::Len(1, KEY_MAX_LEN),
+                "key",
+            ));
+        }
+        if !self.pattern.is_match(key) {
+            return Err(APIError::with_param(
+                APIErrorType::BadParam(ParamErrType::Invalid),
+                Some(format!(
+                    "The key {} does not match {}",
+                    key,
+                    self.pattern.as_str()
+                )),
+            ));
+        }
+        for prefix in self.reserved_prefix.iter() {
+            if key.starts_with(prefix.as_str()) {
+                return Err(APIError::with_param(
+                    APIErrorType::BadParam(ParamErrType::Invalid),
+                    Some(format!("The key prefix {} is reserved", prefix)),
+                ));
+            }
+        }
+        if self.max_depth != 0 && key.split(KEY_DEPTH_PAT).count() > self.max_depth {
+            return Err(APIError::with_param(
+                APIErrorType::BadParam(ParamErrType::Invalid),
+                Some(format!(
+                    "The depth of key should not exceed {}",
+                    self.max_depth
+                )),
+            ));
+        }
+        Ok(())
+    }
+}
+
+// 解析以逗号分隔的保留前缀
+pub fn key_reserved_prefix(prefix: &str) -> Vec<String> {
+    prefix
+        .split(',')
+        .map(|p| p.trim())
+        .filter(|p| !p.is_empty())
+        .map(|p| p.to_owned())
+        .collect()
+}
+
+// key 的正则规则, 为空时不限制
+pub fn key_pattern(pattern: Option<String>) -> Result<Option<String>, APIError> {
+    match pattern {
+        Some(pattern) => {
+            if pattern.len() > 255 {
+                return Err(APIError::new_param_err(
+                    ParamErrType::Len(0, 255),
+                    "key_pattern",
+                ));
+            }
+            if Regex::new(&pattern).is_err() {
+                return Err(APIError::new_param_err(
+                    ParamErrType::Invalid,
+                    "key_pattern",
+                ));
+            }
+            Ok(Some(pattern))
+        }
+        None => Ok(None),
+    }
+}
+
+pub fn item_category(category: Option<String>, field: &str) -> Result<ItemCategory, APIError> {
+    match category {
+        Some(category) => match category.parse::<ItemCategory>() {
+            Ok(category) => Ok(category),
+            Err(_) => Err(APIError::new_param_err(ParamErrType::Invalid, field)),
+        },
+        None => Ok(ItemCategory::default()),
+    }
+}
+
+// 按类型校验 item 的值
+pub fn item_value(category: &ItemCategory, value: &str) -> Result<(), APIError> {
+    if value.len() > VALUE_MAX_LEN {
+        return Err(APIError::new_param_err(
+            ParamErrType::Len(0, VALUE_MAX_LEN),
+            "value",
+        ));
+    }
+    let reason = match category {
+        ItemCategory::Text => None,
+        ItemCategory::Number => number_value(value),
+        ItemCategory::Boolean => match value {
+            "true" | "false" => None,
+            _ => Some("The value should be true or false".to_owned()),
+        },
+        ItemCategory::Json => serde_json::from_str::<serde_json::Value>(value)
+            .err()
+            .map(|err| format!("Invalid json: {}", err)),
+        ItemCategory::Yaml => serde_yaml::from_str::<serde_yaml::Value>(value)
+            .err()
+            .map(|err| format!("Invalid yaml: {}", err)),
+        ItemCategory::Toml => toml::from_str::<toml::Value>(value)
+            .err()
+            .map(|err| format!("Invalid toml: {}", err)),
+        ItemCategory::Properties => properties_value(value),
+        ItemCategory::Xml => xml_value(value),
+        ItemCategory::File => file_value(value),
+    };
+    match reason {
+        Some(reason) => Err(APIError::with_param(
+            APIErrorType::BadParam(ParamErrType::Invalid),
+            Some(reason),
+        )),
+        None => Ok(()),
+    }
+}
+
+fn number_value(value: &str) -> Option<String> {
+    if value.parse::<i64>().is_ok() || value.parse::<u64>().is_ok() {
+        return None;
+    }
+    // 排除 NaN inf 等非 json 数值
+    match value.parse::<f64>() {
+        Ok(v) if v.is_finite() => None,
+        _ => Some("The value should be a number".to_owned()),
+    }
+}
+
+// 校验 java properties 格式
+fn properties_value(value: &str) -> Option<String> {
+    let mut lines = value.lines().enumerate();
+    while let Some((no, line)) = lines.next() {
+        let mut logical = line.trim_start().to_owned();
+        if logical.is_empty() || logical.starts_with('#') || logical.starts_with('!') {
+            continue;
+        }
+        // 以奇数个 \ 结尾的行与下一行合并
+        while logical.chars().rev().take_while(|c| *c == '\\').count() % 2 == 1 {
+            logical.pop();
+            match lines.next() {
+                Some((_, next)) => logical.push_str(next.trim_start()),
+                None => break,
+            }
+        }
+        if logical.starts_with('=') || logical.starts_with(':') {
+            return Some(format!("Missing key at line {}", no + 1));
+        }
+        if !properties_escape(&logical) {
+            return Some(format!("Invalid unicode escape at line {}", no + 1));
+        }
+    }
+    None
+}
+
+// \uXXXX 转义需要 4 位十六进制
+fn properties_escape(line: &str) -> bool {
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' && chars.next() == Some('u') {
+            let hex: String = chars.by_ref().take(4).collect();
+            if hex.len() != 4 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+                return false;
+            }
+        }
+    }
+    true
+}
+
+// 校验 xml 格式, 要求有且仅有一个根元素
+fn xml_value(value: &str) -> Option<String> {
+    use quick_xml::events::Event;
+
+    let mut reader = quick_xml::Reader::from_str(value);
+    reader.check_end_names(true);
+    let mut buf = Vec::new();
+    let mut depth = 0_usize;
+    let mut roots = 0_usize;
+    loop {
+        match reader.read_event(&mut buf) {
+            Ok(Event::Start(_)) => {
+                if depth == 0 {
+                    roots += 1;
+                }
+                depth += 1;
+            }
+            Ok(Event::Empty(_)) => {
+                if depth == 0 {
+                    roots += 1;
+                }
+            }
+            Ok(Event::End(_)) => depth -= 1,
+            Ok(Event::Text(text)) => {
+                if depth == 0 && !text.iter().all(|c| c.is_ascii_whitespace()) {
+                    return Some("Text outside of the root element".to_owned());
+                }
+            }
+            Ok(Event::Eof) => break,
+            Ok(_) => (),
+            Err(err) => {
+                return Some(format!(
+                    "Invalid xml at position {}: {}",
+                    reader.buffer_position(),
+                    err
+                ))
+            }
+        }
+        buf.clear();
+    }
+    if depth != 0 {
+        return Some("Unclosed xml element".to_owned());
+    }
+    if roots != 1 {
+        return Some("The xml should have exactly one root element".to_owned());
+    }
+    None
+}
+
+// file 类型的值为 base64 编码
+fn file_value(value: &str) -> Option<String> {
+    let max_size = config::get_item().file_max_size;
+    match base64::decode(value) {
+        Ok(content) => {
+            if content.len() > max_size {
+                Some(format!(
+                    "The file size should not exceed {} bytes",
+                    max_size
+                ))
+            } else {
+                None
+            }
+        }
+        Err(err) => Some(format!("Invalid base64 content: {}", err)),
+    }
+}
+
+// webhook 地址仅允许 http(s), 未开启 allow_private 时禁止回环、链路本地及内网地址
+// 域名在投递时解析后再次校验
+pub fn webhook_url(url: Option<String>) -> Result<String, APIError> {
+    let url = match url {
+        Some(url) => trim(url),
+        None => return Err(APIError::new_param_err(ParamErrType::Required, "url")),
+    };
+    if url.is_empty() || url.len() > URL_MAX_LEN {
+        return Err(APIError::new_param_err(
+            ParamErrType::Len(1, URL_MAX_LEN),
+            "url",
+        ));
+    }
+    let parsed = match reqwest::Url::parse(&url) {
+        Ok(parsed) => parsed,
+        Err(_) => return Err(APIError::new_param_err(ParamErrType::Invalid, "url")),
+    };
+    if parsed.scheme() != "http" && parsed.scheme() != "https" {
+        return Err(APIError::new_param_err(ParamErrType::Invalid, "url"));
+    }
+    if config::get_webhook().allow_private {
+        return Ok(url);
+    }
+    let host = parsed.host_str().unwrap_or_default();
+    let allowed = match host
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .parse::<IpAddr>()
+    {
+        Ok(ip) => public_ip(&ip),
+        Err(_) => {
+            let domain = host.trim_end_matches('.').to_ascii_lowercase();
+            !domain.is_empty() && domain != "localhost" && !domain.ends_with(".localhost")
+        }
+    };
+    if !allowed {
+        return Err(APIError::with_param(
+            APIErrorType::BadParam(ParamErrType::Invalid),
+            Some("The url should not target a private address".to_owned()),
+        ));
+    }
+    Ok(url)
+}
+
+// 是否为公网地址, 回环、链路本地、内网及保留地址返回 false
+pub fn public_ip(ip: &IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let octets = ip.octets();
+            !(ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_documentation()
+                // 100.64.0.0/10 运营商级 NAT
+                || (octets[0] == 100 && octets[1] & 0xc0 == 64)
+                || octets[0] == 0)
+        }
+        IpAddr::V6(ip) => {
+            if let Some(v4) = ip.to_ipv4_mapped() {
+                return public_ip(&IpAddr::V4(v4));
+            }
+            let segment = ip.segments()[0];
+            !(ip.is_loopback()
+                || ip.is_unspecified()
+                // fc00::/7 唯一本地地址
+                || segment & 0xfe00 == 0xfc00
+                // fe80::/10 链路本地地址
+                || segment & 0xffc0 == 0xfe80)
+        }
+    }
+}
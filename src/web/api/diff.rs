@@ -0,0 +1,226 @@
+use std::collections::HashMap;
+
+use crate::web::extract::response::{APIError, ParamErrType};
+
+use ahash::RandomState;
+use entity::item::ItemDesc;
+use entity::ItemCategory;
+use serde::Serialize;
+use serde_json::Value;
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeKind {
+    // 与已发布一致
+    Normal,
+    // 未发布的新增
+    New,
+    // 未发布的修改
+    Modified,
+    // 未发布的删除
+    Deleted,
+}
+
+// 解析发布的配置
+pub fn parse_configurations(configurations: &str) -> Result<Vec<ItemDesc>, APIError> {
+    match serde_json::from_str::<Vec<ItemDesc>>(configurations) {
+        Ok(items) => Ok(items),
+        Err(err) => {
+            tracing::error!(
+                "failed to parse release config data: {}, {:?}",
+                configurations,
+                err
+            );
+            Err(APIError::new_param_err(ParamErrType::Invalid, "namespace"))
+        }
+    }
+}
+
+// 以 key 为索引的已发布配置
+pub fn published_map(published: Vec<ItemDesc>) -> HashMap<String, ItemDesc, RandomState> {
+    let mut map = HashMap::with_capacity_and_hasher(published.len(), RandomState::new());
+    for item in published.into_iter() {
+        map.insert(item.key.clone(), item);
+    }
+    map
+}
+
+// 对比当前 item 与已发布的 item
+pub fn change_kind(item: &ItemDesc, published: Option<&ItemDesc>) -> ChangeKind {
+    match published {
+        Some(p) => {
+            if p.value == item.value && p.category == item.category {
+                ChangeKind::Normal
+            } else {
+                ChangeKind::Modified
+            }
+        }
+        None => ChangeKind::New,
+    }
+}
+
+// 统计未发布的修改数量
+pub fn pending_count(items: &[ItemDesc], published: Vec<ItemDesc>) -> usize {
+    let mut published = published_map(published);
+    let mut count = 0;
+    for item in items.iter() {
+        if change_kind(item, published.get(&item.key)) != ChangeKind::Normal {
+            count += 1;
+        }
+        published.remove(&item.key);
+    }
+    // 剩余的已发布 key 均已被删除
+    count + published.len()
+}
+
+// key 的变更
+#[derive(Serialize, Debug, Clone)]
+pub struct KeyChange {
+    pub key: String,
+    pub kind: ChangeKind,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub old_value: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub old_category: Option<ItemCategory>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub new_value: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub new_category: Option<ItemCategory>,
+    // json yaml toml 类型的结构化差异
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fields: Option<Vec<FieldChange>>,
+}
+
+// 结构化数据中字段的变更 path 形如 a.b[0].c
+#[derive(Serialize, Debug, Clone)]
+pub struct FieldChange {
+    pub path: String,
+    pub kind: ChangeKind,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub old_value: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub new_value: Option<Value>,
+}
+
+// 对比两组配置 返回以 key 排序的变更, 不包含未变化的 key
+pub fn diff_items(old: Vec<ItemDesc>, new: Vec<ItemDesc>) -> Vec<KeyChange> {
+    let mut old = published_map(old);
+    let mut changes = Vec::new();
+    for item in new.into_iter() {
+        let o = old.remove(&item.key);
+        let kind = change_kind(&item, o.as_ref());
+        if kind == ChangeKind::Normal {
+            continue;
+        }
+        let (old_value, old_category, fields) = match o {
+            Some(o) => {
+                let fields = if o.category == item.category {
+                    structural_diff(&item.category, &o.value, &item.value)
+                } else {
+                    None
+                };
+                (Some(o.value), Some(o.category), fields)
+            }
+            None => (None, None, None),
+        };
+        changes.push(KeyChange {
+            key: item.key,
+            kind,
+            old_value,
+            old_category,
+            new_value: Some(item.value),
+            new_category: Some(item.category),
+            fields,
+        });
+    }
+    for (key, o) in old.into_iter() {
+        changes.push(KeyChange {
+            key,
+            kind: ChangeKind::Deleted,
+            old_value: Some(o.value),
+            old_category: Some(o.category),
+            new_value: None,
+            new_category: None,
+            fields: None,
+        });
+    }
+    changes.sort_by(|a, b| a.key.cmp(&b.key));
+    changes
+}
+
+// 解析为结构化数据 非结构化类型或解析失败时返回 None
+fn structured_value(category: &ItemCategory, value: &str) -> Option<Value> {
+    match category {
+        ItemCategory::Json => serde_json::from_str(value).ok(),
+        ItemCategory::Yaml => serde_yaml::from_str(value).ok(),
+        ItemCategory::Toml => toml::from_str::<toml::Value>(value)
+            .ok()
+            .and_then(|v| serde_json::to_value(v).ok()),
+        _ => None,
+    }
+}
+
+// 对比结构化的值
+pub fn structural_diff(category: &ItemCategory, old: &str, new: &str) -> Option<Vec<FieldChange>> {
+    let old = structured_value(category, old)?;
+    let new = structured_value(category, new)?;
+    let mut changes = Vec::new();
+    diff_value(String::new(), &old, &new, &mut changes);
+    Some(changes)
+}
+
+fn diff_value(path: String, old: &Value, new: &Value, changes: &mut Vec<FieldChange>) {
+    match (old, new) {
+        (Value::Object(o), Value::Object(n)) => {
+            for (k, ov) in o.iter() {
+                let p = if path.is_empty() {
+                    k.clone()
+                } else {
+                    format!("{}.{}", path, k)
+                };
+                match n.get(k) {
+                    Some(nv) => diff_value(p, ov, nv, changes),
+                    None => changes.push(field_change(p, Some(ov), None)),
+                }
+            }
+            for (k, nv) in n.iter() {
+                if !o.contains_key(k) {
+                    let p = if path.is_empty() {
+                        k.clone()
+                    } else {
+                        format!("{}.{}", path, k)
+                    };
+                    changes.push(field_change(p, None, Some(nv)));
+                }
+            }
+        }
+        (Value::Array(o), Value::Array(n)) => {
+            for i in 0..o.len().max(n.len()) {
+                let p = format!("{}[{}]", path, i);
+                match (o.get(i), n.get(i)) {
+                    (Some(ov), Some(nv)) => diff_value(p, ov, nv, changes),
+                    (ov, nv) => changes.push(field_change(p, ov, nv)),
+                }
+            }
+        }
+        _ => {
+            if old != new {
+                changes.push(field_change(path, Some(old), Some(new)));
+            }
+        }
+    }
+}
+
+fn field_change(path: String, old: Option<&Value>, new: Option<&Value>) -> FieldChange {
+    let kind = match (old, new) {
+        (None, _) => ChangeKind::New,
+        (_, None) => ChangeKind::Deleted,
+        _ => ChangeKind::Modified,
+    };
+    FieldChange {
+        path,
+        kind,
+        old_value: old.cloned(),
+        new_value: new.cloned(),
+    }
+}
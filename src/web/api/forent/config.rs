@@ -12,6 +12,7 @@ use crate::web::{
 };
 
 use axum::extract::Extension;
+use axum::http::HeaderMap;
 use axum::Json;
 use serde::{Deserialize, Serialize};
 use tokio::time;
@@ -22,6 +23,7 @@ pub struct DescParam {
     pub cluster: Option<String>,
     pub namespace: Option<String>,
     pub secret: Option<String>,
+    pub env: Option<String>, // 客户端期望的环境 与集群所属环境不一致时拒绝
     pub version: Option<u64>,
     pub timeout: Option<u64>,
 }
@@ -32,7 +34,7 @@ const DEFAULT_TIMEOUT: Duration = Duration::from_secs(60);
 pub async fn description(
     ReqQuery(param): ReqQuery<DescParam>,
     Extension(cache): Extension<CacheItem>,
-) -> APIResult<Json<ApiResponse<NamespaceItem>>> {
+) -> APIResult<(HeaderMap, Json<ApiResponse<NamespaceItem>>)> {
     let app_id = match param.app_id {
         Some(app_id) => {
             if app_id.len() == 0 || app_id.len() > 100 {
@@ -78,6 +80,9 @@ pub async fn description(
             {
                 return Err(APIError::new_param_err(ParamErrType::Invalid, "secret"));
             }
+            if param.env.is_some() && param.env.as_ref() != Some(&secret.env) {
+                return Err(APIError::new_param_err(ParamErrType::Invalid, "env"));
+            }
         }
         None => return Err(APIError::new_param_err(ParamErrType::NotExist, "app_id")),
     };
@@ -99,20 +104,28 @@ pub async fn description(
     .await;
     if namespace_item.is_err() {
         // 超时 无更新
-        return Ok(Json(ApiResponse::ok()));
+        return Ok((HeaderMap::new(), Json(ApiResponse::ok())));
     }
     let namespace_item = namespace_item.unwrap();
     if namespace_item.is_none() {
-        return Ok(Json(ApiResponse::ok()));
+        return Ok((HeaderMap::new(), Json(ApiResponse::ok())));
+    }
+    let namespace_item = namespace_item.unwrap();
+    // 等待期间 namespace 被删除
+    if namespace_item.is_removed() {
+        return Err(APIError::new_param_err(ParamErrType::NotExist, "namespace"));
     }
-    Ok(Json(ApiResponse::ok_data(namespace_item.unwrap())))
+    Ok((
+        item_header(&namespace_item),
+        Json(ApiResponse::ok_data(namespace_item)),
+    ))
 }
 
 // 阻塞链接, 仅更新时返回数据
 pub async fn notifaction(
     ReqQuery(param): ReqQuery<DescParam>,
     Extension(cache): Extension<CacheItem>,
-) -> APIResult<Json<ApiResponse<NamespaceItem>>> {
+) -> APIResult<(HeaderMap, Json<ApiResponse<NamespaceItem>>)> {
     let app_id = match param.app_id {
         Some(app_id) => {
             if app_id.len() == 0 || app_id.len() > 100 {
@@ -178,6 +191,9 @@ pub async fn notifaction(
             {
                 return Err(APIError::new_param_err(ParamErrType::Invalid, "secret"));
             }
+            if param.env.is_some() && param.env.as_ref() != Some(&secret.env) {
+                return Err(APIError::new_param_err(ParamErrType::Invalid, "env"));
+            }
         }
         None => return Err(APIError::new_param_err(ParamErrType::NotExist, "app_id")),
     };
@@ -196,11 +212,29 @@ pub async fn notifaction(
     // let namespace_item = namespace_item.await;
     if namespace_item.is_err() {
         // 超时 无更新
-        return Ok(Json(ApiResponse::ok()));
+        return Ok((HeaderMap::new(), Json(ApiResponse::ok())));
     }
     let namespace_item = namespace_item.unwrap();
     if namespace_item.is_none() {
-        return Ok(Json(ApiResponse::ok()));
+        return Ok((HeaderMap::new(), Json(ApiResponse::ok())));
+    }
+    let namespace_item = namespace_item.unwrap();
+    // 等待期间 namespace 被删除
+    if namespace_item.is_removed() {
+        return Err(APIError::new_param_err(ParamErrType::NotExist, "namespace"));
+    }
+    Ok((
+        item_header(&namespace_item),
+        Json(ApiResponse::ok_data(namespace_item)),
+    ))
+}
+
+// 配置的版本及校验和 便于客户端校验接收到的配置是否完整
+fn item_header(item: &NamespaceItem) -> HeaderMap {
+    let mut header = HeaderMap::with_capacity(2);
+    header.insert("X-Pilot-Version", item.version().into());
+    if let Ok(checksum) = item.checksum().parse() {
+        header.insert("X-Pilot-Checksum", checksum);
     }
-    Ok(Json(ApiResponse::ok_data(namespace_item.unwrap())))
+    header
 }
@@ -1,5 +1,6 @@
 pub mod backend;
 pub mod check;
+pub mod diff;
 pub mod forent;
 pub mod permission;
 
@@ -2,35 +2,42 @@ use std::collections::HashSet;
 
 use crate::web::{
     extract::{jwt::Claims, response::APIError},
-    store::dao::{rule, user_role},
+    store::dao::{app, app_member, cluster, rule, user_role},
 };
 
 use entity::{rule::Verb, users::UserLevel};
 
+// 是否超级管理员
 #[inline]
-pub fn acc_admin(auth: &Claims, app_id: Option<String>) -> bool {
-    match auth.user_level {
-        // 是否超级管理员
-        UserLevel::Admin => true,
-        UserLevel::DeptAdmin => {
-            return match app_id {
-                Some(id) => {
-                    // 判断资源是否属同一部门
-                    // auth.org_id == resource.org_id
-                    false
-                }
-                None => false,
-            };
-        }
-        UserLevel::Normal => false,
+pub fn acc_admin(auth: &Claims) -> bool {
+    auth.user_level == UserLevel::Admin
+}
+
+// 是否应用管理员: 超级管理员、应用所属部门的部门管理员及应用 owner
+// 查询结果在请求内缓存
+pub async fn acc_app_admin(auth: &Claims, app_id: &str) -> Result<bool, APIError> {
+    if acc_admin(auth) {
+        return Ok(true);
+    }
+    if let Some(admin) = auth.app_admin.get(app_id) {
+        return Ok(admin);
     }
+    let mut admin = false;
+    if auth.user_level == UserLevel::DeptAdmin {
+        admin = app::get_dept_id(app_id.to_owned()).await? == Some(auth.dept_id);
+    }
+    if !admin {
+        admin = app_member::is_owner(app_id.to_owned(), auth.user_id).await?;
+    }
+    auth.app_admin.set(app_id, admin);
+    Ok(admin)
 }
 
 pub async fn accredit(auth: &Claims, verb: Verb, resource: Vec<&str>) -> Result<bool, APIError> {
     if resource.len() == 0 {
         return Ok(false);
     }
-    if acc_admin(auth, resource.first().and_then(|x| Some(x.to_string()))) {
+    if acc_app_admin(auth, resource[0]).await? {
         return Ok(true);
     }
     // 获得用户的角色ID
@@ -38,8 +45,16 @@ pub async fn accredit(auth: &Claims, verb: Verb, resource: Vec<&str>) -> Result<
     if user_roles.is_empty() {
         return Ok(false);
     }
+    let mut resources = rule::combination_resource(resource.clone());
+    // 集群所属环境的授权同样适用于集群及其下的 namespace
+    if resource.len() > 1 {
+        if let Some(env) = cluster_env(auth, resource[0], resource[1]).await? {
+            resources.push(rule::env_resource(resource[0], &env));
+            resources.push(rule::global_env_resource(&env));
+        }
+    }
     // 获取授权资源的角色ID
-    let auth_roles = rule::get_resource_role(verb, rule::combination_resource(resource)).await?;
+    let auth_roles = rule::get_resource_role(verb, resources).await?;
     if auth_roles.is_empty() {
         return Ok(false);
     }
@@ -52,3 +67,43 @@ pub async fn accredit(auth: &Claims, verb: Verb, resource: Vec<&str>) -> Result<
     }
     Ok(false)
 }
+
+// 获取用户拥有权限的资源 返回 None 则拥有所有资源的权限
+// 与 accredit 一致, 作为应用管理员的应用拥有其下所有资源的权限
+pub async fn accredit_resources(
+    auth: &Claims,
+    verb: Verb,
+) -> Result<Option<Vec<String>>, APIError> {
+    if acc_admin(auth) {
+        return Ok(None);
+    }
+    let mut resources = app_member::get_owned_app_ids(auth.user_id).await?;
+    if auth.user_level == UserLevel::DeptAdmin {
+        resources.extend(app::get_dept_app_ids(auth.dept_id).await?);
+    }
+    let user_roles = user_role::get_user_role(auth.user_id).await?;
+    if !user_roles.is_empty() {
+        resources.extend(
+            rule::get_roles_resource(verb, user_roles)
+                .await?
+                .into_iter()
+                .map(|r| r.resource),
+        );
+    }
+    Ok(Some(resources))
+}
+
+// 集群所属环境, 查询结果在请求内缓存
+pub async fn cluster_env(
+    auth: &Claims,
+    app_id: &str,
+    cluster: &str,
+) -> Result<Option<String>, APIError> {
+    let key = format!("{}/{}", app_id, cluster);
+    if let Some(env) = auth.cluster_env.get(&key) {
+        return Ok(env);
+    }
+    let env = cluster::get_env(app_id.to_owned(), cluster.to_owned()).await?;
+    auth.cluster_env.set(&key, env.clone());
+    Ok(env)
+}
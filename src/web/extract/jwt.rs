@@ -1,3 +1,6 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
 use crate::config;
 
 use super::response::APIError;
@@ -28,6 +31,36 @@ pub struct Claims {
     pub dept_id: u32,
     pub user_level: UserLevel,
     pub exp: i64,
+    #[serde(skip)]
+    pub app_admin: RequestCache<bool>, // 应用是否由用户管理
+    #[serde(skip)]
+    pub cluster_env: RequestCache<Option<String>>, // 集群所属环境
+}
+
+// 请求内缓存的鉴权查询结果, 避免同一请求多次鉴权时重复查询
+#[derive(Debug)]
+pub struct RequestCache<V>(Arc<Mutex<HashMap<String, V>>>);
+
+impl<V: Clone> RequestCache<V> {
+    pub fn get(&self, key: &str) -> Option<V> {
+        self.0.lock().unwrap().get(key).cloned()
+    }
+
+    pub fn set(&self, key: &str, value: V) {
+        self.0.lock().unwrap().insert(key.to_owned(), value);
+    }
+}
+
+impl<V> Default for RequestCache<V> {
+    fn default() -> Self {
+        Self(Arc::new(Mutex::new(HashMap::new())))
+    }
+}
+
+impl<V> PartialEq for RequestCache<V> {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
 }
 
 #[async_trait]
@@ -70,6 +103,8 @@ pub fn auth_token(
         dept_id,
         user_level,
         exp: Local::now().timestamp() + 86400,
+        app_admin: RequestCache::default(),
+        cluster_env: RequestCache::default(),
     };
     encode(&Header::default(), &claim, &JWT_ENCODE)
 }
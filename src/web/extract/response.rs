@@ -88,6 +88,10 @@ pub enum APIErrorType {
     InvalidToken,
     // 无权限访问 拒绝访问
     Forbidden,
+    // 资源被锁定
+    Locked,
+    // 超出配额
+    QuotaExceeded,
 }
 
 #[derive(Clone)]
@@ -139,6 +143,20 @@ impl APIError {
             cause: None,
         }
     }
+    pub fn new_locked(msg: String) -> Self {
+        Self {
+            error_type: APIErrorType::Locked,
+            message: Some(msg),
+            cause: None,
+        }
+    }
+    pub fn new_quota_exceeded(msg: String) -> Self {
+        Self {
+            error_type: APIErrorType::QuotaExceeded,
+            message: Some(msg),
+            cause: None,
+        }
+    }
     pub fn new_auth_invalid(msg: String) -> Self {
         Self {
             error_type: APIErrorType::InvalidToken,
@@ -244,6 +262,12 @@ impl IntoResponse for APIError {
             APIErrorType::Forbidden => {
                 ApiResponse::err(4300, self.message.unwrap_or("无权限访问".to_owned()))
             }
+            APIErrorType::Locked => {
+                ApiResponse::err(4230, self.message.unwrap_or("资源已锁定".to_owned()))
+            }
+            APIErrorType::QuotaExceeded => {
+                ApiResponse::err(4130, self.message.unwrap_or("超出配额".to_owned()))
+            }
             APIErrorType::ServerAbnormal => ApiResponse::err(5000, "内部服务异常".to_owned()),
             APIErrorType::Database => ApiResponse::err(5000, "内部服务异常".to_owned()),
             APIErrorType::NotFound => ApiResponse::err(0, "OK".to_owned()),
@@ -12,7 +12,7 @@ use axum::{
     http::StatusCode,
     middleware,
     response::Html,
-    routing::{get, post, put},
+    routing::{delete, get, post, put},
     Router,
 };
 
@@ -35,30 +35,94 @@ pub async fn init_router() -> Router {
     let app_group = Router::new()
         .route("/create", post(app::create))
         .route("/edit", put(app::edit))
+        .route("/transfer", post(app::transfer))
+        .route("/quota", get(quota::app_usage).put(quota::edit_app))
+        .route("/delete", delete(app::delete))
+        .route("/restore", post(app::restore))
+        .route("/member", get(app::members))
+        .route("/member/add", post(app::add_member))
+        .route("/member/delete", delete(app::remove_member))
         .route("/list", get(app::list))
         .route("/favorite", get(favorite::list))
-        .route("/favorite/add", post(favorite::add));
+        .route("/favorite/add", post(favorite::add))
+        .route("/favorite/delete", delete(favorite::delete))
+        .route("/favorite/sort", put(favorite::sort))
+        .route("/recent", get(favorite::recent));
 
     let cluster = Router::new()
         .route("/create", post(cluster::create))
+        .route("/edit", put(cluster::edit))
+        .route("/delete", delete(cluster::delete))
         .route("/secret/reset", put(cluster::reset_secret))
+        .route("/approval", put(cluster::edit_approval))
+        .route("/env", put(cluster::edit_env))
         .route("/list", get(cluster::list));
 
+    let environment = Router::new()
+        .route("/create", post(environment::create))
+        .route("/edit", put(environment::edit))
+        .route("/delete", delete(environment::delete))
+        .route("/grant", post(environment::grant))
+        .route("/list", get(environment::list));
+
     let app_extend = Router::new()
         .route("/create", post(app_extend::create))
         .route("/list", get(app_extend::list));
 
     let namespace = Router::new()
         .route("/create", post(namespace::create))
+        .route("/clone", post(namespace::clone))
+        .route("/template", get(template::detail))
+        .route("/template/create", post(template::create))
+        .route("/template/list", get(template::list))
+        .route("/template/delete", delete(template::delete))
         .route("/list", get(namespace::list))
+        .route("/key_policy", put(namespace::edit_key_policy))
+        .route("/approval", put(namespace::edit_approval))
+        .route("/retention", put(namespace::edit_retention))
+        .route("/prune", post(retention::prune))
+        .route(
+            "/quota",
+            get(quota::namespace_usage).put(quota::edit_namespace),
+        )
+        .route("/delete", delete(namespace::delete))
+        .route("/restore", post(namespace::restore))
+        .route("/deleted", get(namespace::list_deleted))
+        .route("/edit_lock", put(namespace::edit_lock))
+        .route("/lock", get(namespace::lock).delete(namespace::unlock))
         .route("/public", get(namespace::list_public));
 
+    let webhook = Router::new()
+        .route("/create", post(webhook::create))
+        .route("/edit", put(webhook::edit))
+        .route("/delete", delete(webhook::delete))
+        .route("/list", get(webhook::list))
+        .route("/delivery", get(webhook::delivery));
+
     let item = Router::new()
         .route("/create", post(item::create))
         .route("/list", get(item::list))
+        .route("/deleted", get(item::list_deleted))
         .route("/edit", put(item::edit))
+        .route("/import", post(item::import))
+        .route("/delete", delete(item::delete))
+        .route("/revert", post(item::revert))
+        .route("/revision", get(item::revision))
+        .route("/blame", get(item::blame))
+        .route("/search", get(item::search))
+        .route("/promote", get(promotion::preview).post(promotion::promote))
         .route("/publish/history", get(publication::release_list))
+        .route("/publish/diff", get(publication::release_diff))
+        .route("/publish/integrity", get(publication::integrity))
         .route("/publish", post(publication::publish))
+        .route("/publish/namespace", post(publication::publish_namespace))
+        .route("/publish/dry_run", post(publication::dry_run))
+        .route("/publish/request", get(approval::list))
+        .route("/publish/request/approve", post(approval::approve))
+        .route("/publish/request/reject", post(approval::reject))
+        .route("/publish/request/cancel", post(approval::cancel))
+        .route("/publish/schedule", get(schedule::list))
+        .route("/publish/schedule/cancel", post(schedule::cancel))
         .route("/rollback", post(publication::rollback));
 
     let api_group = Router::new()
@@ -66,10 +130,12 @@ pub async fn init_router() -> Router {
         .nest("/app", app_group)
         .nest("/department", department_group)
         .nest("/users", users_group)
+        .nest("/environment", environment)
         .nest("/cluster", cluster)
         .nest("/namespace", namespace)
         .nest("/app_extend", app_extend)
-        .nest("/item", item);
+        .nest("/item", item)
+        .nest("/webhook", webhook);
 
     let recorder_handle = metrics::setup_metrics_recorder();
 
@@ -1,8 +1,16 @@
-use std::{collections::HashMap, sync::Arc, time::Duration};
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
 
-use super::dao::release;
+use super::dao::{namespace, release};
 
 use ahash::RandomState;
+use chrono::Local;
 use entity::{item::ConfigItem, orm::DbErr};
 use serde::Serialize;
 use tokio::{
@@ -13,12 +21,32 @@ use tokio::{
     time::{self, MissedTickBehavior},
 };
 
+// 拉取时间写入数据库的最小间隔 second, 多实例通过数据库共享拉取记录
+const POLLED_PERSIST_INTERVAL: u64 = 60;
+
 #[derive(Debug, Clone, Default, Serialize)]
 pub struct NamespaceItem {
     #[serde(skip_serializing)]
     namespace_id: u64,
     items: Vec<ConfigItem>,
     version: u64,
+    checksum: String, // 配置的校验和, 客户端可按相同规则计算 items 校验完整性
+    #[serde(skip_serializing)]
+    removed: bool, // namespace 已被删除
+}
+
+impl NamespaceItem {
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    pub fn checksum(&self) -> &str {
+        &self.checksum
+    }
+
+    pub fn is_removed(&self) -> bool {
+        self.removed
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -28,6 +56,8 @@ pub struct CacheItem {
     list: Vec<Arc<RwLock<HashMap<u64, NamespaceItem, RandomState>>>>,
     reserve: broadcast::Sender<NamespaceItem>,
     namespace_id_sender: mpsc::UnboundedSender<u64>,
+    // namespace 最近一次被客户端拉取的时间 second, 与 list 相同分区, 已记录的 namespace 仅需读锁
+    polled: Vec<Arc<RwLock<HashMap<u64, AtomicU64, RandomState>>>>,
 }
 
 impl CacheItem {
@@ -39,6 +69,7 @@ impl CacheItem {
         const MAP_CAPACITY: usize = 64;
         let mut area = Vec::with_capacity(CAPACITY);
         let mut noti = Vec::with_capacity(CAPACITY);
+        let mut polled = Vec::with_capacity(CAPACITY);
         for _ in 0..CAPACITY {
             area.push(Arc::new(RwLock::new(HashMap::with_capacity_and_hasher(
                 MAP_CAPACITY,
@@ -48,6 +79,10 @@ impl CacheItem {
                 MAP_CAPACITY,
                 RandomState::new(),
             ))));
+            polled.push(Arc::new(RwLock::new(HashMap::with_capacity_and_hasher(
+                MAP_CAPACITY,
+                RandomState::new(),
+            ))));
         }
         let cache = Self {
             capacity: CAPACITY,
@@ -55,6 +90,7 @@ impl CacheItem {
             list: area,
             notifaction: noti,
             namespace_id_sender,
+            polled,
         };
         cache.listen_change(namespace_id_receiver);
         cache
@@ -67,6 +103,7 @@ impl CacheItem {
         version: Option<u64>,
     ) -> Option<NamespaceItem> {
         let mut added_namespace = false;
+        self.set_polled(namespace_id).await;
         // 从缓存中查找
         let version = version.unwrap_or_default();
         match self.get_item_data(namespace_id).await {
@@ -109,8 +146,8 @@ impl CacheItem {
             // 长度为1 获取到的则为最新的数据
             match rcv {
                 Ok(data) => {
-                    // 对比版本 不相同则返回
-                    if version != data.version {
+                    // 对比版本 不相同或 namespace 已删除则返回
+                    if version != data.version || data.removed {
                         return Some(data);
                     }
                 }
@@ -122,6 +159,56 @@ impl CacheItem {
         }
     }
 
+    // 当前实例上等待 namespace 更新的客户端数量
+    pub async fn subscriber_count(&self, namespace_id: u64) -> usize {
+        match self.get_item_sender(namespace_id).await {
+            Some(sender) => sender.receiver_count(),
+            None => 0,
+        }
+    }
+
+    // 当前实例上 namespace 最近一次被拉取的时间, 未被拉取过为 0
+    // 仅记录本实例的拉取, 所有实例的拉取记录按间隔写入 namespace.polled_at
+    pub async fn last_polled(&self, namespace_ids: &[u64]) -> u64 {
+        let mut last = 0;
+        for &id in namespace_ids.iter() {
+            let idx = self.calc_area_index(id);
+            if let Some(polled) = self.polled[idx].read().await.get(&id) {
+                last = last.max(polled.load(Ordering::Relaxed));
+            }
+        }
+        last
+    }
+
+    // 记录 namespace 的拉取时间, 距上次写入超过间隔时写入数据库
+    async fn set_polled(&self, namespace_id: u64) {
+        let now = Local::now().timestamp() as u64;
+        let idx = self.calc_area_index(namespace_id);
+        let last = self.polled[idx]
+            .read()
+            .await
+            .get(&namespace_id)
+            .map(|polled| polled.swap(now, Ordering::Relaxed));
+        if last.is_none() {
+            self.polled[idx]
+                .write()
+                .await
+                .insert(namespace_id, AtomicU64::new(now));
+        }
+        let last = last.unwrap_or_default();
+        if last / POLLED_PERSIST_INTERVAL != now / POLLED_PERSIST_INTERVAL {
+            tokio::spawn(async move {
+                if let Err(err) = namespace::set_polled(namespace_id, now).await {
+                    tracing::error!(
+                        "failed to save namespace [{}] polled time: {:?}",
+                        namespace_id,
+                        err
+                    );
+                }
+            });
+        }
+    }
+
     #[inline]
     async fn add_new_namespace(&self, namespace_id: u64, version: u64) -> Option<NamespaceItem> {
         // namespace 不存在 添加到监听列表
@@ -246,6 +333,36 @@ impl CacheItem {
         true
     }
 
+    // namespace 已被删除时 通知订阅的客户端并移除缓存
+    // 返回是否已移除
+    async fn remove_deleted(&self, namespace_id: u64) -> bool {
+        match namespace::get_app_info(namespace_id).await {
+            Ok(None) => (),
+            Ok(Some(_)) => return false,
+            Err(err) => {
+                tracing::error!(
+                    "failed to get namespace {} info. err: {:?}",
+                    namespace_id,
+                    err
+                );
+                return false;
+            }
+        }
+        let idx = self.calc_area_index(namespace_id);
+        self.list[idx].write().await.remove(&namespace_id);
+        self.polled[idx].write().await.remove(&namespace_id);
+        let sender = self.notifaction[idx].write().await.remove(&namespace_id);
+        if let Some(sender) = sender {
+            let _ = sender.send(NamespaceItem {
+                namespace_id,
+                removed: true,
+                ..Default::default()
+            });
+        }
+        tracing::info!("namespace {} was deleted, stop listening", namespace_id);
+        true
+    }
+
     fn listen_change(&self, mut namespace_receiver: mpsc::UnboundedReceiver<u64>) {
         let self_add_namespace = self.clone();
         let (listen_sender, mut listen_receiver) = mpsc::unbounded_channel::<u64>();
@@ -294,13 +411,18 @@ impl CacheItem {
                         let mut handlers = Vec::with_capacity(listen_ids.len());
                         // TODO 限制最大运行数量
                         for (&namespace_id,_) in listen_ids.iter() {
-                            handlers.push(tokio::spawn(load_database_publication(namespace_id)));
+                            let handler = tokio::spawn(load_database_publication(namespace_id));
+                            handlers.push((namespace_id, handler));
                         }
-                        for handler in handlers {
+                        for (namespace_id, handler) in handlers {
                             match handler.await {
                                 Ok(item) => {
                                     // TODO 批量修改 set
                                     if item.is_none() {
+                                        // 发布已不存在 namespace 被删除时通知客户端并停止监听
+                                        if sync_item.remove_deleted(namespace_id).await {
+                                            listen_ids.remove(&namespace_id);
+                                        }
                                         continue;
                                     }
                                     let item = item.unwrap();
@@ -346,10 +468,27 @@ pub async fn load_database_publication(namespace_id: u64) -> Option<NamespaceIte
                         tracing::error!("failed to parse config item err: {:?}", items);
                         return None;
                     }
+                    // 与校验和的计算顺序一致
+                    let mut items = items.unwrap();
+                    items.sort_by(|a, b| a.key.cmp(&b.key));
+                    // 历史发布未保存校验和
+                    let checksum = if config.checksum.is_empty() {
+                        match release::checksum(&config.configurations) {
+                            Ok(checksum) => checksum,
+                            Err(err) => {
+                                tracing::error!("failed to compute checksum err: {:?}", err);
+                                return None;
+                            }
+                        }
+                    } else {
+                        config.checksum
+                    };
                     return Some(NamespaceItem {
                         namespace_id,
                         version: config.id,
-                        items: items.unwrap(),
+                        items,
+                        checksum,
+                        removed: false,
                     });
                 }
                 None => return None,
@@ -1,16 +1,20 @@
 use std::str::FromStr;
 
-use super::master;
+use super::{master, namespace, slaver};
 use crate::web::extract::jwt::Claims;
 
+use entity::app_member::MemberRole;
+use entity::orm::sea_query::Expr;
 use entity::orm::{
-    ActiveModelTrait, ColumnTrait, DbErr, EntityTrait, Iterable, QueryFilter, QuerySelect, Set,
-    TransactionError, TransactionTrait,
+    ActiveModelTrait, ColumnTrait, DbErr, EntityTrait, Iterable, QueryFilter, QueryOrder,
+    QuerySelect, Set, TransactionError, TransactionTrait,
 };
 use entity::rule::Verb;
 use entity::{
-    AppActive, AppColumn, AppEntity, AppModel, IDu32, RoleActive, RoleEntity, RoleRuleActive,
-    RoleRuleEntity, RuleActive, RuleEntity, UserRoleActive, UserRoleEntity, ID,
+    AppActive, AppColumn, AppEntity, AppMemberActive, AppMemberColumn, AppMemberEntity, AppModel,
+    ClusterColumn, ClusterEntity, FavoriteColumn, FavoriteEntity, IDu32, Quota, RecentColumn,
+    RecentEntity, RoleActive, RoleEntity, RoleRuleActive, RoleRuleEntity, RuleActive, RuleEntity,
+    UserRoleActive, UserRoleEntity, ID,
 };
 
 pub async fn add(app_id: String, name: String, dept_id: u32, auth: &Claims) -> Result<(), DbErr> {
@@ -44,6 +48,14 @@ pub async fn add(app_id: String, name: String, dept_id: u32, auth: &Claims) -> R
         rules.push(rule);
     }
     let mut binds: Vec<RoleRuleActive> = Vec::with_capacity(verb_len);
+    // 创建者为应用 owner
+    let owner = AppMemberActive {
+        app_id: Set(app_id.clone()),
+        user_id: Set(auth.user_id),
+        role: Set(MemberRole::Owner),
+        creator_user: Set(auth.user_id),
+        ..Default::default()
+    };
 
     let transaction = master()
         .transaction::<_, (), DbErr>(|tx| {
@@ -71,6 +83,7 @@ pub async fn add(app_id: String, name: String, dept_id: u32, auth: &Claims) -> R
                 // 为用户添加角色
                 user_bind.role_id = Set(role_id);
                 UserRoleEntity::insert(user_bind).exec(tx).await?;
+                AppMemberEntity::insert(owner).exec(tx).await?;
                 Ok(())
             })
         })
@@ -119,6 +132,70 @@ pub async fn get_app_id(app_id: String) -> Result<Option<u32>, DbErr> {
     Ok(id.and_then(|x| Some(x.id)))
 }
 
+pub async fn get_quota(app_id: String) -> Result<Option<Quota>, DbErr> {
+    AppEntity::find()
+        .select_only()
+        .column(AppColumn::QuotaItemCount)
+        .column(AppColumn::QuotaValueSize)
+        .column(AppColumn::QuotaReleaseSize)
+        .filter(AppColumn::AppId.eq(app_id))
+        .filter(AppColumn::DeletedAt.eq(0_u64))
+        .into_model::<Quota>()
+        .one(master())
+        .await
+}
+
+pub async fn update_quota(app_id: String, quota: Quota) -> Result<(), DbErr> {
+    AppEntity::update_many()
+        .col_expr(
+            AppColumn::QuotaItemCount,
+            Expr::value(quota.quota_item_count),
+        )
+        .col_expr(
+            AppColumn::QuotaValueSize,
+            Expr::value(quota.quota_value_size),
+        )
+        .col_expr(
+            AppColumn::QuotaReleaseSize,
+            Expr::value(quota.quota_release_size),
+        )
+        .filter(AppColumn::AppId.eq(app_id))
+        .filter(AppColumn::DeletedAt.eq(0_u64))
+        .exec(master())
+        .await?;
+    Ok(())
+}
+
+// 获取应用所属部门
+pub async fn get_dept_id(app_id: String) -> Result<Option<u32>, DbErr> {
+    let app = AppEntity::find()
+        .filter(AppColumn::AppId.eq(app_id))
+        .filter(AppColumn::DeletedAt.eq(0_u64))
+        .one(slaver())
+        .await?;
+    Ok(app.map(|x| x.dept_id))
+}
+
+// 获取部门下的应用
+pub async fn get_dept_app_ids(dept_id: u32) -> Result<Vec<String>, DbErr> {
+    let list = AppEntity::find()
+        .filter(AppColumn::DeptId.eq(dept_id))
+        .filter(AppColumn::DeletedAt.eq(0_u64))
+        .all(slaver())
+        .await?;
+    Ok(list.into_iter().map(|x| x.app_id).collect())
+}
+
+// 获取最近删除的应用
+pub async fn find_deleted(app_id: String) -> Result<Option<AppModel>, DbErr> {
+    AppEntity::find()
+        .filter(AppColumn::AppId.eq(app_id))
+        .filter(AppColumn::DeletedAt.gt(0_u64))
+        .order_by_desc(AppColumn::DeletedAt)
+        .one(master())
+        .await
+}
+
 // 查找 app_id 是否存在
 pub async fn is_exist(app_id: String) -> Result<bool, DbErr> {
     let entiy = AppEntity::find()
@@ -131,3 +208,101 @@ pub async fn is_exist(app_id: String) -> Result<bool, DbErr> {
         .await?;
     Ok(entiy.is_some())
 }
+
+// 删除应用及其下所有集群、namespace、成员、收藏与最近访问记录, 应用已被删除时返回 false
+pub async fn delete(app: AppModel, namespace_ids: Vec<u64>, now: u64) -> Result<bool, DbErr> {
+    let transaction = master()
+        .transaction::<_, bool, DbErr>(|tx| {
+            Box::pin(async move {
+                let r = AppEntity::update_many()
+                    .col_expr(AppColumn::DeletedAt, Expr::value(now))
+                    .filter(AppColumn::Id.eq(app.id))
+                    .filter(AppColumn::DeletedAt.eq(0_u64))
+                    .exec(tx)
+                    .await?;
+                if r.rows_affected == 0 {
+                    return Ok(false);
+                }
+                ClusterEntity::update_many()
+                    .col_expr(ClusterColumn::DeletedAt, Expr::value(now))
+                    .filter(ClusterColumn::AppId.eq(app.app_id.clone()))
+                    .filter(ClusterColumn::DeletedAt.eq(0_u64))
+                    .exec(tx)
+                    .await?;
+                for namespace_id in namespace_ids.into_iter() {
+                    namespace::delete_cascade(tx, namespace_id, now).await?;
+                }
+                AppMemberEntity::update_many()
+                    .col_expr(AppMemberColumn::DeletedAt, Expr::value(now))
+                    .filter(AppMemberColumn::AppId.eq(app.app_id.clone()))
+                    .filter(AppMemberColumn::DeletedAt.eq(0_u64))
+                    .exec(tx)
+                    .await?;
+                FavoriteEntity::update_many()
+                    .col_expr(FavoriteColumn::DeletedAt, Expr::value(now))
+                    .filter(FavoriteColumn::AppId.eq(app.id))
+                    .filter(FavoriteColumn::DeletedAt.eq(0_u64))
+                    .exec(tx)
+                    .await?;
+                // 最近访问记录不随应用恢复
+                RecentEntity::delete_many()
+                    .filter(RecentColumn::AppId.eq(app.id))
+                    .exec(tx)
+                    .await?;
+                Ok(true)
+            })
+        })
+        .await;
+    match transaction {
+        Ok(deleted) => Ok(deleted),
+        Err(TransactionError::Connection(err)) => Err(err),
+        Err(TransactionError::Transaction(err)) => Err(DbErr::Exec(err.to_string())),
+    }
+}
+
+// 恢复应用及随其一同删除的数据, 已恢复时返回 false
+pub async fn restore(app: AppModel, namespace_ids: Vec<u64>) -> Result<bool, DbErr> {
+    let deleted_at = app.deleted_at;
+    let transaction = master()
+        .transaction::<_, bool, DbErr>(|tx| {
+            Box::pin(async move {
+                let r = AppEntity::update_many()
+                    .col_expr(AppColumn::DeletedAt, Expr::value(0_u64))
+                    .filter(AppColumn::Id.eq(app.id))
+                    .filter(AppColumn::DeletedAt.eq(deleted_at))
+                    .exec(tx)
+                    .await?;
+                if r.rows_affected == 0 {
+                    return Ok(false);
+                }
+                ClusterEntity::update_many()
+                    .col_expr(ClusterColumn::DeletedAt, Expr::value(0_u64))
+                    .filter(ClusterColumn::AppId.eq(app.app_id.clone()))
+                    .filter(ClusterColumn::DeletedAt.eq(deleted_at))
+                    .exec(tx)
+                    .await?;
+                for namespace_id in namespace_ids.into_iter() {
+                    namespace::restore_cascade(tx, namespace_id, deleted_at).await?;
+                }
+                AppMemberEntity::update_many()
+                    .col_expr(AppMemberColumn::DeletedAt, Expr::value(0_u64))
+                    .filter(AppMemberColumn::AppId.eq(app.app_id.clone()))
+                    .filter(AppMemberColumn::DeletedAt.eq(deleted_at))
+                    .exec(tx)
+                    .await?;
+                FavoriteEntity::update_many()
+                    .col_expr(FavoriteColumn::DeletedAt, Expr::value(0_u64))
+                    .filter(FavoriteColumn::AppId.eq(app.id))
+                    .filter(FavoriteColumn::DeletedAt.eq(deleted_at))
+                    .exec(tx)
+                    .await?;
+                Ok(true)
+            })
+        })
+        .await;
+    match transaction {
+        Ok(restored) => Ok(restored),
+        Err(TransactionError::Connection(err)) => Err(err),
+        Err(TransactionError::Transaction(err)) => Err(DbErr::Exec(err.to_string())),
+    }
+}
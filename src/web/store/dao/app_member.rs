@@ -0,0 +1,103 @@
+use super::{master, slaver};
+
+use entity::app_member::{MemberItem, MemberRole};
+use entity::orm::sea_query::Expr;
+use entity::orm::{ColumnTrait, DbErr, EntityTrait, QueryFilter, QueryOrder, QuerySelect, Set};
+use entity::{
+    AppMemberActive, AppMemberColumn, AppMemberEntity, AppMemberModel, UsersColumn, UsersEntity, ID,
+};
+
+pub async fn add(
+    app_id: String,
+    user_id: u32,
+    role: MemberRole,
+    creator_user: u32,
+) -> Result<u64, DbErr> {
+    let r = AppMemberEntity::insert(AppMemberActive {
+        app_id: Set(app_id),
+        user_id: Set(user_id),
+        role: Set(role),
+        creator_user: Set(creator_user),
+        ..Default::default()
+    })
+    .exec(master())
+    .await?;
+    Ok(r.last_insert_id)
+}
+
+pub async fn get_member(app_id: String, user_id: u32) -> Result<Option<AppMemberModel>, DbErr> {
+    AppMemberEntity::find()
+        .filter(AppMemberColumn::AppId.eq(app_id))
+        .filter(AppMemberColumn::UserId.eq(user_id))
+        .filter(AppMemberColumn::DeletedAt.eq(0_u64))
+        .one(master())
+        .await
+}
+
+// 用户是否为应用 owner
+pub async fn is_owner(app_id: String, user_id: u32) -> Result<bool, DbErr> {
+    let member = get_member(app_id, user_id).await?;
+    Ok(member.is_some_and(|m| m.role == MemberRole::Owner))
+}
+
+// 用户作为 owner 的应用
+pub async fn get_owned_app_ids(user_id: u32) -> Result<Vec<String>, DbErr> {
+    let list = AppMemberEntity::find()
+        .filter(AppMemberColumn::UserId.eq(user_id))
+        .filter(AppMemberColumn::Role.eq(MemberRole::Owner))
+        .filter(AppMemberColumn::DeletedAt.eq(0_u64))
+        .all(slaver())
+        .await?;
+    Ok(list.into_iter().map(|m| m.app_id).collect())
+}
+
+// 应用 owner 数量
+pub async fn owner_count(app_id: String) -> Result<usize, DbErr> {
+    let owners = AppMemberEntity::find()
+        .select_only()
+        .column(AppMemberColumn::Id)
+        .filter(AppMemberColumn::AppId.eq(app_id))
+        .filter(AppMemberColumn::Role.eq(MemberRole::Owner))
+        .filter(AppMemberColumn::DeletedAt.eq(0_u64))
+        .into_model::<ID>()
+        .all(master())
+        .await?;
+    Ok(owners.len())
+}
+
+pub async fn update_role(id: u64, role: MemberRole) -> Result<(), DbErr> {
+    AppMemberEntity::update_many()
+        .col_expr(AppMemberColumn::Role, Expr::value(role))
+        .filter(AppMemberColumn::Id.eq(id))
+        .exec(master())
+        .await?;
+    Ok(())
+}
+
+pub async fn remove(id: u64, now: u64) -> Result<(), DbErr> {
+    AppMemberEntity::update_many()
+        .col_expr(AppMemberColumn::DeletedAt, Expr::value(now))
+        .filter(AppMemberColumn::Id.eq(id))
+        .filter(AppMemberColumn::DeletedAt.eq(0_u64))
+        .exec(master())
+        .await?;
+    Ok(())
+}
+
+pub async fn find_by_app(app_id: String) -> Result<Vec<MemberItem>, DbErr> {
+    AppMemberEntity::find()
+        .select_only()
+        .column(AppMemberColumn::UserId)
+        .column(UsersColumn::Account)
+        .column(UsersColumn::Nickname)
+        .column(AppMemberColumn::Role)
+        .column(AppMemberColumn::CreatedAt)
+        .left_join(UsersEntity)
+        .filter(AppMemberColumn::AppId.eq(app_id))
+        .filter(AppMemberColumn::DeletedAt.eq(0_u64))
+        .order_by_asc(AppMemberColumn::Role)
+        .order_by_asc(AppMemberColumn::Id)
+        .into_model::<MemberItem>()
+        .all(slaver())
+        .await
+}
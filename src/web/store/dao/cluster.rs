@@ -1,8 +1,15 @@
-use super::{master, slaver};
+use super::{master, namespace, rule, slaver};
 
 use entity::cluster::ClusterItem;
-use entity::orm::{ColumnTrait, DbErr, EntityTrait, QueryFilter, QuerySelect};
-use entity::{ClusterActive, ClusterColumn, ClusterEntity, SecretData, ID};
+use entity::orm::sea_query::{Expr, Value};
+use entity::orm::{
+    ColumnTrait, Condition, DbErr, EntityTrait, QueryFilter, QuerySelect, TransactionError,
+    TransactionTrait,
+};
+use entity::{
+    Approval, ClusterActive, ClusterColumn, ClusterEntity, NamespaceColumn, NamespaceEntity,
+    RuleColumn, RuleEntity, SecretData, ID,
+};
 
 pub async fn add(cluster: ClusterActive) -> Result<u64, DbErr> {
     let r = ClusterEntity::insert(cluster).exec(master()).await?;
@@ -22,6 +29,16 @@ pub async fn find_app_cluster(app_id: String, cluster: String) -> Result<Option<
     Ok(r.and_then(|r| Some(r.id)))
 }
 
+pub async fn get_approval(app_id: String, cluster: String) -> Result<Option<Approval>, DbErr> {
+    let entity = ClusterEntity::find()
+        .filter(ClusterColumn::AppId.eq(app_id))
+        .filter(ClusterColumn::Name.eq(cluster))
+        .filter(ClusterColumn::DeletedAt.eq(0_u64))
+        .one(slaver())
+        .await?;
+    Ok(entity.map(|c| c.approval))
+}
+
 pub async fn update_by_id(model: ClusterActive, id: u64) -> Result<(), DbErr> {
     ClusterEntity::update_many()
         .set(model)
@@ -31,16 +48,21 @@ pub async fn update_by_id(model: ClusterActive, id: u64) -> Result<(), DbErr> {
     Ok(())
 }
 
-pub async fn find_cluster_by_app(app_id: String) -> Result<Vec<ClusterItem>, DbErr> {
-    ClusterEntity::find()
+pub async fn find_cluster_by_app(
+    app_id: String,
+    env: Option<String>,
+) -> Result<Vec<ClusterItem>, DbErr> {
+    let mut select = ClusterEntity::find()
         .select_only()
         .column(ClusterColumn::Id)
         .column(ClusterColumn::Name)
+        .column(ClusterColumn::Env)
         .filter(ClusterColumn::AppId.eq(app_id))
-        .filter(ClusterColumn::DeletedAt.eq(0_u64))
-        .into_model::<ClusterItem>()
-        .all(slaver())
-        .await
+        .filter(ClusterColumn::DeletedAt.eq(0_u64));
+    if let Some(env) = env {
+        select = select.filter(ClusterColumn::Env.eq(env));
+    }
+    select.into_model::<ClusterItem>().all(slaver()).await
 }
 
 pub async fn get_secret_by_cluster(
@@ -50,6 +72,7 @@ pub async fn get_secret_by_cluster(
     ClusterEntity::find()
         .select_only()
         .column(ClusterColumn::Secret)
+        .column(ClusterColumn::Env)
         .filter(ClusterColumn::AppId.eq(app_id.clone()))
         .filter(ClusterColumn::Name.eq(cluster.clone()))
         .into_model::<SecretData>()
@@ -57,6 +80,30 @@ pub async fn get_secret_by_cluster(
         .await
 }
 
+// 获取集群所属环境
+pub async fn get_env(app_id: String, cluster: String) -> Result<Option<String>, DbErr> {
+    let entity = ClusterEntity::find()
+        .filter(ClusterColumn::AppId.eq(app_id))
+        .filter(ClusterColumn::Name.eq(cluster))
+        .filter(ClusterColumn::DeletedAt.eq(0_u64))
+        .one(slaver())
+        .await?;
+    Ok(entity.map(|c| c.env))
+}
+
+// 环境下是否还有集群
+pub async fn env_in_use(env: String) -> Result<bool, DbErr> {
+    let entity = ClusterEntity::find()
+        .select_only()
+        .column(ClusterColumn::Id)
+        .filter(ClusterColumn::Env.eq(env))
+        .filter(ClusterColumn::DeletedAt.eq(0_u64))
+        .into_model::<ID>()
+        .one(master())
+        .await?;
+    Ok(entity.is_some())
+}
+
 pub async fn is_exist(app_id: String, cluster: String) -> Result<bool, DbErr> {
     let entity = ClusterEntity::find()
         .select_only()
@@ -69,3 +116,90 @@ pub async fn is_exist(app_id: String, cluster: String) -> Result<bool, DbErr> {
         .await?;
     Ok(entity.is_some())
 }
+
+// 重命名集群 同时修改 namespace 及授权资源中的集群名称
+// 集群不存在时返回 false
+pub async fn rename(id: u64, app_id: String, cluster: String, name: String) -> Result<bool, DbErr> {
+    let transaction = master()
+        .transaction::<_, bool, DbErr>(|tx| {
+            Box::pin(async move {
+                let r = ClusterEntity::update_many()
+                    .col_expr(ClusterColumn::Name, Expr::value(name.clone()))
+                    .filter(ClusterColumn::Id.eq(id))
+                    .filter(ClusterColumn::Name.eq(cluster.clone()))
+                    .filter(ClusterColumn::DeletedAt.eq(0_u64))
+                    .exec(tx)
+                    .await?;
+                if r.rows_affected == 0 {
+                    return Ok(false);
+                }
+                NamespaceEntity::update_many()
+                    .col_expr(NamespaceColumn::Cluster, Expr::value(name.clone()))
+                    .filter(NamespaceColumn::AppId.eq(app_id.clone()))
+                    .filter(NamespaceColumn::Cluster.eq(cluster.clone()))
+                    .filter(NamespaceColumn::DeletedAt.eq(0_u64))
+                    .exec(tx)
+                    .await?;
+                // 授权资源形如 app_id/cluster/namespace
+                let resource = rule::combination_resource(vec![&app_id, &cluster])
+                    .pop()
+                    .unwrap();
+                let renamed = rule::combination_resource(vec![&app_id, &name])
+                    .pop()
+                    .unwrap();
+                RuleEntity::update_many()
+                    .col_expr(
+                        RuleColumn::Resource,
+                        Expr::cust_with_values(
+                            "CONCAT(?, SUBSTRING(`resource`, ?))",
+                            vec![
+                                Value::from(renamed),
+                                Value::from(resource.chars().count() as u64 + 1),
+                            ],
+                        ),
+                    )
+                    .filter(
+                        Condition::any()
+                            .add(RuleColumn::Resource.eq(resource.clone()))
+                            .add(rule::prefix_condition(format!("{}/", resource))),
+                    )
+                    .exec(tx)
+                    .await?;
+                Ok(true)
+            })
+        })
+        .await;
+    match transaction {
+        Ok(renamed) => Ok(renamed),
+        Err(TransactionError::Connection(err)) => Err(err),
+        Err(TransactionError::Transaction(err)) => Err(DbErr::Exec(err.to_string())),
+    }
+}
+
+// 删除集群及其下所有 namespace, 集群已被删除时返回 false
+pub async fn delete(id: u64, namespace_ids: Vec<u64>, now: u64) -> Result<bool, DbErr> {
+    let transaction = master()
+        .transaction::<_, bool, DbErr>(|tx| {
+            Box::pin(async move {
+                let r = ClusterEntity::update_many()
+                    .col_expr(ClusterColumn::DeletedAt, Expr::value(now))
+                    .filter(ClusterColumn::Id.eq(id))
+                    .filter(ClusterColumn::DeletedAt.eq(0_u64))
+                    .exec(tx)
+                    .await?;
+                if r.rows_affected == 0 {
+                    return Ok(false);
+                }
+                for namespace_id in namespace_ids.into_iter() {
+                    namespace::delete_cascade(tx, namespace_id, now).await?;
+                }
+                Ok(true)
+            })
+        })
+        .await;
+    match transaction {
+        Ok(deleted) => Ok(deleted),
+        Err(TransactionError::Connection(err)) => Err(err),
+        Err(TransactionError::Transaction(err)) => Err(DbErr::Exec(err.to_string())),
+    }
+}
@@ -0,0 +1,58 @@
+use super::{master, slaver};
+
+use entity::orm::sea_query::Expr;
+use entity::orm::{ColumnTrait, DbErr, EntityTrait, QueryFilter, QueryOrder};
+use entity::{Approval, EnvironmentActive, EnvironmentColumn, EnvironmentEntity, EnvironmentModel};
+
+pub async fn add(env: EnvironmentActive) -> Result<u32, DbErr> {
+    let r = EnvironmentEntity::insert(env).exec(master()).await?;
+    Ok(r.last_insert_id)
+}
+
+pub async fn update_by_id(env: EnvironmentActive, id: u32) -> Result<(), DbErr> {
+    EnvironmentEntity::update_many()
+        .set(env)
+        .filter(EnvironmentColumn::Id.eq(id))
+        .exec(master())
+        .await?;
+    Ok(())
+}
+
+pub async fn delete(id: u32, now: u64) -> Result<bool, DbErr> {
+    let r = EnvironmentEntity::update_many()
+        .col_expr(EnvironmentColumn::DeletedAt, Expr::value(now))
+        .filter(EnvironmentColumn::Id.eq(id))
+        .filter(EnvironmentColumn::DeletedAt.eq(0_u64))
+        .exec(master())
+        .await?;
+    Ok(r.rows_affected != 0)
+}
+
+pub async fn find_all() -> Result<Vec<EnvironmentModel>, DbErr> {
+    EnvironmentEntity::find()
+        .filter(EnvironmentColumn::DeletedAt.eq(0_u64))
+        .order_by_asc(EnvironmentColumn::Id)
+        .all(slaver())
+        .await
+}
+
+pub async fn get_by_name(name: String) -> Result<Option<EnvironmentModel>, DbErr> {
+    EnvironmentEntity::find()
+        .filter(EnvironmentColumn::Name.eq(name))
+        .filter(EnvironmentColumn::DeletedAt.eq(0_u64))
+        .one(master())
+        .await
+}
+
+pub async fn is_exist(name: String) -> Result<bool, DbErr> {
+    Ok(get_by_name(name).await?.is_some())
+}
+
+pub async fn get_approval(name: String) -> Result<Option<Approval>, DbErr> {
+    let env = EnvironmentEntity::find()
+        .filter(EnvironmentColumn::Name.eq(name))
+        .filter(EnvironmentColumn::DeletedAt.eq(0_u64))
+        .one(slaver())
+        .await?;
+    Ok(env.map(|e| e.approval))
+}
@@ -2,7 +2,10 @@ use super::{master, slaver};
 
 use entity::{
     app::AppItem,
-    orm::{ColumnTrait, DbErr, EntityTrait, QueryFilter, QueryOrder, QuerySelect, Set},
+    orm::{
+        sea_query::Expr, ColumnTrait, DbErr, EntityTrait, QueryFilter, QueryOrder, QuerySelect,
+        Set, TransactionError, TransactionTrait,
+    },
     AppColumn, AppEntity, FavoriteActive, FavoriteColumn, FavoriteEntity, ID,
 };
 
@@ -39,10 +42,48 @@ pub async fn get_app(user_id: u32, offset: u64, limit: u64) -> Result<Vec<AppIte
         .filter(FavoriteColumn::UserId.eq(user_id))
         .filter(FavoriteColumn::DeletedAt.eq(0_u64))
         .filter(AppColumn::DeletedAt.eq(0_u64))
-        .order_by_desc(FavoriteColumn::Id) // 最后收藏的放前面
+        .order_by_asc(FavoriteColumn::Sort)
+        .order_by_desc(FavoriteColumn::Id) // 同序时最后收藏的放前面
         .offset(offset)
         .limit(limit)
         .into_model::<AppItem>()
         .all(slaver())
         .await
 }
+
+// 取消收藏 收藏不存在时返回 false
+pub async fn remove(app_id: u32, user_id: u32, now: u64) -> Result<bool, DbErr> {
+    let r = FavoriteEntity::update_many()
+        .col_expr(FavoriteColumn::DeletedAt, Expr::value(now))
+        .filter(FavoriteColumn::UserId.eq(user_id))
+        .filter(FavoriteColumn::AppId.eq(app_id))
+        .filter(FavoriteColumn::DeletedAt.eq(0_u64))
+        .exec(master())
+        .await?;
+    Ok(r.rows_affected > 0)
+}
+
+// 按 app_ids 的先后顺序设置收藏排序
+pub async fn sort(user_id: u32, app_ids: Vec<u32>) -> Result<(), DbErr> {
+    let transaction = master()
+        .transaction::<_, (), DbErr>(|tx| {
+            Box::pin(async move {
+                for (idx, app_id) in app_ids.into_iter().enumerate() {
+                    FavoriteEntity::update_many()
+                        .col_expr(FavoriteColumn::Sort, Expr::value(idx as u32 + 1))
+                        .filter(FavoriteColumn::UserId.eq(user_id))
+                        .filter(FavoriteColumn::AppId.eq(app_id))
+                        .filter(FavoriteColumn::DeletedAt.eq(0_u64))
+                        .exec(tx)
+                        .await?;
+                }
+                Ok(())
+            })
+        })
+        .await;
+    match transaction {
+        Ok(()) => Ok(()),
+        Err(TransactionError::Connection(err)) => Err(err),
+        Err(TransactionError::Transaction(err)) => Err(DbErr::Exec(err.to_string())),
+    }
+}
@@ -1,14 +1,72 @@
-use super::{master, slaver};
+use super::{like_contains, master, slaver};
 
-use entity::item::{ItemData, ItemDesc};
+use chrono::Local;
+use entity::item::{ItemData, ItemDesc, ItemSearch, ItemUsage};
+use entity::item_revision::Operation;
+use entity::orm::sea_query::Expr;
 use entity::orm::{
-    ActiveModelTrait, ColumnTrait, DbErr, EntityTrait, QueryFilter, QuerySelect, Set,
+    ActiveModelTrait, ColumnTrait, Condition, DatabaseTransaction, DbErr, EntityTrait,
+    PaginatorTrait, QueryFilter, QueryOrder, QuerySelect, Set, TransactionError, TransactionTrait,
 };
-use entity::{ItemActive, ItemCategory, ItemColumn, ItemEntity, ItemModel, ID};
+use entity::{
+    ItemActive, ItemCategory, ItemColumn, ItemEntity, ItemModel, ItemRevisionActive,
+    ItemRevisionEntity, NamespaceColumn, NamespaceEntity, ID,
+};
+
+pub async fn add(item: ItemActive) -> Result<u64, DbErr> {
+    // 构造修改记录
+    let mut revision = ItemRevisionActive {
+        namespace_id: item.namespace_id.clone(),
+        key: item.key.clone(),
+        old_value: Set(String::new()),
+        new_value: item.value.clone(),
+        category: item.category.clone(),
+        operation: Set(Operation::Create),
+        version: item.version.clone(),
+        user_id: item.modify_user_id.clone(),
+        ..Default::default()
+    };
+    let transaction = master()
+        .transaction::<_, u64, DbErr>(|tx| {
+            Box::pin(async move {
+                let id = ItemEntity::insert(item).exec(tx).await?.last_insert_id;
+                revision.item_id = Set(id);
+                ItemRevisionEntity::insert(revision).exec(tx).await?;
+                Ok(id)
+            })
+        })
+        .await;
+    match transaction {
+        Ok(id) => Ok(id),
+        Err(TransactionError::Connection(err)) => Err(err),
+        Err(TransactionError::Transaction(err)) => Err(DbErr::Exec(err.to_string())),
+    }
+}
 
-pub async fn add(app: ItemActive) -> Result<u64, DbErr> {
-    let r = ItemEntity::insert(app).exec(master()).await?;
-    Ok(r.last_insert_id)
+// 在事务中为新建的 namespace 批量创建 item
+pub async fn create_in(
+    tx: &DatabaseTransaction,
+    ns_id: u64,
+    items: Vec<ItemActive>,
+) -> Result<(), DbErr> {
+    for mut item in items.into_iter() {
+        item.namespace_id = Set(ns_id);
+        let mut revision = ItemRevisionActive {
+            namespace_id: Set(ns_id),
+            key: item.key.clone(),
+            old_value: Set(String::new()),
+            new_value: item.value.clone(),
+            category: item.category.clone(),
+            operation: Set(Operation::Create),
+            version: item.version.clone(),
+            user_id: item.modify_user_id.clone(),
+            ..Default::default()
+        };
+        let id = ItemEntity::insert(item).exec(tx).await?.last_insert_id;
+        revision.item_id = Set(id);
+        ItemRevisionEntity::insert(revision).exec(tx).await?;
+    }
+    Ok(())
 }
 
 pub async fn get_item_by_ids(ids: Vec<u64>) -> Result<Vec<ItemData>, DbErr> {
@@ -30,6 +88,7 @@ pub async fn get_item_by_ids(ids: Vec<u64>) -> Result<Vec<ItemData>, DbErr> {
 pub async fn get_namespace_items(id: u64) -> Result<Vec<ItemDesc>, DbErr> {
     ItemEntity::find()
         .select_only()
+        .column(ItemColumn::Id)
         .column(ItemColumn::Key)
         .column(ItemColumn::Value)
         .column(ItemColumn::Category)
@@ -41,6 +100,24 @@ pub async fn get_namespace_items(id: u64) -> Result<Vec<ItemDesc>, DbErr> {
         .await
 }
 
+// 批量获取多个 namespace 的 item
+pub async fn get_namespaces_items(ids: Vec<u64>) -> Result<Vec<ItemData>, DbErr> {
+    ItemEntity::find()
+        .select_only()
+        .column(ItemColumn::Id)
+        .column(ItemColumn::NamespaceId)
+        .column(ItemColumn::Key)
+        .column(ItemColumn::Value)
+        .column(ItemColumn::Category)
+        .column(ItemColumn::Version)
+        .column(ItemColumn::DeletedAt)
+        .filter(ItemColumn::NamespaceId.is_in(ids))
+        .filter(ItemColumn::DeletedAt.eq(0_u64))
+        .into_model::<ItemData>()
+        .all(master())
+        .await
+}
+
 pub async fn find_by_nsid_all(
     ns_id: u64,
     offset: u64,
@@ -55,12 +132,73 @@ pub async fn find_by_nsid_all(
         .await
 }
 
+// 获取已删除的 key, 同一 key 最后删除的在前
+pub async fn find_deleted_by_keys(ns_id: u64, keys: Vec<String>) -> Result<Vec<ItemModel>, DbErr> {
+    ItemEntity::find()
+        .filter(ItemColumn::NamespaceId.eq(ns_id))
+        .filter(ItemColumn::Key.is_in(keys))
+        .filter(ItemColumn::DeletedAt.ne(0_u64))
+        .order_by_desc(ItemColumn::Id)
+        .all(slaver())
+        .await
+}
+
+// 按 key 或 value 搜索未发布的 item
+pub async fn search(
+    keyword: String,
+    app_id: Option<String>,
+    cluster: Option<String>,
+    category: Option<ItemCategory>,
+    resource: Option<Condition>,
+    offset: u64,
+    limit: u64,
+) -> Result<Vec<ItemSearch>, DbErr> {
+    let pattern = like_contains(&keyword);
+    let mut stmt = ItemEntity::find()
+        .select_only()
+        .column(ItemColumn::Id)
+        .column(ItemColumn::NamespaceId)
+        .column(NamespaceColumn::AppId)
+        .column(NamespaceColumn::Cluster)
+        .column(NamespaceColumn::Namespace)
+        .column(ItemColumn::Key)
+        .column(ItemColumn::Value)
+        .column(ItemColumn::Category)
+        .inner_join(NamespaceEntity)
+        // 与已发布配置的搜索一致 不区分大小写
+        .filter(Expr::cust_with_values(
+            "(LOWER(`item`.`key`) LIKE ? ESCAPE '!' OR LOWER(`item`.`value`) LIKE ? ESCAPE '!')",
+            vec![pattern.clone(), pattern],
+        ))
+        .filter(ItemColumn::DeletedAt.eq(0_u64))
+        .filter(NamespaceColumn::DeletedAt.eq(0_u64));
+    if let Some(app_id) = app_id {
+        stmt = stmt.filter(NamespaceColumn::AppId.eq(app_id));
+    }
+    if let Some(cluster) = cluster {
+        stmt = stmt.filter(NamespaceColumn::Cluster.eq(cluster));
+    }
+    if let Some(category) = category {
+        stmt = stmt.filter(ItemColumn::Category.eq(category));
+    }
+    if let Some(resource) = resource {
+        stmt = stmt.filter(resource);
+    }
+    stmt.order_by_asc(ItemColumn::Id)
+        .offset(offset)
+        .limit(limit)
+        .into_model::<ItemSearch>()
+        .all(slaver())
+        .await
+}
+
 pub async fn is_key_exist(ns_id: u64, key: String) -> Result<bool, DbErr> {
     let entity = ItemEntity::find()
         .select_only()
         .column(ItemColumn::Id)
         .filter(ItemColumn::NamespaceId.eq(ns_id))
         .filter(ItemColumn::Key.eq(key))
+        .filter(ItemColumn::DeletedAt.eq(0_u64))
         .into_model::<ID>()
         .one(master())
         .await?;
@@ -75,7 +213,7 @@ pub async fn update(
     entity: ItemModel,
     key: Option<String>,
     value: Option<String>,
-    category: Option<String>,
+    category: Option<ItemCategory>,
     remark: Option<String>,
     version: i64,
     modify_user_id: u32,
@@ -83,7 +221,6 @@ pub async fn update(
     let mut active: ItemActive = entity.clone().into();
 
     if let Some(category) = category {
-        let category: ItemCategory = category.into();
         if entity.category != category {
             active.category = Set(category);
         }
@@ -110,16 +247,333 @@ pub async fn update(
     active.version = Set(entity.version + 1);
     active.modify_user_id = Set(modify_user_id);
 
-    // let result = active.save(master()).await?;
-    let result = ItemEntity::update_many()
-        .set(active)
-        .filter(ItemColumn::Id.eq(entity.id))
-        .filter(ItemColumn::Version.eq(version))
-        .exec(master())
-        .await?;
-    if result.rows_affected == 0 {
-        Ok(false)
-    } else {
-        Ok(true)
+    let revision = ItemRevisionActive {
+        namespace_id: Set(entity.namespace_id),
+        item_id: Set(entity.id),
+        key: active.key.clone(),
+        old_value: Set(entity.value),
+        new_value: active.value.clone(),
+        category: active.category.clone(),
+        operation: Set(Operation::Modify),
+        version: active.version.clone(),
+        user_id: Set(modify_user_id),
+        ..Default::default()
+    };
+    let transaction = master()
+        .transaction::<_, bool, DbErr>(|tx| {
+            Box::pin(async move {
+                let result = ItemEntity::update_many()
+                    .set(active)
+                    .filter(ItemColumn::Id.eq(entity.id))
+                    .filter(ItemColumn::Version.eq(version))
+                    .filter(ItemColumn::DeletedAt.eq(0_u64))
+                    .exec(tx)
+                    .await?;
+                if result.rows_affected == 0 {
+                    return Ok(false);
+                }
+                ItemRevisionEntity::insert(revision).exec(tx).await?;
+                Ok(true)
+            })
+        })
+        .await;
+    match transaction {
+        Ok(success) => Ok(success),
+        Err(TransactionError::Connection(err)) => Err(err),
+        Err(TransactionError::Transaction(err)) => Err(DbErr::Exec(err.to_string())),
+    }
+}
+
+// 软删除 item 并记录修改
+pub async fn delete(entity: ItemModel, version: i64, modify_user_id: u32) -> Result<bool, DbErr> {
+    let active = ItemActive {
+        version: Set(entity.version + 1),
+        modify_user_id: Set(modify_user_id),
+        deleted_at: Set(Local::now().timestamp() as u64),
+        ..Default::default()
+    };
+    let revision = ItemRevisionActive {
+        namespace_id: Set(entity.namespace_id),
+        item_id: Set(entity.id),
+        key: Set(entity.key),
+        old_value: Set(entity.value),
+        new_value: Set(String::new()),
+        category: Set(entity.category),
+        operation: Set(Operation::Delete),
+        version: Set(entity.version + 1),
+        user_id: Set(modify_user_id),
+        ..Default::default()
+    };
+    let transaction = master()
+        .transaction::<_, bool, DbErr>(|tx| {
+            Box::pin(async move {
+                let result = ItemEntity::update_many()
+                    .set(active)
+                    .filter(ItemColumn::Id.eq(entity.id))
+                    .filter(ItemColumn::Version.eq(version))
+                    .filter(ItemColumn::DeletedAt.eq(0_u64))
+                    .exec(tx)
+                    .await?;
+                if result.rows_affected == 0 {
+                    return Ok(false);
+                }
+                ItemRevisionEntity::insert(revision).exec(tx).await?;
+                Ok(true)
+            })
+        })
+        .await;
+    match transaction {
+        Ok(success) => Ok(success),
+        Err(TransactionError::Connection(err)) => Err(err),
+        Err(TransactionError::Transaction(err)) => Err(DbErr::Exec(err.to_string())),
+    }
+}
+
+// namespace 下未删除的 item 数量
+pub async fn count(ns_id: u64) -> Result<usize, DbErr> {
+    ItemEntity::find()
+        .filter(ItemColumn::NamespaceId.eq(ns_id))
+        .filter(ItemColumn::DeletedAt.eq(0_u64))
+        .count(master())
+        .await
+}
+
+// 各 namespace 未删除的 item 数量及最大的值字节数
+pub async fn get_usage(ids: Vec<u64>) -> Result<Vec<ItemUsage>, DbErr> {
+    ItemEntity::find()
+        .select_only()
+        .column(ItemColumn::NamespaceId)
+        .column_as(Expr::cust("CAST(COUNT(*) AS UNSIGNED)"), "item_count")
+        .column_as(
+            Expr::cust("CAST(MAX(LENGTH(`value`)) AS UNSIGNED)"),
+            "value_size",
+        )
+        .filter(ItemColumn::NamespaceId.is_in(ids))
+        .filter(ItemColumn::DeletedAt.eq(0_u64))
+        .group_by(ItemColumn::NamespaceId)
+        .into_model::<ItemUsage>()
+        .all(master())
+        .await
+}
+
+pub async fn find_by_namespace(ns_id: u64) -> Result<Vec<ItemModel>, DbErr> {
+    ItemEntity::find()
+        .filter(ItemColumn::NamespaceId.eq(ns_id))
+        .filter(ItemColumn::DeletedAt.eq(0_u64))
+        .all(master())
+        .await
+}
+
+// 还原到已发布状态的操作
+pub enum Revert {
+    // 恢复为已发布的值 已删除的 item 同时取消删除
+    Restore(ItemModel, ItemDesc),
+    // 删除未发布过的 item
+    Remove(ItemModel),
+    // 已发布的 key 无可恢复的记录 重新创建
+    Create(ItemDesc),
+}
+
+// 还原 item 至已发布的状态 任一 item 版本发生变化则全部回滚
+pub async fn revert(ns_id: u64, actions: Vec<Revert>, modify_user_id: u32) -> Result<bool, DbErr> {
+    let transaction = master()
+        .transaction::<_, bool, DbErr>(|tx| {
+            Box::pin(async move {
+                for action in actions.into_iter() {
+                    match action {
+                        Revert::Restore(entity, published) => {
+                            let active = ItemActive {
+                                value: Set(published.value.clone()),
+                                category: Set(published.category.clone()),
+                                version: Set(entity.version + 1),
+                                modify_user_id: Set(modify_user_id),
+                                deleted_at: Set(0),
+                                ..Default::default()
+                            };
+                            let result = ItemEntity::update_many()
+                                .set(active)
+                                .filter(ItemColumn::Id.eq(entity.id))
+                                .filter(ItemColumn::Version.eq(entity.version))
+                                .exec(tx)
+                                .await?;
+                            if result.rows_affected == 0 {
+                                return Err(DbErr::Custom("The item is changed".to_owned()));
+                            }
+                            let old_value = if entity.deleted_at == 0 {
+                                entity.value
+                            } else {
+                                String::new()
+                            };
+                            let revision = ItemRevisionActive {
+                                namespace_id: Set(ns_id),
+                                item_id: Set(entity.id),
+                                key: Set(entity.key),
+                                old_value: Set(old_value),
+                                new_value: Set(published.value),
+                                category: Set(published.category),
+                                operation: Set(Operation::Revert),
+                                version: Set(entity.version + 1),
+                                user_id: Set(modify_user_id),
+                                ..Default::default()
+                            };
+                            ItemRevisionEntity::insert(revision).exec(tx).await?;
+                        }
+                        Revert::Remove(entity) => {
+                            let active = ItemActive {
+                                version: Set(entity.version + 1),
+                                modify_user_id: Set(modify_user_id),
+                                deleted_at: Set(Local::now().timestamp() as u64),
+                                ..Default::default()
+                            };
+                            let result = ItemEntity::update_many()
+                                .set(active)
+                                .filter(ItemColumn::Id.eq(entity.id))
+                                .filter(ItemColumn::Version.eq(entity.version))
+                                .filter(ItemColumn::DeletedAt.eq(0_u64))
+                                .exec(tx)
+                                .await?;
+                            if result.rows_affected == 0 {
+                                return Err(DbErr::Custom("The item is changed".to_owned()));
+                            }
+                            let revision = ItemRevisionActive {
+                                namespace_id: Set(ns_id),
+                                item_id: Set(entity.id),
+                                key: Set(entity.key),
+                                old_value: Set(entity.value),
+                                new_value: Set(String::new()),
+                                category: Set(entity.category),
+                                operation: Set(Operation::Delete),
+                                version: Set(entity.version + 1),
+                                user_id: Set(modify_user_id),
+                                ..Default::default()
+                            };
+                            ItemRevisionEntity::insert(revision).exec(tx).await?;
+                        }
+                        Revert::Create(published) => {
+                            let active = ItemActive {
+                                namespace_id: Set(ns_id),
+                                key: Set(published.key.clone()),
+                                value: Set(published.value.clone()),
+                                category: Set(published.category.clone()),
+                                remark: Set(String::new()),
+                                version: Set(1),
+                                modify_user_id: Set(modify_user_id),
+                                ..Default::default()
+                            };
+                            let id = ItemEntity::insert(active).exec(tx).await?.last_insert_id;
+                            let revision = ItemRevisionActive {
+                                namespace_id: Set(ns_id),
+                                item_id: Set(id),
+                                key: Set(published.key),
+                                old_value: Set(String::new()),
+                                new_value: Set(published.value),
+                                category: Set(published.category),
+                                operation: Set(Operation::Revert),
+                                version: Set(1),
+                                user_id: Set(modify_user_id),
+                                ..Default::default()
+                            };
+                            ItemRevisionEntity::insert(revision).exec(tx).await?;
+                        }
+                    }
+                }
+                Ok(true)
+            })
+        })
+        .await;
+    match transaction {
+        Ok(success) => Ok(success),
+        // 版本发生变化
+        Err(TransactionError::Transaction(DbErr::Custom(_))) => Ok(false),
+        Err(TransactionError::Connection(err)) => Err(err),
+        Err(TransactionError::Transaction(err)) => Err(DbErr::Exec(err.to_string())),
+    }
+}
+
+// 批量新增、修改、删除 item, 任一 item 版本变化则全部回滚
+pub async fn import(
+    creates: Vec<ItemActive>,
+    updates: Vec<(ItemModel, ItemActive)>,
+    deletes: Vec<(ItemModel, ItemActive)>,
+) -> Result<bool, DbErr> {
+    let transaction = master()
+        .transaction::<_, bool, DbErr>(|tx| {
+            Box::pin(async move {
+                for item in creates.into_iter() {
+                    let mut revision = ItemRevisionActive {
+                        namespace_id: item.namespace_id.clone(),
+                        key: item.key.clone(),
+                        old_value: Set(String::new()),
+                        new_value: item.value.clone(),
+                        category: item.category.clone(),
+                        operation: Set(Operation::Create),
+                        version: item.version.clone(),
+                        user_id: item.modify_user_id.clone(),
+                        ..Default::default()
+                    };
+                    let id = ItemEntity::insert(item).exec(tx).await?.last_insert_id;
+                    revision.item_id = Set(id);
+                    ItemRevisionEntity::insert(revision).exec(tx).await?;
+                }
+                for (entity, active) in updates.into_iter() {
+                    let revision = ItemRevisionActive {
+                        namespace_id: Set(entity.namespace_id),
+                        item_id: Set(entity.id),
+                        key: Set(entity.key),
+                        old_value: Set(entity.value),
+                        new_value: active.value.clone(),
+                        category: active.category.clone(),
+                        operation: Set(Operation::Modify),
+                        version: active.version.clone(),
+                        user_id: active.modify_user_id.clone(),
+                        ..Default::default()
+                    };
+                    let result = ItemEntity::update_many()
+                        .set(active)
+                        .filter(ItemColumn::Id.eq(entity.id))
+                        .filter(ItemColumn::Version.eq(entity.version))
+                        .filter(ItemColumn::DeletedAt.eq(0_u64))
+                        .exec(tx)
+                        .await?;
+                    if result.rows_affected == 0 {
+                        return Err(DbErr::Custom("The item is changed".to_owned()));
+                    }
+                    ItemRevisionEntity::insert(revision).exec(tx).await?;
+                }
+                for (entity, active) in deletes.into_iter() {
+                    let revision = ItemRevisionActive {
+                        namespace_id: Set(entity.namespace_id),
+                        item_id: Set(entity.id),
+                        key: Set(entity.key),
+                        old_value: Set(entity.value),
+                        new_value: Set(String::new()),
+                        category: Set(entity.category),
+                        operation: Set(Operation::Delete),
+                        version: active.version.clone(),
+                        user_id: active.modify_user_id.clone(),
+                        ..Default::default()
+                    };
+                    let result = ItemEntity::update_many()
+                        .set(active)
+                        .filter(ItemColumn::Id.eq(entity.id))
+                        .filter(ItemColumn::Version.eq(entity.version))
+                        .filter(ItemColumn::DeletedAt.eq(0_u64))
+                        .exec(tx)
+                        .await?;
+                    if result.rows_affected == 0 {
+                        return Err(DbErr::Custom("The item is changed".to_owned()));
+                    }
+                    ItemRevisionEntity::insert(revision).exec(tx).await?;
+                }
+                Ok(true)
+            })
+        })
+        .await;
+    match transaction {
+        Ok(success) => Ok(success),
+        // 版本发生变化
+        Err(TransactionError::Transaction(DbErr::Custom(_))) => Ok(false),
+        Err(TransactionError::Connection(err)) => Err(err),
+        Err(TransactionError::Transaction(err)) => Err(DbErr::Exec(err.to_string())),
     }
 }
@@ -0,0 +1,63 @@
+use super::slaver;
+
+use entity::item_revision::{BlameItem, Operation, RevisionItem};
+use entity::orm::sea_query::{Expr, Query};
+use entity::orm::{ColumnTrait, DbErr, EntityTrait, QueryFilter, QueryOrder, QuerySelect};
+use entity::{ItemRevisionColumn, ItemRevisionEntity, UsersColumn, UsersEntity};
+
+pub async fn get_item_revision(
+    item_id: u64,
+    offset: u64,
+    limit: u64,
+) -> Result<Vec<RevisionItem>, DbErr> {
+    ItemRevisionEntity::find()
+        .select_only()
+        .column(ItemRevisionColumn::Id)
+        .column(ItemRevisionColumn::Key)
+        .column(ItemRevisionColumn::OldValue)
+        .column(ItemRevisionColumn::NewValue)
+        .column(ItemRevisionColumn::Category)
+        .column(ItemRevisionColumn::Operation)
+        .column(ItemRevisionColumn::Version)
+        .column(ItemRevisionColumn::UserId)
+        .column(UsersColumn::Nickname)
+        .column(ItemRevisionColumn::CreatedAt)
+        .left_join(UsersEntity)
+        .filter(ItemRevisionColumn::ItemId.eq(item_id))
+        .filter(ItemRevisionColumn::DeletedAt.eq(0_u64))
+        .order_by_desc(ItemRevisionColumn::Id)
+        .offset(offset)
+        .limit(limit)
+        .into_model::<RevisionItem>()
+        .all(slaver())
+        .await
+}
+
+// 获取 namespace 下每个 key 的最后一次修改记录
+pub async fn get_namespace_blame(namespace_id: u64) -> Result<Vec<BlameItem>, DbErr> {
+    let last_revision = Query::select()
+        .expr(Expr::col(ItemRevisionColumn::Id).max())
+        .from(ItemRevisionEntity)
+        .and_where(Expr::col(ItemRevisionColumn::NamespaceId).eq(namespace_id))
+        .and_where(Expr::col(ItemRevisionColumn::DeletedAt).eq(0_u64))
+        .group_by_col(ItemRevisionColumn::ItemId)
+        .to_owned();
+
+    ItemRevisionEntity::find()
+        .select_only()
+        .column(ItemRevisionColumn::ItemId)
+        .column(ItemRevisionColumn::Key)
+        .column(ItemRevisionColumn::Operation)
+        .column(ItemRevisionColumn::Version)
+        .column(ItemRevisionColumn::UserId)
+        .column(UsersColumn::Nickname)
+        .column(ItemRevisionColumn::CreatedAt)
+        .left_join(UsersEntity)
+        .filter(Expr::col((ItemRevisionEntity, ItemRevisionColumn::Id)).in_subquery(last_revision))
+        // 已删除的 key 不再展示
+        .filter(ItemRevisionColumn::Operation.ne(Operation::Delete))
+        .order_by_asc(ItemRevisionColumn::Key)
+        .into_model::<BlameItem>()
+        .all(slaver())
+        .await
+}
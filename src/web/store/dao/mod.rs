@@ -1,15 +1,24 @@
 pub mod app;
 pub mod app_extend;
+pub mod app_member;
 pub mod cluster;
 pub mod department;
+pub mod environment;
 pub mod favorite;
 pub mod item;
+pub mod item_revision;
 pub mod namespace;
+pub mod namespace_template;
+pub mod publish_request;
+pub mod publish_schedule;
+pub mod recent;
 pub mod release;
 pub mod release_history;
 pub mod rule;
 pub mod user_role;
 pub mod users;
+pub mod webhook;
+pub mod webhook_delivery;
 
 use entity::orm::DatabaseConnection;
 
@@ -22,3 +31,17 @@ fn master() -> &'static DatabaseConnection {
 fn slaver() -> &'static DatabaseConnection {
     get_store().db().slaver()
 }
+
+// 构造不区分大小写的包含匹配, 转义 LIKE 的通配符, 配合 ESCAPE '!' 使用
+fn like_contains(keyword: &str) -> String {
+    let mut pattern = String::with_capacity(keyword.len() + 2);
+    pattern.push('%');
+    for c in keyword.to_lowercase().chars() {
+        if matches!(c, '!' | '%' | '_') {
+            pattern.push('!');
+        }
+        pattern.push(c);
+    }
+    pattern.push('%');
+    pattern
+}
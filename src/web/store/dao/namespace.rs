@@ -1,14 +1,54 @@
-use super::{master, slaver};
+use super::{item, master, slaver};
 
-use entity::namespace::{NamespaceInfo, NamespaceItem};
-use entity::orm::{ColumnTrait, DbErr, EntityTrait, QueryFilter, QuerySelect};
-use entity::{NamespaceActive, NamespaceColumn, NamespaceEntity, Scope, ID};
+use chrono::Local;
+
+use entity::namespace::{
+    DeletedNamespace, KeyPolicy, NamespaceInfo, NamespaceItem, NamespaceLock, NamespaceRetention,
+};
+use entity::orm::sea_query::{Expr, Query};
+use entity::orm::{
+    ColumnTrait, Condition, DatabaseTransaction, DbErr, EntityTrait, QueryFilter, QueryOrder,
+    QuerySelect, TransactionError, TransactionTrait,
+};
+use entity::publish_schedule::ScheduleStatus;
+use entity::{
+    AppExtendColumn, AppExtendEntity, Approval, ItemActive, ItemColumn, ItemEntity,
+    ItemRevisionColumn, ItemRevisionEntity, NamespaceActive, NamespaceColumn, NamespaceEntity,
+    NamespaceModel, PublishRequestColumn, PublishRequestEntity, PublishScheduleColumn,
+    PublishScheduleEntity, Quota, ReleaseColumn, ReleaseEntity, ReleaseHistoryColumn,
+    ReleaseHistoryEntity, Scope, WebhookColumn, WebhookDeliveryColumn, WebhookDeliveryEntity,
+    WebhookEntity, ID,
+};
 
 pub async fn add(namespace: NamespaceActive) -> Result<u64, DbErr> {
     let r = NamespaceEntity::insert(namespace).exec(master()).await?;
     Ok(r.last_insert_id)
 }
 
+// 创建 namespace 及其草稿 item
+pub async fn add_with_items(
+    namespace: NamespaceActive,
+    items: Vec<ItemActive>,
+) -> Result<u64, DbErr> {
+    let transaction = master()
+        .transaction::<_, u64, DbErr>(|tx| {
+            Box::pin(async move {
+                let id = NamespaceEntity::insert(namespace)
+                    .exec(tx)
+                    .await?
+                    .last_insert_id;
+                item::create_in(tx, id, items).await?;
+                Ok(id)
+            })
+        })
+        .await;
+    match transaction {
+        Ok(id) => Ok(id),
+        Err(TransactionError::Connection(err)) => Err(err),
+        Err(TransactionError::Transaction(err)) => Err(DbErr::Exec(err.to_string())),
+    }
+}
+
 pub async fn get_namespace_by_appcluster(
     app_id: String,
     cluster: String,
@@ -100,3 +140,510 @@ pub async fn get_namespace_id(
         .await?;
     Ok(entity.and_then(|x| Some(x.id)))
 }
+
+pub async fn find_by_id(id: u64) -> Result<Option<NamespaceModel>, DbErr> {
+    NamespaceEntity::find_by_id(id)
+        .filter(NamespaceColumn::DeletedAt.eq(0_u64))
+        .one(slaver())
+        .await
+}
+
+pub async fn get_key_policy(id: u64) -> Result<Option<KeyPolicy>, DbErr> {
+    NamespaceEntity::find()
+        .select_only()
+        .column(NamespaceColumn::KeyPattern)
+        .column(NamespaceColumn::KeyReservedPrefix)
+        .column(NamespaceColumn::KeyMaxDepth)
+        .filter(NamespaceColumn::Id.eq(id))
+        .filter(NamespaceColumn::DeletedAt.eq(0_u64))
+        .into_model::<KeyPolicy>()
+        .one(master())
+        .await
+}
+
+pub async fn update_by_id(active: NamespaceActive, id: u64) -> Result<(), DbErr> {
+    NamespaceEntity::update_many()
+        .set(active)
+        .filter(NamespaceColumn::Id.eq(id))
+        .exec(master())
+        .await?;
+    Ok(())
+}
+
+pub async fn get_approval(id: u64) -> Result<Option<Approval>, DbErr> {
+    let entity = NamespaceEntity::find_by_id(id)
+        .filter(NamespaceColumn::DeletedAt.eq(0_u64))
+        .one(slaver())
+        .await?;
+    Ok(entity.map(|n| n.approval))
+}
+
+pub async fn get_lock(id: u64) -> Result<Option<NamespaceLock>, DbErr> {
+    NamespaceEntity::find()
+        .select_only()
+        .column(NamespaceColumn::EditLock)
+        .column(NamespaceColumn::LockUserId)
+        .column(NamespaceColumn::LockExpiredAt)
+        .filter(NamespaceColumn::Id.eq(id))
+        .filter(NamespaceColumn::DeletedAt.eq(0_u64))
+        .into_model::<NamespaceLock>()
+        .one(master())
+        .await
+}
+
+// 获取或续期编辑锁 锁未被持有、由自己持有或已过期时成功
+pub async fn lock(id: u64, user_id: u32, expired_at: u64, now: u64) -> Result<bool, DbErr> {
+    let r = NamespaceEntity::update_many()
+        .col_expr(NamespaceColumn::LockUserId, Expr::value(user_id))
+        .col_expr(NamespaceColumn::LockExpiredAt, Expr::value(expired_at))
+        .filter(NamespaceColumn::Id.eq(id))
+        .filter(NamespaceColumn::DeletedAt.eq(0_u64))
+        .filter(
+            Condition::any()
+                .add(NamespaceColumn::LockUserId.eq(0_u32))
+                .add(NamespaceColumn::LockUserId.eq(user_id))
+                .add(NamespaceColumn::LockExpiredAt.lt(now)),
+        )
+        .exec(master())
+        .await?;
+    Ok(r.rows_affected != 0)
+}
+
+pub async fn unlock(id: u64) -> Result<(), DbErr> {
+    NamespaceEntity::update_many()
+        .col_expr(NamespaceColumn::LockUserId, Expr::value(0_u32))
+        .col_expr(NamespaceColumn::LockExpiredAt, Expr::value(0_u64))
+        .filter(NamespaceColumn::Id.eq(id))
+        .exec(master())
+        .await?;
+    Ok(())
+}
+
+pub async fn get_retention(id: u64) -> Result<Option<NamespaceRetention>, DbErr> {
+    NamespaceEntity::find()
+        .select_only()
+        .column(NamespaceColumn::Id)
+        .column(NamespaceColumn::RetainCount)
+        .column(NamespaceColumn::RetainDays)
+        .filter(NamespaceColumn::Id.eq(id))
+        .filter(NamespaceColumn::DeletedAt.eq(0_u64))
+        .into_model::<NamespaceRetention>()
+        .one(slaver())
+        .await
+}
+
+pub async fn get_quota(id: u64) -> Result<Option<Quota>, DbErr> {
+    NamespaceEntity::find()
+        .select_only()
+        .column(NamespaceColumn::QuotaItemCount)
+        .column(NamespaceColumn::QuotaValueSize)
+        .column(NamespaceColumn::QuotaReleaseSize)
+        .filter(NamespaceColumn::Id.eq(id))
+        .filter(NamespaceColumn::DeletedAt.eq(0_u64))
+        .into_model::<Quota>()
+        .one(master())
+        .await
+}
+
+// 获取应用下所有未删除的 namespace
+pub async fn find_by_app(app_id: String) -> Result<Vec<NamespaceModel>, DbErr> {
+    NamespaceEntity::find()
+        .filter(NamespaceColumn::AppId.eq(app_id))
+        .filter(NamespaceColumn::DeletedAt.eq(0_u64))
+        .order_by_asc(NamespaceColumn::Cluster)
+        .order_by_asc(NamespaceColumn::Id)
+        .all(slaver())
+        .await
+}
+
+// 按ID顺序分批获取 namespace 的保留策略
+pub async fn get_retention_after(
+    after_id: u64,
+    limit: u64,
+) -> Result<Vec<NamespaceRetention>, DbErr> {
+    NamespaceEntity::find()
+        .select_only()
+        .column(NamespaceColumn::Id)
+        .column(NamespaceColumn::RetainCount)
+        .column(NamespaceColumn::RetainDays)
+        .filter(NamespaceColumn::Id.gt(after_id))
+        .filter(NamespaceColumn::DeletedAt.eq(0_u64))
+        .order_by_asc(NamespaceColumn::Id)
+        .limit(limit)
+        .into_model::<NamespaceRetention>()
+        .all(slaver())
+        .await
+}
+
+// 删除 namespace 及其关联的数据. namespace 已被删除时返回 false
+pub async fn delete(id: u64, now: u64) -> Result<bool, DbErr> {
+    let transaction = master()
+        .transaction::<_, bool, DbErr>(|tx| {
+            Box::pin(async move { delete_cascade(tx, id, now).await })
+        })
+        .await;
+    match transaction {
+        Ok(deleted) => Ok(deleted),
+        Err(TransactionError::Connection(err)) => Err(err),
+        Err(TransactionError::Transaction(err)) => Err(DbErr::Exec(err.to_string())),
+    }
+}
+
+// 在事务中删除 namespace, 同时删除其 item、发布、发布记录、发布申请、关联及 Webhook
+// 随之删除的数据记录 namespace 的删除批次, 等待中的定时发布被取消. namespace 已被删除时返回 false
+pub async fn delete_cascade(tx: &DatabaseTransaction, id: u64, now: u64) -> Result<bool, DbErr> {
+    let batch = Local::now().timestamp_nanos() as u64;
+    let r = NamespaceEntity::update_many()
+        .col_expr(NamespaceColumn::DeletedAt, Expr::value(now))
+        .col_expr(NamespaceColumn::DeleteBatch, Expr::value(batch))
+        .filter(NamespaceColumn::Id.eq(id))
+        .filter(NamespaceColumn::DeletedAt.eq(0_u64))
+        .exec(tx)
+        .await?;
+    if r.rows_affected == 0 {
+        return Ok(false);
+    }
+    ItemEntity::update_many()
+        .col_expr(ItemColumn::DeletedAt, Expr::value(now))
+        .col_expr(ItemColumn::DeleteBatch, Expr::value(batch))
+        .filter(ItemColumn::NamespaceId.eq(id))
+        .filter(ItemColumn::DeletedAt.eq(0_u64))
+        .exec(tx)
+        .await?;
+    ReleaseEntity::update_many()
+        .col_expr(ReleaseColumn::DeletedAt, Expr::value(now))
+        .col_expr(ReleaseColumn::DeleteBatch, Expr::value(batch))
+        .filter(ReleaseColumn::NamespaceId.eq(id))
+        .filter(ReleaseColumn::DeletedAt.eq(0_u64))
+        .exec(tx)
+        .await?;
+    ReleaseHistoryEntity::update_many()
+        .col_expr(ReleaseHistoryColumn::DeletedAt, Expr::value(now))
+        .col_expr(ReleaseHistoryColumn::DeleteBatch, Expr::value(batch))
+        .filter(ReleaseHistoryColumn::NamespaceId.eq(id))
+        .filter(ReleaseHistoryColumn::DeletedAt.eq(0_u64))
+        .exec(tx)
+        .await?;
+    PublishRequestEntity::update_many()
+        .col_expr(PublishRequestColumn::DeletedAt, Expr::value(now))
+        .col_expr(PublishRequestColumn::DeleteBatch, Expr::value(batch))
+        .filter(PublishRequestColumn::NamespaceId.eq(id))
+        .filter(PublishRequestColumn::DeletedAt.eq(0_u64))
+        .exec(tx)
+        .await?;
+    AppExtendEntity::update_many()
+        .col_expr(AppExtendColumn::DeletedAt, Expr::value(now))
+        .col_expr(AppExtendColumn::DeleteBatch, Expr::value(batch))
+        .filter(AppExtendColumn::NamespaceId.eq(id))
+        .filter(AppExtendColumn::DeletedAt.eq(0_u64))
+        .exec(tx)
+        .await?;
+    WebhookEntity::update_many()
+        .col_expr(WebhookColumn::DeletedAt, Expr::value(now))
+        .col_expr(WebhookColumn::DeleteBatch, Expr::value(batch))
+        .filter(WebhookColumn::NamespaceId.eq(id))
+        .filter(WebhookColumn::DeletedAt.eq(0_u64))
+        .exec(tx)
+        .await?;
+    PublishScheduleEntity::update_many()
+        .col_expr(
+            PublishScheduleColumn::Status,
+            Expr::value(ScheduleStatus::Cancelled),
+        )
+        .col_expr(
+            PublishScheduleColumn::Reason,
+            Expr::value("namespace 已删除"),
+        )
+        .filter(PublishScheduleColumn::NamespaceId.eq(id))
+        .filter(PublishScheduleColumn::Status.eq(ScheduleStatus::Waiting))
+        .filter(PublishScheduleColumn::DeletedAt.eq(0_u64))
+        .exec(tx)
+        .await?;
+    Ok(true)
+}
+
+// 恢复已删除的 namespace 及随其一同删除的数据, 已恢复时返回 false
+pub async fn restore(id: u64, deleted_at: u64) -> Result<bool, DbErr> {
+    let transaction = master()
+        .transaction::<_, bool, DbErr>(|tx| {
+            Box::pin(async move { restore_cascade(tx, id, deleted_at).await })
+        })
+        .await;
+    match transaction {
+        Ok(restored) => Ok(restored),
+        Err(TransactionError::Connection(err)) => Err(err),
+        Err(TransactionError::Transaction(err)) => Err(DbErr::Exec(err.to_string())),
+    }
+}
+
+// 在事务中恢复 namespace 及删除批次相同的关联数据
+pub async fn restore_cascade(
+    tx: &DatabaseTransaction,
+    id: u64,
+    deleted_at: u64,
+) -> Result<bool, DbErr> {
+    let namespace = NamespaceEntity::find_by_id(id)
+        .filter(NamespaceColumn::DeletedAt.eq(deleted_at))
+        .lock_exclusive()
+        .one(tx)
+        .await?;
+    let batch = match namespace {
+        Some(namespace) => namespace.delete_batch,
+        None => return Ok(false),
+    };
+    NamespaceEntity::update_many()
+        .col_expr(NamespaceColumn::DeletedAt, Expr::value(0_u64))
+        .col_expr(NamespaceColumn::DeleteBatch, Expr::value(0_u64))
+        .filter(NamespaceColumn::Id.eq(id))
+        .exec(tx)
+        .await?;
+    ItemEntity::update_many()
+        .col_expr(ItemColumn::DeletedAt, Expr::value(0_u64))
+        .col_expr(ItemColumn::DeleteBatch, Expr::value(0_u64))
+        .filter(ItemColumn::NamespaceId.eq(id))
+        .filter(cascaded(
+            ItemColumn::DeleteBatch,
+            ItemColumn::DeletedAt,
+            batch,
+            deleted_at,
+        ))
+        .exec(tx)
+        .await?;
+    ReleaseEntity::update_many()
+        .col_expr(ReleaseColumn::DeletedAt, Expr::value(0_u64))
+        .col_expr(ReleaseColumn::DeleteBatch, Expr::value(0_u64))
+        .filter(ReleaseColumn::NamespaceId.eq(id))
+        .filter(cascaded(
+            ReleaseColumn::DeleteBatch,
+            ReleaseColumn::DeletedAt,
+            batch,
+            deleted_at,
+        ))
+        .exec(tx)
+        .await?;
+    ReleaseHistoryEntity::update_many()
+        .col_expr(ReleaseHistoryColumn::DeletedAt, Expr::value(0_u64))
+        .col_expr(ReleaseHistoryColumn::DeleteBatch, Expr::value(0_u64))
+        .filter(ReleaseHistoryColumn::NamespaceId.eq(id))
+        .filter(cascaded(
+            ReleaseHistoryColumn::DeleteBatch,
+            ReleaseHistoryColumn::DeletedAt,
+            batch,
+            deleted_at,
+        ))
+        .exec(tx)
+        .await?;
+    PublishRequestEntity::update_many()
+        .col_expr(PublishRequestColumn::DeletedAt, Expr::value(0_u64))
+        .col_expr(PublishRequestColumn::DeleteBatch, Expr::value(0_u64))
+        .filter(PublishRequestColumn::NamespaceId.eq(id))
+        .filter(cascaded(
+            PublishRequestColumn::DeleteBatch,
+            PublishRequestColumn::DeletedAt,
+            batch,
+            deleted_at,
+        ))
+        .exec(tx)
+        .await?;
+    AppExtendEntity::update_many()
+        .col_expr(AppExtendColumn::DeletedAt, Expr::value(0_u64))
+        .col_expr(AppExtendColumn::DeleteBatch, Expr::value(0_u64))
+        .filter(AppExtendColumn::NamespaceId.eq(id))
+        .filter(cascaded(
+            AppExtendColumn::DeleteBatch,
+            AppExtendColumn::DeletedAt,
+            batch,
+            deleted_at,
+        ))
+        .exec(tx)
+        .await?;
+    WebhookEntity::update_many()
+        .col_expr(WebhookColumn::DeletedAt, Expr::value(0_u64))
+        .col_expr(WebhookColumn::DeleteBatch, Expr::value(0_u64))
+        .filter(WebhookColumn::NamespaceId.eq(id))
+        .filter(cascaded(
+            WebhookColumn::DeleteBatch,
+            WebhookColumn::DeletedAt,
+            batch,
+            deleted_at,
+        ))
+        .exec(tx)
+        .await?;
+    Ok(true)
+}
+
+// 随 namespace 删除的数据, 未记录删除批次的 namespace (早期删除) 以相同的删除时间匹配
+fn cascaded<C: ColumnTrait>(
+    batch_col: C,
+    deleted_col: C,
+    batch: u64,
+    deleted_at: u64,
+) -> Condition {
+    match batch {
+        0 => Condition::all()
+            .add(batch_col.eq(0_u64))
+            .add(deleted_col.eq(deleted_at)),
+        batch => Condition::all().add(batch_col.eq(batch)),
+    }
+}
+
+// 物理删除 deleted_before 之前删除的 namespace 及其所有数据, 返回清理的 namespace 数量
+pub async fn purge_deleted(deleted_before: u64, limit: u64) -> Result<u64, DbErr> {
+    let ids: Vec<u64> = NamespaceEntity::find()
+        .select_only()
+        .column(NamespaceColumn::Id)
+        .filter(NamespaceColumn::DeletedAt.gt(0_u64))
+        .filter(NamespaceColumn::DeletedAt.lt(deleted_before))
+        .order_by_asc(NamespaceColumn::Id)
+        .limit(limit)
+        .into_model::<ID>()
+        .all(master())
+        .await?
+        .into_iter()
+        .map(|x| x.id)
+        .collect();
+    if ids.is_empty() {
+        return Ok(0);
+    }
+    let count = ids.len() as u64;
+    let transaction = master()
+        .transaction::<_, (), DbErr>(|tx| {
+            Box::pin(async move {
+                let webhooks = Query::select()
+                    .column(WebhookColumn::Id)
+                    .from(WebhookEntity)
+                    .and_where(Expr::col(WebhookColumn::NamespaceId).is_in(ids.clone()))
+                    .to_owned();
+                WebhookDeliveryEntity::delete_many()
+                    .filter(Expr::col(WebhookDeliveryColumn::WebhookId).in_subquery(webhooks))
+                    .exec(tx)
+                    .await?;
+                WebhookEntity::delete_many()
+                    .filter(WebhookColumn::NamespaceId.is_in(ids.clone()))
+                    .exec(tx)
+                    .await?;
+                ItemRevisionEntity::delete_many()
+                    .filter(ItemRevisionColumn::NamespaceId.is_in(ids.clone()))
+                    .exec(tx)
+                    .await?;
+                ItemEntity::delete_many()
+                    .filter(ItemColumn::NamespaceId.is_in(ids.clone()))
+                    .exec(tx)
+                    .await?;
+                ReleaseHistoryEntity::delete_many()
+                    .filter(ReleaseHistoryColumn::NamespaceId.is_in(ids.clone()))
+                    .exec(tx)
+                    .await?;
+                ReleaseEntity::delete_many()
+                    .filter(ReleaseColumn::NamespaceId.is_in(ids.clone()))
+                    .exec(tx)
+                    .await?;
+                PublishRequestEntity::delete_many()
+                    .filter(PublishRequestColumn::NamespaceId.is_in(ids.clone()))
+                    .exec(tx)
+                    .await?;
+                PublishScheduleEntity::delete_many()
+                    .filter(PublishScheduleColumn::NamespaceId.is_in(ids.clone()))
+                    .exec(tx)
+                    .await?;
+                AppExtendEntity::delete_many()
+                    .filter(AppExtendColumn::NamespaceId.is_in(ids.clone()))
+                    .exec(tx)
+                    .await?;
+                NamespaceEntity::delete_many()
+                    .filter(NamespaceColumn::Id.is_in(ids))
+                    .filter(NamespaceColumn::DeletedAt.gt(0_u64))
+                    .exec(tx)
+                    .await?;
+                Ok(())
+            })
+        })
+        .await;
+    match transaction {
+        Ok(()) => Ok(count),
+        Err(TransactionError::Connection(err)) => Err(err),
+        Err(TransactionError::Transaction(err)) => Err(DbErr::Exec(err.to_string())),
+    }
+}
+
+pub async fn find_deleted(id: u64) -> Result<Option<NamespaceModel>, DbErr> {
+    NamespaceEntity::find_by_id(id)
+        .filter(NamespaceColumn::DeletedAt.gt(0_u64))
+        .one(master())
+        .await
+}
+
+// 获取 deleted_after 之后删除的 namespace
+pub async fn get_deleted(
+    app_id: String,
+    cluster: String,
+    deleted_after: u64,
+) -> Result<Vec<DeletedNamespace>, DbErr> {
+    NamespaceEntity::find()
+        .select_only()
+        .column(NamespaceColumn::Id)
+        .column(NamespaceColumn::Namespace)
+        .column(NamespaceColumn::DeletedAt)
+        .filter(NamespaceColumn::AppId.eq(app_id))
+        .filter(NamespaceColumn::Cluster.eq(cluster))
+        .filter(NamespaceColumn::DeletedAt.gt(deleted_after))
+        .order_by_desc(NamespaceColumn::DeletedAt)
+        .into_model::<DeletedNamespace>()
+        .all(slaver())
+        .await
+}
+
+// 获取应用下删除时间为 deleted_at 的 namespace ID, deleted_at 为 0 时获取未删除的
+pub async fn get_app_namespace_ids(app_id: String, deleted_at: u64) -> Result<Vec<u64>, DbErr> {
+    let list = NamespaceEntity::find()
+        .select_only()
+        .column(NamespaceColumn::Id)
+        .filter(NamespaceColumn::AppId.eq(app_id))
+        .filter(NamespaceColumn::DeletedAt.eq(deleted_at))
+        .into_model::<ID>()
+        .all(master())
+        .await?;
+    Ok(list.into_iter().map(|x| x.id).collect())
+}
+
+// 记录客户端拉取 namespace 的时间, 不修改 updated_at
+pub async fn set_polled(id: u64, now: u64) -> Result<(), DbErr> {
+    NamespaceEntity::update_many()
+        .col_expr(NamespaceColumn::PolledAt, Expr::value(now))
+        .col_expr(NamespaceColumn::UpdatedAt, Expr::col(NamespaceColumn::UpdatedAt).into())
+        .filter(NamespaceColumn::Id.eq(id))
+        .filter(NamespaceColumn::PolledAt.lt(now))
+        .exec(master())
+        .await?;
+    Ok(())
+}
+
+// 获取多个 namespace 中最近一次被客户端拉取的时间, 包含所有实例的记录
+pub async fn last_polled(ids: Vec<u64>) -> Result<u64, DbErr> {
+    if ids.is_empty() {
+        return Ok(0);
+    }
+    let polled = NamespaceEntity::find()
+        .select_only()
+        .column_as(NamespaceColumn::PolledAt, "id")
+        .filter(NamespaceColumn::Id.is_in(ids))
+        .order_by_desc(NamespaceColumn::PolledAt)
+        .into_model::<ID>()
+        .one(master())
+        .await?;
+    Ok(polled.map(|x| x.id).unwrap_or_default())
+}
+
+// 获取集群下所有 namespace 的ID
+pub async fn get_cluster_namespace_ids(app_id: String, cluster: String) -> Result<Vec<u64>, DbErr> {
+    let list = NamespaceEntity::find()
+        .select_only()
+        .column(NamespaceColumn::Id)
+        .filter(NamespaceColumn::AppId.eq(app_id))
+        .filter(NamespaceColumn::Cluster.eq(cluster))
+        .filter(NamespaceColumn::DeletedAt.eq(0_u64))
+        .into_model::<ID>()
+        .all(master())
+        .await?;
+    Ok(list.into_iter().map(|x| x.id).collect())
+}
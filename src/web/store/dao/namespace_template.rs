@@ -0,0 +1,70 @@
+use super::{master, slaver};
+
+use entity::namespace_template::TemplateInfo;
+use entity::orm::sea_query::Expr;
+use entity::orm::{ColumnTrait, DbErr, EntityTrait, QueryFilter, QueryOrder, QuerySelect};
+use entity::{
+    NamespaceTemplateActive, NamespaceTemplateColumn, NamespaceTemplateEntity,
+    NamespaceTemplateModel, ID,
+};
+
+pub async fn add(template: NamespaceTemplateActive) -> Result<u64, DbErr> {
+    let r = NamespaceTemplateEntity::insert(template)
+        .exec(master())
+        .await?;
+    Ok(r.last_insert_id)
+}
+
+pub async fn find_by_id(id: u64) -> Result<Option<NamespaceTemplateModel>, DbErr> {
+    NamespaceTemplateEntity::find_by_id(id)
+        .filter(NamespaceTemplateColumn::DeletedAt.eq(0_u64))
+        .one(slaver())
+        .await
+}
+
+pub async fn is_exist(app_id: String, name: String) -> Result<bool, DbErr> {
+    let entity = NamespaceTemplateEntity::find()
+        .select_only()
+        .column(NamespaceTemplateColumn::Id)
+        .filter(NamespaceTemplateColumn::AppId.eq(app_id))
+        .filter(NamespaceTemplateColumn::Name.eq(name))
+        .filter(NamespaceTemplateColumn::DeletedAt.eq(0_u64))
+        .into_model::<ID>()
+        .one(master())
+        .await?;
+    Ok(entity.is_some())
+}
+
+pub async fn find_by_app(
+    app_id: String,
+    offset: u64,
+    limit: u64,
+) -> Result<Vec<TemplateInfo>, DbErr> {
+    NamespaceTemplateEntity::find()
+        .select_only()
+        .column(NamespaceTemplateColumn::Id)
+        .column(NamespaceTemplateColumn::AppId)
+        .column(NamespaceTemplateColumn::Name)
+        .column(NamespaceTemplateColumn::Description)
+        .column(NamespaceTemplateColumn::Scope)
+        .column(NamespaceTemplateColumn::CreatorUser)
+        .column(NamespaceTemplateColumn::CreatedAt)
+        .filter(NamespaceTemplateColumn::AppId.eq(app_id))
+        .filter(NamespaceTemplateColumn::DeletedAt.eq(0_u64))
+        .order_by_desc(NamespaceTemplateColumn::Id)
+        .offset(offset)
+        .limit(limit)
+        .into_model::<TemplateInfo>()
+        .all(slaver())
+        .await
+}
+
+pub async fn delete(id: u64, now: u64) -> Result<bool, DbErr> {
+    let r = NamespaceTemplateEntity::update_many()
+        .col_expr(NamespaceTemplateColumn::DeletedAt, Expr::value(now))
+        .filter(NamespaceTemplateColumn::Id.eq(id))
+        .filter(NamespaceTemplateColumn::DeletedAt.eq(0_u64))
+        .exec(master())
+        .await?;
+    Ok(r.rows_affected != 0)
+}
@@ -0,0 +1,219 @@
+use super::release::{insert_release, rollback_release};
+use super::{master, rule, slaver};
+
+use entity::orm::sea_query::{Expr, Query};
+use entity::orm::{
+    ColumnTrait, Condition, DbErr, EntityTrait, QueryFilter, QueryOrder, QuerySelect, Select, Set,
+    TransactionError, TransactionTrait,
+};
+use entity::publish_request::{RequestItem, RequestStatus};
+use entity::{
+    NamespaceColumn, NamespaceEntity, PublishRequestActive, PublishRequestColumn,
+    PublishRequestEntity, PublishRequestModel, ReleaseActive, ReleaseModel, UsersColumn,
+    UsersEntity, ID,
+};
+
+pub async fn add(active: PublishRequestActive) -> Result<u64, DbErr> {
+    let r = PublishRequestEntity::insert(active).exec(master()).await?;
+    Ok(r.last_insert_id)
+}
+
+pub async fn find_by_id(id: u64) -> Result<Option<PublishRequestModel>, DbErr> {
+    PublishRequestEntity::find_by_id(id)
+        .filter(PublishRequestColumn::DeletedAt.eq(0_u64))
+        .one(master())
+        .await
+}
+
+pub async fn get_namespace_request(
+    namespace_id: u64,
+    status: Option<RequestStatus>,
+    offset: u64,
+    limit: u64,
+) -> Result<Vec<RequestItem>, DbErr> {
+    let stmt = request_select().filter(PublishRequestColumn::NamespaceId.eq(namespace_id));
+    request_page(stmt, status, offset, limit).await
+}
+
+// 获取应用下指定环境集群的 namespace 的发布申请, resource 为有权限的 namespace 条件
+pub async fn get_env_request(
+    app_id: String,
+    env: String,
+    resource: Option<Condition>,
+    status: Option<RequestStatus>,
+    offset: u64,
+    limit: u64,
+) -> Result<Vec<RequestItem>, DbErr> {
+    let mut namespace = Query::select()
+        .column(NamespaceColumn::Id)
+        .from(NamespaceEntity)
+        .and_where(Expr::col(NamespaceColumn::AppId).eq(app_id))
+        .and_where(rule::env_condition(&env))
+        .and_where(Expr::col(NamespaceColumn::DeletedAt).eq(0_u64))
+        .to_owned();
+    if let Some(resource) = resource {
+        namespace.cond_where(resource);
+    }
+    let stmt = request_select()
+        .filter(Expr::col(PublishRequestColumn::NamespaceId).in_subquery(namespace));
+    request_page(stmt, status, offset, limit).await
+}
+
+fn request_select() -> Select<PublishRequestEntity> {
+    PublishRequestEntity::find()
+        .select_only()
+        .column(PublishRequestColumn::Id)
+        .column(PublishRequestColumn::NamespaceId)
+        .column(PublishRequestColumn::Name)
+        .column(PublishRequestColumn::Remark)
+        .column(PublishRequestColumn::Change)
+        .column(PublishRequestColumn::RollbackReleaseId)
+        .column(PublishRequestColumn::Status)
+        .column(PublishRequestColumn::RequestUserId)
+        .column(UsersColumn::Nickname)
+        .column(PublishRequestColumn::ReviewUserId)
+        .column(PublishRequestColumn::ReviewRemark)
+        .column(PublishRequestColumn::ReleaseId)
+        .column(PublishRequestColumn::CreatedAt)
+        .left_join(UsersEntity)
+        .filter(PublishRequestColumn::DeletedAt.eq(0_u64))
+}
+
+async fn request_page(
+    mut stmt: Select<PublishRequestEntity>,
+    status: Option<RequestStatus>,
+    offset: u64,
+    limit: u64,
+) -> Result<Vec<RequestItem>, DbErr> {
+    if let Some(status) = status {
+        stmt = stmt.filter(PublishRequestColumn::Status.eq(status));
+    }
+    stmt.order_by_desc(PublishRequestColumn::Id)
+        .offset(offset)
+        .limit(limit)
+        .into_model::<RequestItem>()
+        .all(slaver())
+        .await
+}
+
+// 更新待审批的申请 申请已被处理时返回 false
+pub async fn update_pending(id: u64, active: PublishRequestActive) -> Result<bool, DbErr> {
+    let r = PublishRequestEntity::update_many()
+        .set(active)
+        .filter(PublishRequestColumn::Id.eq(id))
+        .filter(PublishRequestColumn::Status.eq(RequestStatus::Pending))
+        .filter(PublishRequestColumn::DeletedAt.eq(0_u64))
+        .exec(master())
+        .await?;
+    Ok(r.rows_affected != 0)
+}
+
+// 审批通过 生成发布并更新申请状态
+// 返回发布ID, 线上发布已变化或申请已被处理时返回 None
+pub async fn approve(
+    id: u64,
+    mut review: PublishRequestActive,
+    r_id: u64,
+    release: ReleaseActive,
+    change: String,
+) -> Result<Option<u64>, DbErr> {
+    let namespace_id = release.namespace_id.clone().unwrap();
+    let transaction = master()
+        .transaction::<_, Option<u64>, DbErr>(|tx| {
+            Box::pin(async move {
+                let release_id =
+                    match insert_release(tx, r_id, namespace_id, release, change).await? {
+                        Some(release_id) => release_id,
+                        None => return Ok(None),
+                    };
+                review.status = Set(RequestStatus::Approved);
+                review.release_id = Set(release_id);
+                let r = PublishRequestEntity::update_many()
+                    .set(review)
+                    .filter(PublishRequestColumn::Id.eq(id))
+                    .filter(PublishRequestColumn::Status.eq(RequestStatus::Pending))
+                    .exec(tx)
+                    .await?;
+                if r.rows_affected == 0 {
+                    return Err(DbErr::Custom("The request is changed".to_owned()));
+                }
+                Ok(Some(release_id))
+            })
+        })
+        .await;
+    match transaction {
+        Ok(id) => Ok(id),
+        // 申请已被处理
+        Err(TransactionError::Transaction(DbErr::Custom(_))) => Ok(None),
+        Err(TransactionError::Connection(err)) => Err(err),
+        Err(TransactionError::Transaction(err)) => Err(DbErr::Exec(err.to_string())),
+    }
+}
+
+// 审批通过回滚申请 回滚至目标发布并更新申请状态
+// 返回新发布的ID, 线上发布已变化或申请已被处理时返回 None
+pub async fn approve_rollback(
+    id: u64,
+    mut review: PublishRequestActive,
+    current_id: u64,
+    target: ReleaseModel,
+    remark: String,
+    change: String,
+    user_id: u32,
+) -> Result<Option<u64>, DbErr> {
+    let transaction = master()
+        .transaction::<_, Option<u64>, DbErr>(|tx| {
+            Box::pin(async move {
+                let release_id = match rollback_release(
+                    tx, current_id, target, remark, change, user_id,
+                )
+                .await?
+                {
+                    Some(release_id) => release_id,
+                    None => return Ok(None),
+                };
+                review.status = Set(RequestStatus::Approved);
+                review.release_id = Set(release_id);
+                let r = PublishRequestEntity::update_many()
+                    .set(review)
+                    .filter(PublishRequestColumn::Id.eq(id))
+                    .filter(PublishRequestColumn::Status.eq(RequestStatus::Pending))
+                    .exec(tx)
+                    .await?;
+                if r.rows_affected == 0 {
+                    return Err(DbErr::Custom("The request is changed".to_owned()));
+                }
+                Ok(Some(release_id))
+            })
+        })
+        .await;
+    match transaction {
+        Ok(id) => Ok(id),
+        // 申请已被处理
+        Err(TransactionError::Transaction(DbErr::Custom(_))) => Ok(None),
+        Err(TransactionError::Connection(err)) => Err(err),
+        Err(TransactionError::Transaction(err)) => Err(DbErr::Exec(err.to_string())),
+    }
+}
+
+// 待审批申请所基于的发布及回滚申请的目标发布
+pub async fn get_pending_base(namespace_id: u64) -> Result<Vec<u64>, DbErr> {
+    let mut list = Vec::new();
+    for column in [
+        PublishRequestColumn::BaseReleaseId,
+        PublishRequestColumn::RollbackReleaseId,
+    ] {
+        let ids = PublishRequestEntity::find()
+            .select_only()
+            .column_as(column, "id")
+            .filter(PublishRequestColumn::NamespaceId.eq(namespace_id))
+            .filter(PublishRequestColumn::Status.eq(RequestStatus::Pending))
+            .filter(PublishRequestColumn::DeletedAt.eq(0_u64))
+            .filter(column.ne(0_u64))
+            .into_model::<ID>()
+            .all(slaver())
+            .await?;
+        list.extend(ids.into_iter().map(|x| x.id));
+    }
+    Ok(list)
+}
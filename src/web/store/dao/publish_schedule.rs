@@ -0,0 +1,98 @@
+use super::{master, slaver};
+
+use entity::orm::sea_query::Expr;
+use entity::orm::{ColumnTrait, DbErr, EntityTrait, QueryFilter, QueryOrder, QuerySelect};
+use entity::publish_schedule::{ScheduleItem, ScheduleStatus};
+use entity::{
+    PublishScheduleActive, PublishScheduleColumn, PublishScheduleEntity, PublishScheduleModel,
+    UsersColumn, UsersEntity,
+};
+
+pub async fn add(active: PublishScheduleActive) -> Result<u64, DbErr> {
+    let r = PublishScheduleEntity::insert(active).exec(master()).await?;
+    Ok(r.last_insert_id)
+}
+
+pub async fn find_by_id(id: u64) -> Result<Option<PublishScheduleModel>, DbErr> {
+    PublishScheduleEntity::find_by_id(id)
+        .filter(PublishScheduleColumn::DeletedAt.eq(0_u64))
+        .one(master())
+        .await
+}
+
+pub async fn get_namespace_schedule(
+    namespace_id: u64,
+    status: Option<ScheduleStatus>,
+    offset: u64,
+    limit: u64,
+) -> Result<Vec<ScheduleItem>, DbErr> {
+    let mut stmt = PublishScheduleEntity::find()
+        .select_only()
+        .column(PublishScheduleColumn::Id)
+        .column(PublishScheduleColumn::Name)
+        .column(PublishScheduleColumn::Remark)
+        .column(PublishScheduleColumn::PublishAt)
+        .column(PublishScheduleColumn::Status)
+        .column(PublishScheduleColumn::Reason)
+        .column(PublishScheduleColumn::ReleaseId)
+        .column(PublishScheduleColumn::UserId)
+        .column(UsersColumn::Nickname)
+        .column(PublishScheduleColumn::CreatedAt)
+        .left_join(UsersEntity)
+        .filter(PublishScheduleColumn::NamespaceId.eq(namespace_id))
+        .filter(PublishScheduleColumn::DeletedAt.eq(0_u64));
+    if let Some(status) = status {
+        stmt = stmt.filter(PublishScheduleColumn::Status.eq(status));
+    }
+    stmt.order_by_desc(PublishScheduleColumn::PublishAt)
+        .offset(offset)
+        .limit(limit)
+        .into_model::<ScheduleItem>()
+        .all(slaver())
+        .await
+}
+
+// 获取已到期等待执行的定时发布
+pub async fn get_due(now: u64, limit: u64) -> Result<Vec<PublishScheduleModel>, DbErr> {
+    PublishScheduleEntity::find()
+        .filter(PublishScheduleColumn::Status.eq(ScheduleStatus::Waiting))
+        .filter(PublishScheduleColumn::PublishAt.lte(now))
+        .filter(PublishScheduleColumn::DeletedAt.eq(0_u64))
+        .order_by_asc(PublishScheduleColumn::PublishAt)
+        .limit(limit)
+        .all(master())
+        .await
+}
+
+// 将开始执行时间早于 claimed_before 的执行中定时发布恢复为等待, 返回回收的数量
+// 执行实例中断时发布可能已完成, 再次执行时基于的发布已变化会失败而不会重复发布
+pub async fn reclaim_stale(claimed_before: u64) -> Result<u64, DbErr> {
+    let r = PublishScheduleEntity::update_many()
+        .col_expr(
+            PublishScheduleColumn::Status,
+            Expr::value(ScheduleStatus::Waiting),
+        )
+        .col_expr(PublishScheduleColumn::ClaimedAt, Expr::value(0_u64))
+        .filter(PublishScheduleColumn::Status.eq(ScheduleStatus::Running))
+        .filter(PublishScheduleColumn::ClaimedAt.lt(claimed_before))
+        .filter(PublishScheduleColumn::DeletedAt.eq(0_u64))
+        .exec(master())
+        .await?;
+    Ok(r.rows_affected)
+}
+
+// 仅在状态为 status 时更新 状态已变化时返回 false
+pub async fn update_status(
+    id: u64,
+    status: ScheduleStatus,
+    active: PublishScheduleActive,
+) -> Result<bool, DbErr> {
+    let r = PublishScheduleEntity::update_many()
+        .set(active)
+        .filter(PublishScheduleColumn::Id.eq(id))
+        .filter(PublishScheduleColumn::Status.eq(status))
+        .filter(PublishScheduleColumn::DeletedAt.eq(0_u64))
+        .exec(master())
+        .await?;
+    Ok(r.rows_affected != 0)
+}
@@ -0,0 +1,67 @@
+use super::{master, slaver};
+
+use entity::{
+    app::AppItem,
+    orm::{
+        sea_query::Expr, ColumnTrait, DbErr, EntityTrait, QueryFilter, QueryOrder, QuerySelect, Set,
+    },
+    AppColumn, AppEntity, RecentActive, RecentColumn, RecentEntity, ID,
+};
+
+// 记录用户访问应用 每个用户仅保留最近 limit 条
+pub async fn visit(user_id: u32, app_id: u32, now: u64, limit: u64) -> Result<(), DbErr> {
+    let r = RecentEntity::update_many()
+        .col_expr(RecentColumn::VisitedAt, Expr::value(now))
+        .filter(RecentColumn::UserId.eq(user_id))
+        .filter(RecentColumn::AppId.eq(app_id))
+        .exec(master())
+        .await?;
+    if r.rows_affected > 0 {
+        return Ok(());
+    }
+    RecentEntity::insert(RecentActive {
+        user_id: Set(user_id),
+        app_id: Set(app_id),
+        visited_at: Set(now),
+        ..Default::default()
+    })
+    .exec(master())
+    .await?;
+
+    // 清理超出数量的记录
+    let expired: Vec<u64> = RecentEntity::find()
+        .select_only()
+        .column(RecentColumn::Id)
+        .filter(RecentColumn::UserId.eq(user_id))
+        .order_by_desc(RecentColumn::VisitedAt)
+        .offset(limit)
+        .limit(limit)
+        .into_model::<ID>()
+        .all(master())
+        .await?
+        .into_iter()
+        .map(|x| x.id)
+        .collect();
+    if !expired.is_empty() {
+        RecentEntity::delete_many()
+            .filter(RecentColumn::Id.is_in(expired))
+            .exec(master())
+            .await?;
+    }
+    Ok(())
+}
+
+pub async fn get_app(user_id: u32, limit: u64) -> Result<Vec<AppItem>, DbErr> {
+    RecentEntity::find()
+        .select_only()
+        .column(AppColumn::AppId)
+        .column(AppColumn::Name)
+        .left_join(AppEntity)
+        .filter(RecentColumn::UserId.eq(user_id))
+        .filter(AppColumn::DeletedAt.eq(0_u64))
+        .order_by_desc(RecentColumn::VisitedAt)
+        .limit(limit)
+        .into_model::<AppItem>()
+        .all(slaver())
+        .await
+}
@@ -1,14 +1,22 @@
-use super::{master, slaver};
+use super::{like_contains, master, slaver};
 
-use entity::item::ItemDesc;
+use entity::item::{ConfigItem, ItemDesc, ItemSearch};
+use entity::orm::prelude::DateTimeWithTimeZone;
+use entity::orm::sea_query::{Expr, MysqlQueryBuilder, Query};
 use entity::orm::{
-    ColumnTrait, DbErr, EntityTrait, NotSet, QueryFilter, QueryOrder, QuerySelect, Set,
+    ActiveEnum, ColumnTrait, Condition, DatabaseTransaction, DbBackend, DbErr, EntityTrait,
+    FromQueryResult, QueryFilter, QueryOrder, QuerySelect, QueryTrait, Set, Statement,
     TransactionError, TransactionTrait,
 };
-use entity::release::{Effective, ReleaseConfig};
+use entity::release::{
+    Effective, NamespaceReleaseSize, Reclaimed, ReleaseChecksum, ReleaseConfig, ReleaseSize,
+};
+use entity::release_history::Operation;
 use entity::{
-    ReleaseActive, ReleaseColumn, ReleaseEntity, ReleaseHistoryActive, ReleaseHistoryEntity, ID,
+    ItemCategory, NamespaceColumn, NamespaceEntity, ReleaseActive, ReleaseColumn, ReleaseEntity,
+    ReleaseHistoryActive, ReleaseHistoryColumn, ReleaseHistoryEntity, ReleaseModel, ID,
 };
+use sha2::{Digest, Sha256};
 
 pub async fn publication_item(
     r_id: u64,
@@ -18,14 +26,381 @@ pub async fn publication_item(
     config: Vec<ItemDesc>,
     change: Vec<ItemDesc>,
     user_id: u32,
-) -> Result<bool, DbErr> {
+) -> Result<Option<u64>, DbErr> {
     // 序列化
-    let config_data = serde_json::to_string(&config).unwrap();
     let change_data = serde_json::to_string(&change).unwrap();
+    let release = new_release(namespace_id, name, remark, &config, user_id)?;
+    let transaction = master()
+        .transaction::<_, Option<u64>, DbErr>(|tx| {
+            Box::pin(
+                async move { insert_release(tx, r_id, namespace_id, release, change_data).await },
+            )
+        })
+        .await;
+    match transaction {
+        Ok(id) => Ok(id),
+        Err(TransactionError::Connection(err)) => Err(err),
+        Err(TransactionError::Transaction(err)) => Err(DbErr::Exec(err.to_string())),
+    }
+}
+
+// 发布配置的校验和
+// 配置项按 key 排序后序列化为与下发客户端一致的 JSON, 取其 sha256 的十六进制
+pub fn checksum(configurations: &str) -> Result<String, serde_json::Error> {
+    let mut items = serde_json::from_str::<Vec<ConfigItem>>(configurations)?;
+    items.sort_by(|a, b| a.key.cmp(&b.key));
+    let data = serde_json::to_string(&items)?;
+    Ok(Sha256::digest(data.as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect())
+}
+
+// 计算待保存发布的校验和 配置无法解析时返回错误
+fn release_checksum(configurations: &str) -> Result<String, DbErr> {
+    checksum(configurations).map_err(|err| DbErr::Json(err.to_string()))
+}
+
+pub fn new_release(
+    namespace_id: u64,
+    name: String,
+    remark: String,
+    config: &[ItemDesc],
+    user_id: u32,
+) -> Result<ReleaseActive, DbErr> {
+    let configurations = serde_json::to_string(config).unwrap();
+    Ok(ReleaseActive {
+        namespace_id: Set(namespace_id),
+        name: Set(name),
+        checksum: Set(release_checksum(&configurations)?),
+        configurations: Set(configurations),
+        remark: Set(remark),
+        publish_user_id: Set(user_id),
+        is_abandoned: Set(Effective::Valid),
+        ..Default::default()
+    })
+}
+
+// 在事务中生成发布及发布记录 返回发布ID, 线上发布已不是 r_id 时返回 None
+pub async fn insert_release(
+    tx: &DatabaseTransaction,
+    r_id: u64,
+    namespace_id: u64,
+    release: ReleaseActive,
+    change: String,
+) -> Result<Option<u64>, DbErr> {
+    let id = ReleaseEntity::find()
+        .select_only()
+        .column(ReleaseColumn::Id)
+        .filter(ReleaseColumn::NamespaceId.eq(namespace_id))
+        .filter(ReleaseColumn::DeletedAt.eq(0_u64))
+        .order_by_desc(ReleaseColumn::Id)
+        .lock_exclusive()
+        .into_model::<ID>()
+        .one(tx)
+        .await?
+        .map(|x| x.id)
+        .unwrap_or_default();
+    if r_id != id {
+        // 已被发布过
+        return Ok(None);
+    }
+    let id = ReleaseEntity::insert(release)
+        .exec(tx)
+        .await?
+        .last_insert_id;
+    // 增加更改记录
+    let history = ReleaseHistoryActive {
+        namespace_id: Set(namespace_id),
+        change: Set(change),
+        release_id: Set(id),
+        operation: Set(Operation::Publish),
+        ..Default::default()
+    };
+    ReleaseHistoryEntity::insert(history).exec(tx).await?;
+    // 发布后释放编辑锁
+    NamespaceEntity::update_many()
+        .col_expr(NamespaceColumn::LockUserId, Expr::value(0_u32))
+        .col_expr(NamespaceColumn::LockExpiredAt, Expr::value(0_u64))
+        .filter(NamespaceColumn::Id.eq(namespace_id))
+        .exec(tx)
+        .await?;
+    Ok(Some(id))
+}
+
+// 回滚至指定的发布 复制目标发布为新的发布, 目标之后的发布标记为无效
+// 返回新发布的ID, 当前发布已变化时返回 None
+pub async fn rollback(
+    current_id: u64,
+    target: ReleaseModel,
+    remark: String,
+    change: String,
+    user_id: u32,
+) -> Result<Option<u64>, DbErr> {
     let transaction = master()
-        .transaction::<_, bool, DbErr>(|tx| {
+        .transaction::<_, Option<u64>, DbErr>(|tx| {
             Box::pin(async move {
-                let id = ReleaseEntity::find()
+                rollback_release(tx, current_id, target, remark, change, user_id).await
+            })
+        })
+        .await;
+    match transaction {
+        Ok(id) => Ok(id),
+        Err(TransactionError::Connection(err)) => Err(err),
+        Err(TransactionError::Transaction(err)) => Err(DbErr::Exec(err.to_string())),
+    }
+}
+
+// 在事务中回滚至指定的发布 当前发布已不是 current_id 时返回 None
+pub async fn rollback_release(
+    tx: &DatabaseTransaction,
+    current_id: u64,
+    target: ReleaseModel,
+    remark: String,
+    change: String,
+    user_id: u32,
+) -> Result<Option<u64>, DbErr> {
+    let id = ReleaseEntity::find()
+        .select_only()
+        .column(ReleaseColumn::Id)
+        .filter(ReleaseColumn::NamespaceId.eq(target.namespace_id))
+        .filter(ReleaseColumn::DeletedAt.eq(0_u64))
+        .order_by_desc(ReleaseColumn::Id)
+        .lock_exclusive()
+        .into_model::<ID>()
+        .one(tx)
+        .await?
+        .map(|x| x.id)
+        .unwrap_or_default();
+    if current_id != id {
+        return Ok(None);
+    }
+    // 目标之后的发布均失效
+    ReleaseEntity::update_many()
+        .col_expr(ReleaseColumn::IsAbandoned, Expr::value(Effective::Invalid))
+        .filter(ReleaseColumn::NamespaceId.eq(target.namespace_id))
+        .filter(ReleaseColumn::Id.gt(target.id))
+        .filter(ReleaseColumn::DeletedAt.eq(0_u64))
+        .exec(tx)
+        .await?;
+    let release = ReleaseActive {
+        namespace_id: Set(target.namespace_id),
+        name: Set(target.name),
+        checksum: Set(release_checksum(&target.configurations)?),
+        configurations: Set(target.configurations),
+        remark: Set(remark),
+        publish_user_id: Set(user_id),
+        is_abandoned: Set(Effective::Valid),
+        ..Default::default()
+    };
+    let id = ReleaseEntity::insert(release)
+        .exec(tx)
+        .await?
+        .last_insert_id;
+    let history = ReleaseHistoryActive {
+        namespace_id: Set(target.namespace_id),
+        change: Set(change),
+        release_id: Set(id),
+        operation: Set(Operation::Rollback),
+        ..Default::default()
+    };
+    ReleaseHistoryEntity::insert(history).exec(tx).await?;
+    Ok(Some(id))
+}
+
+pub async fn find_by_id(id: u64) -> Result<Option<ReleaseModel>, DbErr> {
+    ReleaseEntity::find_by_id(id)
+        .filter(ReleaseColumn::DeletedAt.eq(0_u64))
+        .one(slaver())
+        .await
+}
+
+pub async fn get_namespace_config(namespace_id: u64) -> Result<Option<ReleaseConfig>, DbErr> {
+    ReleaseEntity::find()
+        .select_only()
+        .column(ReleaseColumn::Id)
+        .column(ReleaseColumn::Configurations)
+        .column(ReleaseColumn::Checksum)
+        .filter(ReleaseColumn::NamespaceId.eq(namespace_id))
+        .filter(ReleaseColumn::DeletedAt.eq(0_u64))
+        .order_by_desc(ReleaseColumn::Id)
+        .into_model::<ReleaseConfig>()
+        .one(slaver())
+        .await
+}
+
+// 批量获取多个 namespace 最后一次发布的配置
+pub async fn get_namespaces_config(ids: Vec<u64>) -> Result<Vec<ReleaseChecksum>, DbErr> {
+    let last_release = Query::select()
+        .expr(Expr::col(ReleaseColumn::Id).max())
+        .from(ReleaseEntity)
+        .and_where(Expr::col(ReleaseColumn::NamespaceId).is_in(ids))
+        .and_where(Expr::col(ReleaseColumn::DeletedAt).eq(0_u64))
+        .group_by_col(ReleaseColumn::NamespaceId)
+        .to_owned();
+    ReleaseEntity::find()
+        .select_only()
+        .column(ReleaseColumn::Id)
+        .column(ReleaseColumn::NamespaceId)
+        .column(ReleaseColumn::Configurations)
+        .column(ReleaseColumn::Checksum)
+        .filter(ReleaseColumn::Id.in_subquery(last_release))
+        .into_model::<ReleaseChecksum>()
+        .all(slaver())
+        .await
+}
+
+// 批量获取多个 namespace 最后一次发布的配置字节数
+pub async fn get_namespaces_size(ids: Vec<u64>) -> Result<Vec<NamespaceReleaseSize>, DbErr> {
+    let last_release = Query::select()
+        .expr(Expr::col(ReleaseColumn::Id).max())
+        .from(ReleaseEntity)
+        .and_where(Expr::col(ReleaseColumn::NamespaceId).is_in(ids))
+        .and_where(Expr::col(ReleaseColumn::DeletedAt).eq(0_u64))
+        .group_by_col(ReleaseColumn::NamespaceId)
+        .to_owned();
+    ReleaseEntity::find()
+        .select_only()
+        .column(ReleaseColumn::NamespaceId)
+        .column_as(
+            Expr::cust("CAST(LENGTH(`configurations`) AS UNSIGNED)"),
+            "size",
+        )
+        .filter(ReleaseColumn::Id.in_subquery(last_release))
+        .into_model::<NamespaceReleaseSize>()
+        .all(slaver())
+        .await
+}
+
+// 批量获取发布的配置 包含已清理的发布
+pub async fn get_configs(ids: Vec<u64>) -> Result<Vec<ReleaseConfig>, DbErr> {
+    ReleaseEntity::find()
+        .select_only()
+        .column(ReleaseColumn::Id)
+        .column(ReleaseColumn::Configurations)
+        .column(ReleaseColumn::Checksum)
+        .filter(ReleaseColumn::Id.is_in(ids))
+        .into_model::<ReleaseConfig>()
+        .all(slaver())
+        .await
+}
+
+// 获取 namespace 在 [start, end] 范围内及 start 前一次的发布ID 包含已清理的发布, 按ID升序
+pub async fn get_ids_between(namespace_id: u64, start: u64, end: u64) -> Result<Vec<u64>, DbErr> {
+    let previous = ReleaseEntity::find()
+        .select_only()
+        .column(ReleaseColumn::Id)
+        .filter(ReleaseColumn::NamespaceId.eq(namespace_id))
+        .filter(ReleaseColumn::Id.lt(start))
+        .order_by_desc(ReleaseColumn::Id)
+        .into_model::<ID>()
+        .one(slaver())
+        .await?;
+    let list = ReleaseEntity::find()
+        .select_only()
+        .column(ReleaseColumn::Id)
+        .filter(ReleaseColumn::NamespaceId.eq(namespace_id))
+        .filter(ReleaseColumn::Id.between(start, end))
+        .order_by_asc(ReleaseColumn::Id)
+        .into_model::<ID>()
+        .all(slaver())
+        .await?;
+    Ok(previous.into_iter().chain(list).map(|x| x.id).collect())
+}
+
+// 在各 namespace 最后一次发布的配置中搜索 key 或值包含关键字的配置项, 不区分大小写
+// 使用 JSON_TABLE 展开发布的配置 在数据库中完成过滤及分页
+pub async fn search_published(
+    keyword: String,
+    app_id: Option<String>,
+    cluster: Option<String>,
+    category: Option<ItemCategory>,
+    resource: Option<Condition>,
+    offset: u64,
+    limit: u64,
+) -> Result<Vec<ItemSearch>, DbErr> {
+    let last_release = Query::select()
+        .expr(Expr::col(ReleaseColumn::Id).max())
+        .from(ReleaseEntity)
+        .and_where(Expr::col(ReleaseColumn::DeletedAt).eq(0_u64))
+        .group_by_col(ReleaseColumn::NamespaceId)
+        .to_owned();
+    let mut stmt = ReleaseEntity::find()
+        .select_only()
+        .column(ReleaseColumn::NamespaceId)
+        .column(NamespaceColumn::AppId)
+        .column(NamespaceColumn::Cluster)
+        .column(NamespaceColumn::Namespace)
+        .column(ReleaseColumn::Configurations)
+        .inner_join(NamespaceEntity)
+        .filter(Expr::col((ReleaseEntity, ReleaseColumn::Id)).in_subquery(last_release))
+        .filter(NamespaceColumn::DeletedAt.eq(0_u64));
+    if let Some(app_id) = app_id {
+        stmt = stmt.filter(NamespaceColumn::AppId.eq(app_id));
+    }
+    if let Some(cluster) = cluster {
+        stmt = stmt.filter(NamespaceColumn::Cluster.eq(cluster));
+    }
+    if let Some(resource) = resource {
+        stmt = stmt.filter(resource);
+    }
+    let (release_sql, values) = stmt.into_query().build(MysqlQueryBuilder);
+    let mut values = values.0;
+    let mut sql = format!(
+        "SELECT CAST(0 AS UNSIGNED) AS `id`, `r`.`namespace_id`, `r`.`app_id`, `r`.`cluster`, `r`.`namespace`, `jt`.`key`, `jt`.`value`, `jt`.`category` \
+        FROM ({}) AS `r`, JSON_TABLE(`r`.`configurations`, '$[*]' COLUMNS(`key` VARCHAR(255) PATH '$.key', `value` LONGTEXT PATH '$.value', `category` VARCHAR(20) PATH '$.category')) AS `jt` \
+        WHERE (LOWER(`jt`.`key`) LIKE ? ESCAPE '!' OR LOWER(`jt`.`value`) LIKE ? ESCAPE '!')",
+        release_sql
+    );
+    let pattern = like_contains(&keyword);
+    values.push(pattern.clone().into());
+    values.push(pattern.into());
+    if let Some(category) = category {
+        sql.push_str(" AND `jt`.`category` = ?");
+        values.push(category.to_value().into());
+    }
+    sql.push_str(&format!(
+        " ORDER BY `r`.`namespace_id`, `jt`.`key` LIMIT {} OFFSET {}",
+        limit, offset
+    ));
+    ItemSearch::find_by_statement(Statement::from_sql_and_values(
+        DbBackend::MySql,
+        &sql,
+        values,
+    ))
+    .all(slaver())
+    .await
+}
+
+// 最近第 keep 次发布的ID, 发布数量不足时返回 None
+pub async fn get_retention_boundary(namespace_id: u64, keep: u64) -> Result<Option<u64>, DbErr> {
+    let id = ReleaseEntity::find()
+        .select_only()
+        .column(ReleaseColumn::Id)
+        .filter(ReleaseColumn::NamespaceId.eq(namespace_id))
+        .filter(ReleaseColumn::DeletedAt.eq(0_u64))
+        .order_by_desc(ReleaseColumn::Id)
+        .offset(keep.max(1) - 1)
+        .into_model::<ID>()
+        .one(slaver())
+        .await?;
+    Ok(id.map(|x| x.id))
+}
+
+// 软删除 boundary 之前且早于 before 的发布及其发布记录, protected 中的发布不删除
+pub async fn prune(
+    namespace_id: u64,
+    boundary: u64,
+    before: Option<DateTimeWithTimeZone>,
+    protected: Vec<u64>,
+    now: u64,
+    limit: u64,
+) -> Result<Reclaimed, DbErr> {
+    let transaction = master()
+        .transaction::<_, Reclaimed, DbErr>(|tx| {
+            Box::pin(async move {
+                // 当前生效的发布 不论策略如何均保留
+                let current = ReleaseEntity::find()
                     .select_only()
                     .column(ReleaseColumn::Id)
                     .filter(ReleaseColumn::NamespaceId.eq(namespace_id))
@@ -35,93 +410,148 @@ pub async fn publication_item(
                     .into_model::<ID>()
                     .one(tx)
                     .await?
-                    .and_then(|x| Some(x.id))
+                    .map(|x| x.id)
                     .unwrap_or_default();
-                if r_id != id {
-                    // 已被发布过
-                    return Ok(false);
+                let mut stmt = ReleaseEntity::find()
+                    .select_only()
+                    .column(ReleaseColumn::Id)
+                    .column_as(
+                        Expr::cust("CAST(LENGTH(`configurations`) AS UNSIGNED)"),
+                        "size",
+                    )
+                    .filter(ReleaseColumn::NamespaceId.eq(namespace_id))
+                    .filter(ReleaseColumn::Id.lt(boundary.min(current)))
+                    .filter(ReleaseColumn::DeletedAt.eq(0_u64));
+                if let Some(before) = before {
+                    stmt = stmt.filter(ReleaseColumn::CreatedAt.lt(before));
                 }
-
-                let release = ReleaseActive {
-                    namespace_id: Set(namespace_id),
-                    name: Set(name),
-                    configurations: Set(config_data),
-                    remark: Set(remark),
-                    publish_user_id: Set(user_id),
-                    is_abandoned: Set(Effective::Valid),
-                    ..Default::default()
-                };
-                let id = ReleaseEntity::insert(release).exec(tx).await?;
-                // 增加更改记录
-                let history = ReleaseHistoryActive {
-                    namespace_id: Set(namespace_id),
-                    change: Set(change_data),
-                    release_id: Set(id.last_insert_id),
-                    ..Default::default()
-                };
-                ReleaseHistoryEntity::insert(history).exec(tx).await?;
-                Ok(true)
+                if !protected.is_empty() {
+                    stmt = stmt.filter(ReleaseColumn::Id.is_not_in(protected));
+                }
+                let releases = stmt
+                    .order_by_asc(ReleaseColumn::Id)
+                    .limit(limit)
+                    .into_model::<ReleaseSize>()
+                    .all(tx)
+                    .await?;
+                if releases.is_empty() {
+                    return Ok(Reclaimed::default());
+                }
+                let ids: Vec<u64> = releases.iter().map(|r| r.id).collect();
+                let histories = ReleaseHistoryEntity::find()
+                    .select_only()
+                    .column(ReleaseHistoryColumn::Id)
+                    .column_as(Expr::cust("CAST(LENGTH(`change`) AS UNSIGNED)"), "size")
+                    .filter(ReleaseHistoryColumn::ReleaseId.is_in(ids.clone()))
+                    .filter(ReleaseHistoryColumn::DeletedAt.eq(0_u64))
+                    .into_model::<ReleaseSize>()
+                    .all(tx)
+                    .await?;
+                ReleaseEntity::update_many()
+                    .col_expr(ReleaseColumn::DeletedAt, Expr::value(now))
+                    .filter(ReleaseColumn::Id.is_in(ids.clone()))
+                    .exec(tx)
+                    .await?;
+                ReleaseHistoryEntity::update_many()
+                    .col_expr(ReleaseHistoryColumn::DeletedAt, Expr::value(now))
+                    .filter(ReleaseHistoryColumn::ReleaseId.is_in(ids))
+                    .filter(ReleaseHistoryColumn::DeletedAt.eq(0_u64))
+                    .exec(tx)
+                    .await?;
+                Ok(reclaimed(&releases, &histories))
             })
         })
         .await;
-    if let Err(e) = transaction {
-        match e {
-            TransactionError::Connection(err) => {
-                return Err(err);
-            }
-            TransactionError::Transaction(err) => {
-                return Err(DbErr::Exec(err.to_string()));
-            }
-        }
+    match transaction {
+        Ok(reclaimed) => Ok(reclaimed),
+        Err(TransactionError::Connection(err)) => Err(err),
+        Err(TransactionError::Transaction(err)) => Err(DbErr::Exec(err.to_string())),
     }
-    Ok(transaction.unwrap())
 }
 
-pub async fn rollback_item(id: u64, remark: String) -> Result<(), DbErr> {
-    let history = ReleaseHistoryEntity::find_by_id(id).one(master()).await?;
-    if history.is_none() {
-        return Err(DbErr::RecordNotFound(
-            "Not found release history".to_owned(),
-        ));
+// 物理删除 deleted_before 之前软删除的发布及发布记录, 已删除 namespace 的数据在恢复期限后随 namespace 清理
+pub async fn purge(deleted_before: u64, limit: u64) -> Result<Reclaimed, DbErr> {
+    let namespace = Query::select()
+        .column(NamespaceColumn::Id)
+        .from(NamespaceEntity)
+        .and_where(Expr::col(NamespaceColumn::DeletedAt).eq(0_u64))
+        .to_owned();
+    let releases = ReleaseEntity::find()
+        .select_only()
+        .column(ReleaseColumn::Id)
+        .column_as(
+            Expr::cust("CAST(LENGTH(`configurations`) AS UNSIGNED)"),
+            "size",
+        )
+        .filter(ReleaseColumn::DeletedAt.gt(0_u64))
+        .filter(ReleaseColumn::DeletedAt.lt(deleted_before))
+        .filter(Expr::col(ReleaseColumn::NamespaceId).in_subquery(namespace))
+        .order_by_asc(ReleaseColumn::Id)
+        .limit(limit)
+        .into_model::<ReleaseSize>()
+        .all(master())
+        .await?;
+    if releases.is_empty() {
+        return Ok(Reclaimed::default());
     }
-    let history = history.unwrap();
-    let release = ReleaseEntity::find_by_id(history.release_id)
-        .one(master())
+    let ids: Vec<u64> = releases.iter().map(|r| r.id).collect();
+    let histories = ReleaseHistoryEntity::find()
+        .select_only()
+        .column(ReleaseHistoryColumn::Id)
+        .column_as(Expr::cust("CAST(LENGTH(`change`) AS UNSIGNED)"), "size")
+        .filter(ReleaseHistoryColumn::ReleaseId.is_in(ids.clone()))
+        .filter(ReleaseHistoryColumn::DeletedAt.gt(0_u64))
+        .into_model::<ReleaseSize>()
+        .all(master())
         .await?;
-    if release.is_none() {
-        return Err(DbErr::RecordNotFound(
-            "Not found release history".to_owned(),
-        ));
+    let history_ids: Vec<u64> = histories.iter().map(|h| h.id).collect();
+    let transaction = master()
+        .transaction::<_, (), DbErr>(|tx| {
+            Box::pin(async move {
+                ReleaseHistoryEntity::delete_many()
+                    .filter(ReleaseHistoryColumn::Id.is_in(history_ids))
+                    .exec(tx)
+                    .await?;
+                ReleaseEntity::delete_many()
+                    .filter(ReleaseColumn::Id.is_in(ids))
+                    .exec(tx)
+                    .await?;
+                Ok(())
+            })
+        })
+        .await;
+    match transaction {
+        Ok(()) => Ok(reclaimed(&releases, &histories)),
+        Err(TransactionError::Connection(err)) => Err(err),
+        Err(TransactionError::Transaction(err)) => Err(DbErr::Exec(err.to_string())),
     }
-    let release = release.unwrap();
-    let mut release: ReleaseActive = release.into();
-    release.deleted_at = NotSet;
-    release.updated_at = NotSet;
-    release.created_at = NotSet;
-    release.id = NotSet;
-    release.is_abandoned = NotSet;
-    release.remark = Set(remark);
-
-    let r = ReleaseEntity::insert(release).exec(master()).await?;
-    let mut history: ReleaseHistoryActive = history.into();
-    history.created_at = NotSet;
-    history.updated_at = NotSet;
-    history.deleted_at = NotSet;
-    history.id = NotSet;
-    history.release_id = Set(r.last_insert_id);
-    ReleaseHistoryEntity::insert(history).exec(master()).await?;
-    Ok(())
 }
 
-pub async fn get_namespace_config(namespace_id: u64) -> Result<Option<ReleaseConfig>, DbErr> {
+fn reclaimed(releases: &[ReleaseSize], histories: &[ReleaseSize]) -> Reclaimed {
+    Reclaimed {
+        releases: releases.len() as u64,
+        histories: histories.len() as u64,
+        bytes: releases
+            .iter()
+            .chain(histories.iter())
+            .map(|r| r.size)
+            .sum(),
+    }
+}
+
+// 按ID顺序分批获取发布的配置及校验和
+pub async fn get_checksum_after(after_id: u64, limit: u64) -> Result<Vec<ReleaseChecksum>, DbErr> {
     ReleaseEntity::find()
         .select_only()
         .column(ReleaseColumn::Id)
+        .column(ReleaseColumn::NamespaceId)
         .column(ReleaseColumn::Configurations)
-        .filter(ReleaseColumn::NamespaceId.eq(namespace_id))
+        .column(ReleaseColumn::Checksum)
+        .filter(ReleaseColumn::Id.gt(after_id))
         .filter(ReleaseColumn::DeletedAt.eq(0_u64))
-        .order_by_desc(ReleaseColumn::Id)
-        .into_model::<ReleaseConfig>()
-        .one(slaver())
+        .order_by_asc(ReleaseColumn::Id)
+        .limit(limit)
+        .into_model::<ReleaseChecksum>()
+        .all(slaver())
         .await
 }
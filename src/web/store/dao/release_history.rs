@@ -1,37 +1,65 @@
 use super::{master, slaver};
 
-use entity::orm::{ColumnTrait, DbErr, EntityTrait, QueryFilter, QuerySelect};
-use entity::release_history::{HistoryItem, HistoryNamespaceID};
-use entity::{ReleaseHistoryActive, ReleaseHistoryColumn, ReleaseHistoryEntity};
+use entity::orm::prelude::DateTimeWithTimeZone;
+use entity::orm::{
+    ColumnTrait, DbErr, EntityTrait, JoinType, QueryFilter, QueryOrder, QuerySelect, RelationTrait,
+};
+use entity::release::Relation as ReleaseRelation;
+use entity::release_history::HistoryItem;
+use entity::{
+    ReleaseColumn, ReleaseEntity, ReleaseHistoryActive, ReleaseHistoryColumn, ReleaseHistoryEntity,
+    ReleaseHistoryModel, UsersColumn,
+};
 
 pub async fn add(active: ReleaseHistoryActive) -> Result<u64, DbErr> {
     let r = ReleaseHistoryEntity::insert(active).exec(master()).await?;
     Ok(r.last_insert_id)
 }
 
-pub async fn get_namespace_id(id: u64) -> Result<Option<u64>, DbErr> {
-    let entity = ReleaseHistoryEntity::find_by_id(id)
-        .select_only()
-        .column(ReleaseHistoryColumn::NamespaceId)
-        .into_model::<HistoryNamespaceID>()
+pub async fn find_by_id(id: u64) -> Result<Option<ReleaseHistoryModel>, DbErr> {
+    ReleaseHistoryEntity::find_by_id(id)
+        .filter(ReleaseHistoryColumn::DeletedAt.eq(0_u64))
         .one(master())
-        .await?;
-    Ok(entity.and_then(|x| Some(x.namespace_id)))
+        .await
 }
+
+// 获取 namespace 的发布记录, 可按发布时间范围及发布人过滤
 pub async fn get_namespace_history(
     namespace_id: u64,
+    start_at: Option<DateTimeWithTimeZone>,
+    end_at: Option<DateTimeWithTimeZone>,
+    user_id: Option<u32>,
     offset: u64,
     limit: u64,
 ) -> Result<Vec<HistoryItem>, DbErr> {
-    ReleaseHistoryEntity::find()
-        .offset(offset)
-        .limit(limit)
+    let mut stmt = ReleaseHistoryEntity::find()
         .select_only()
         .column(ReleaseHistoryColumn::Id)
+        .column(ReleaseHistoryColumn::NamespaceId)
         .column(ReleaseHistoryColumn::ReleaseId)
+        .column(ReleaseHistoryColumn::Operation)
         .column(ReleaseHistoryColumn::Change)
+        .column(ReleaseColumn::Name)
+        .column(ReleaseColumn::Remark)
+        .column(ReleaseColumn::PublishUserId)
+        .column(UsersColumn::Nickname)
+        .column(ReleaseHistoryColumn::CreatedAt)
+        .left_join(ReleaseEntity)
+        .join(JoinType::LeftJoin, ReleaseRelation::Users.def())
         .filter(ReleaseHistoryColumn::NamespaceId.eq(namespace_id))
-        .filter(ReleaseHistoryColumn::DeletedAt.eq(0_u64))
+        .filter(ReleaseHistoryColumn::DeletedAt.eq(0_u64));
+    if let Some(start_at) = start_at {
+        stmt = stmt.filter(ReleaseHistoryColumn::CreatedAt.gte(start_at));
+    }
+    if let Some(end_at) = end_at {
+        stmt = stmt.filter(ReleaseHistoryColumn::CreatedAt.lt(end_at));
+    }
+    if let Some(user_id) = user_id {
+        stmt = stmt.filter(ReleaseColumn::PublishUserId.eq(user_id));
+    }
+    stmt.order_by_desc(ReleaseHistoryColumn::Id)
+        .offset(offset)
+        .limit(limit)
         .into_model::<HistoryItem>()
         .all(slaver())
         .await
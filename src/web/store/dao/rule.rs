@@ -1,11 +1,21 @@
-use super::slaver;
+use super::{master, slaver};
 
-use entity::orm::{ColumnTrait, DbErr, EntityTrait, QueryFilter, QuerySelect};
+use entity::orm::sea_query::{Expr, SimpleExpr};
+use entity::orm::{
+    ColumnTrait, Condition, DbErr, EntityTrait, QueryFilter, QuerySelect, Set, TransactionError,
+    TransactionTrait,
+};
 use entity::rule::Verb;
 use entity::user_role::{RoleResource, UserRoleID};
-use entity::{RoleRuleColumn, RoleRuleEntity, RuleColumn, RuleEntity};
+use entity::{
+    NamespaceColumn, RoleActive, RoleColumn, RoleEntity, RoleRuleActive, RoleRuleColumn,
+    RoleRuleEntity, RuleActive, RuleColumn, RuleEntity, UserRoleActive, UserRoleColumn,
+    UserRoleEntity,
+};
 
 const RESOURCE_PAT: &str = "/";
+// 环境资源前缀 app_id/@prod 表示应用下 prod 环境的所有集群, @prod 表示所有应用 prod 环境的集群
+const ENV_PAT: &str = "@";
 
 #[inline]
 pub fn combination_resource(resource: Vec<&str>) -> Vec<String> {
@@ -23,6 +33,38 @@ pub fn combination_resource(resource: Vec<&str>) -> Vec<String> {
     resources
 }
 
+#[inline]
+pub fn env_resource(app_id: &str, env: &str) -> String {
+    format!("{}{}{}{}", app_id, RESOURCE_PAT, ENV_PAT, env)
+}
+
+#[inline]
+pub fn global_env_resource(env: &str) -> String {
+    format!("{}{}", ENV_PAT, env)
+}
+
+// 集群级资源或全局资源为环境时返回环境名称
+#[inline]
+pub fn parse_env(resource: &str) -> Option<&str> {
+    resource.strip_prefix(ENV_PAT)
+}
+
+// 资源以 prefix 开头, 按字符精确比较 避免 LIKE 将 _ 视为通配符
+pub fn prefix_condition(prefix: String) -> SimpleExpr {
+    Expr::cust_with_values(
+        "LEFT(`rule`.`resource`, CHAR_LENGTH(?)) = ?",
+        vec![prefix.clone(), prefix],
+    )
+}
+
+// 所属集群在指定环境的 namespace
+pub fn env_condition(env: &str) -> SimpleExpr {
+    Expr::cust_with_values(
+        "`namespace`.`cluster` IN (SELECT `name` FROM `cluster` WHERE `cluster`.`app_id` = `namespace`.`app_id` AND `env` = ? AND `deleted_at` = 0)",
+        vec![env.to_owned()],
+    )
+}
+
 #[inline]
 pub fn parse_resource_kind_len(resource: &str) -> usize {
     resource.split(RESOURCE_PAT).count()
@@ -38,6 +80,49 @@ pub fn parse_resource_kind(resource: String) -> Vec<String> {
         .collect()
 }
 
+// 将授权资源转换为 namespace 的查询条件
+pub fn resource_condition(resources: Vec<String>) -> Condition {
+    let mut cond = Condition::any();
+    for resource in resources.into_iter() {
+        let rk = parse_resource_kind(resource);
+        let mut c = Condition::all();
+        for (idx, r) in rk.into_iter().enumerate() {
+            c = match idx {
+                0 => match parse_env(&r) {
+                    Some(env) => c.add(env_condition(env)),
+                    None => c.add(NamespaceColumn::AppId.eq(r)),
+                },
+                1 => match parse_env(&r) {
+                    Some(env) => c.add(env_condition(env)),
+                    None => c.add(NamespaceColumn::Cluster.eq(r)),
+                },
+                _ => c.add(NamespaceColumn::Namespace.eq(r)),
+            };
+        }
+        cond = cond.add(c);
+    }
+    cond
+}
+
+// 获取角色拥有的授权资源
+pub async fn get_roles_resource(
+    verb: Verb,
+    role_ids: Vec<u32>,
+) -> Result<Vec<RoleResource>, DbErr> {
+    RuleEntity::find()
+        .select_only()
+        .column(RoleRuleColumn::RoleId)
+        .column(RuleColumn::Resource)
+        .left_join(RoleRuleEntity)
+        .filter(RoleRuleColumn::RoleId.is_in(role_ids))
+        .filter(RuleColumn::Verb.eq(verb))
+        .filter(RuleColumn::DeletedAt.eq(0_u64))
+        .filter(RoleRuleColumn::DeletedAt.eq(0_u64))
+        .into_model::<RoleResource>()
+        .all(slaver())
+        .await
+}
+
 pub async fn get_resource_prefix_role(
     verb: Verb,
     mut app_id: String,
@@ -64,6 +149,22 @@ pub async fn get_resource_prefix_role(
         .await
 }
 
+// 获取全局环境资源拥有权限的角色
+pub async fn get_global_env_role(verb: Verb) -> Result<Vec<RoleResource>, DbErr> {
+    RuleEntity::find()
+        .select_only()
+        .column(RoleRuleColumn::RoleId)
+        .column(RuleColumn::Resource)
+        .left_join(RoleRuleEntity)
+        .filter(RuleColumn::Resource.starts_with(ENV_PAT))
+        .filter(RuleColumn::Verb.eq(verb))
+        .filter(RuleColumn::DeletedAt.eq(0_u64))
+        .filter(RoleRuleColumn::DeletedAt.eq(0_u64))
+        .into_model::<RoleResource>()
+        .all(slaver())
+        .await
+}
+
 pub async fn get_resource_role(verb: Verb, resource: Vec<String>) -> Result<Vec<u32>, DbErr> {
     let role_ids = RuleEntity::find()
         .select_only()
@@ -84,3 +185,82 @@ pub async fn get_resource_role(verb: Verb, resource: Vec<String>) -> Result<Vec<
 
     Ok(role_ids)
 }
+
+// 授予用户资源的权限, 同一资源的授权归属于同一角色, 已有的权限及绑定不重复创建
+pub async fn grant(resource: String, verbs: Vec<Verb>, user_id: u32) -> Result<(), DbErr> {
+    let transaction = master()
+        .transaction::<_, (), DbErr>(|tx| {
+            Box::pin(async move {
+                let name = format!("Env/{}", resource);
+                let role = RoleEntity::find()
+                    .filter(RoleColumn::Name.eq(name.clone()))
+                    .filter(RoleColumn::DeletedAt.eq(0_u64))
+                    .one(tx)
+                    .await?;
+                let role_id = match role {
+                    Some(role) => role.id,
+                    None => {
+                        let role = RoleActive {
+                            name: Set(name),
+                            ..Default::default()
+                        };
+                        RoleEntity::insert(role).exec(tx).await?.last_insert_id
+                    }
+                };
+                for verb in verbs.into_iter() {
+                    let rule = RuleEntity::find()
+                        .filter(RuleColumn::Resource.eq(resource.clone()))
+                        .filter(RuleColumn::Verb.eq(verb.clone()))
+                        .filter(RuleColumn::DeletedAt.eq(0_u64))
+                        .one(tx)
+                        .await?;
+                    let rule_id = match rule {
+                        Some(rule) => rule.id,
+                        None => {
+                            let rule = RuleActive {
+                                verb: Set(verb),
+                                resource: Set(resource.clone()),
+                                ..Default::default()
+                            };
+                            RuleEntity::insert(rule).exec(tx).await?.last_insert_id
+                        }
+                    };
+                    let bind = RoleRuleEntity::find()
+                        .filter(RoleRuleColumn::RoleId.eq(role_id))
+                        .filter(RoleRuleColumn::RuleId.eq(rule_id))
+                        .filter(RoleRuleColumn::DeletedAt.eq(0_u64))
+                        .one(tx)
+                        .await?;
+                    if bind.is_none() {
+                        let bind = RoleRuleActive {
+                            role_id: Set(role_id),
+                            rule_id: Set(rule_id),
+                            ..Default::default()
+                        };
+                        RoleRuleEntity::insert(bind).exec(tx).await?;
+                    }
+                }
+                let user_bind = UserRoleEntity::find()
+                    .filter(UserRoleColumn::UserId.eq(user_id))
+                    .filter(UserRoleColumn::RoleId.eq(role_id))
+                    .filter(UserRoleColumn::DeletedAt.eq(0_u64))
+                    .one(tx)
+                    .await?;
+                if user_bind.is_none() {
+                    let user_bind = UserRoleActive {
+                        user_id: Set(user_id),
+                        role_id: Set(role_id),
+                        ..Default::default()
+                    };
+                    UserRoleEntity::insert(user_bind).exec(tx).await?;
+                }
+                Ok(())
+            })
+        })
+        .await;
+    match transaction {
+        Ok(()) => Ok(()),
+        Err(TransactionError::Connection(err)) => Err(err),
+        Err(TransactionError::Transaction(err)) => Err(DbErr::Exec(err.to_string())),
+    }
+}
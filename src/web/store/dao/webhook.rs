@@ -0,0 +1,69 @@
+use super::{master, slaver};
+
+use chrono::Local;
+use entity::orm::sea_query::Expr;
+use entity::orm::{ColumnTrait, DbErr, EntityTrait, QueryFilter, QueryOrder, QuerySelect};
+use entity::{WebhookActive, WebhookColumn, WebhookEntity, WebhookModel};
+
+pub async fn add(active: WebhookActive) -> Result<u64, DbErr> {
+    let r = WebhookEntity::insert(active).exec(master()).await?;
+    Ok(r.last_insert_id)
+}
+
+pub async fn find_by_id(id: u64) -> Result<Option<WebhookModel>, DbErr> {
+    WebhookEntity::find_by_id(id)
+        .filter(WebhookColumn::DeletedAt.eq(0_u64))
+        .one(master())
+        .await
+}
+
+pub async fn update_by_id(active: WebhookActive, id: u64) -> Result<(), DbErr> {
+    WebhookEntity::update_many()
+        .set(active)
+        .filter(WebhookColumn::Id.eq(id))
+        .filter(WebhookColumn::DeletedAt.eq(0_u64))
+        .exec(master())
+        .await?;
+    Ok(())
+}
+
+pub async fn delete(id: u64) -> Result<bool, DbErr> {
+    let r = WebhookEntity::update_many()
+        .col_expr(
+            WebhookColumn::DeletedAt,
+            Expr::value(Local::now().timestamp() as u64),
+        )
+        .filter(WebhookColumn::Id.eq(id))
+        .filter(WebhookColumn::DeletedAt.eq(0_u64))
+        .exec(master())
+        .await?;
+    Ok(r.rows_affected != 0)
+}
+
+pub async fn get_app_webhook(
+    app_id: String,
+    offset: u64,
+    limit: u64,
+) -> Result<Vec<WebhookModel>, DbErr> {
+    WebhookEntity::find()
+        .filter(WebhookColumn::AppId.eq(app_id))
+        .filter(WebhookColumn::DeletedAt.eq(0_u64))
+        .order_by_desc(WebhookColumn::Id)
+        .offset(offset)
+        .limit(limit)
+        .all(slaver())
+        .await
+}
+
+// 获取订阅了 namespace 的 webhook, 包括订阅整个 app 的
+pub async fn find_subscriber(
+    app_id: String,
+    namespace_id: u64,
+) -> Result<Vec<WebhookModel>, DbErr> {
+    WebhookEntity::find()
+        .filter(WebhookColumn::AppId.eq(app_id))
+        .filter(WebhookColumn::NamespaceId.is_in(vec![0, namespace_id]))
+        .filter(WebhookColumn::DeletedAt.eq(0_u64))
+        .all(slaver())
+        .await
+}
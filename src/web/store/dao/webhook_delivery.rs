@@ -0,0 +1,66 @@
+use super::{master, slaver};
+
+use entity::orm::prelude::DateTimeWithTimeZone;
+use entity::orm::sea_query::Expr;
+use entity::orm::{ColumnTrait, DbErr, EntityTrait, QueryFilter, QueryOrder, QuerySelect};
+use entity::webhook_delivery::DeliveryStatus;
+use entity::{
+    WebhookDeliveryActive, WebhookDeliveryColumn, WebhookDeliveryEntity, WebhookDeliveryModel,
+};
+
+pub async fn add(active: WebhookDeliveryActive) -> Result<u64, DbErr> {
+    let r = WebhookDeliveryEntity::insert(active).exec(master()).await?;
+    Ok(r.last_insert_id)
+}
+
+pub async fn update_by_id(active: WebhookDeliveryActive, id: u64) -> Result<(), DbErr> {
+    WebhookDeliveryEntity::update_many()
+        .set(active)
+        .filter(WebhookDeliveryColumn::Id.eq(id))
+        .exec(master())
+        .await?;
+    Ok(())
+}
+
+pub async fn get_webhook_delivery(
+    webhook_id: u64,
+    offset: u64,
+    limit: u64,
+) -> Result<Vec<WebhookDeliveryModel>, DbErr> {
+    WebhookDeliveryEntity::find()
+        .filter(WebhookDeliveryColumn::WebhookId.eq(webhook_id))
+        .order_by_desc(WebhookDeliveryColumn::Id)
+        .offset(offset)
+        .limit(limit)
+        .all(slaver())
+        .await
+}
+
+// 获取 updated_before 之前未再更新的投递中记录
+pub async fn get_stale(
+    updated_before: DateTimeWithTimeZone,
+    limit: u64,
+) -> Result<Vec<WebhookDeliveryModel>, DbErr> {
+    WebhookDeliveryEntity::find()
+        .filter(WebhookDeliveryColumn::Status.eq(DeliveryStatus::Pending))
+        .filter(WebhookDeliveryColumn::UpdatedAt.lt(updated_before))
+        .order_by_asc(WebhookDeliveryColumn::Id)
+        .limit(limit)
+        .all(master())
+        .await
+}
+
+// 以更新时间抢占投递 记录已被其他实例更新时返回 false
+pub async fn claim(delivery: &WebhookDeliveryModel) -> Result<bool, DbErr> {
+    let r = WebhookDeliveryEntity::update_many()
+        .col_expr(
+            WebhookDeliveryColumn::UpdatedAt,
+            Expr::cust("CURRENT_TIMESTAMP"),
+        )
+        .filter(WebhookDeliveryColumn::Id.eq(delivery.id))
+        .filter(WebhookDeliveryColumn::Status.eq(DeliveryStatus::Pending))
+        .filter(WebhookDeliveryColumn::UpdatedAt.eq(delivery.updated_at))
+        .exec(master())
+        .await?;
+    Ok(r.rows_affected != 0)
+}